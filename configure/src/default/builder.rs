@@ -0,0 +1,174 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::{Casing, DefaultSource, MissingVars, UnknownVars};
+
+/// A builder collecting the options of a `DefaultSource`.
+///
+/// Unlike the methods of `DefaultSource`, the builder can also choose which
+/// `Cargo.toml` the source reads from, which is only read when the source
+/// is built:
+///
+/// ```rust,ignore
+/// CONFIGURATION.set(DefaultSource::builder()
+///     .manifest("/etc/myapp/Cargo.toml")
+///     .casing(Casing::Kebab)
+///     .unknown_vars(UnknownVars::Deny)
+///     .build());
+/// ```
+///
+/// A builder with no options builds the same source as
+/// `DefaultSource::init`.
+pub struct DefaultSourceBuilder {
+    source: DefaultSource,
+    manifest: Manifest,
+}
+
+/// Which `Cargo.toml` a source reads from.
+enum Manifest {
+    /// The `Cargo.toml` in `CARGO_MANIFEST_DIR`.
+    Cargo,
+    /// The `Cargo.toml` at this path.
+    Path(PathBuf),
+    /// TOML embedded in the program.
+    Embedded(toml::Value),
+    /// No `Cargo.toml` at all.
+    None,
+}
+
+macro_rules! forward {
+    ($($(#[$attr:meta])* $f:ident($($arg:ident: $t:ty),*);)*) => {$(
+        $(#[$attr])*
+        pub fn $f(mut self, $($arg: $t),*) -> DefaultSourceBuilder {
+            self.source = self.source.$f($($arg),*);
+            self
+        }
+    )*}
+}
+
+impl DefaultSourceBuilder {
+    pub(super) fn new() -> DefaultSourceBuilder {
+        DefaultSourceBuilder {
+            source: DefaultSource::with_toml(None),
+            manifest: Manifest::Cargo,
+        }
+    }
+
+    /// Read the `[package.metadata]` of the `Cargo.toml` at this path,
+    /// instead of the one in `CARGO_MANIFEST_DIR`.
+    pub fn manifest<P: Into<PathBuf>>(mut self, path: P) -> DefaultSourceBuilder {
+        self.manifest = Manifest::Path(path.into());
+        self
+    }
+
+    /// Read this TOML, embedded in the program, instead of any `Cargo.toml`.
+    /// See `DefaultSource::with_embedded_toml`.
+    ///
+    /// # Panics
+    ///
+    /// If the TOML is not valid.
+    pub fn embedded_toml(mut self, toml: &str) -> DefaultSourceBuilder {
+        self.manifest = Manifest::Embedded(DefaultSource::parse_embedded(toml));
+        self
+    }
+
+    /// Read only from the environment, ignoring any `Cargo.toml`.
+    pub fn without_toml(mut self) -> DefaultSourceBuilder {
+        self.manifest = Manifest::None;
+        self
+    }
+
+    forward! {
+        /// See `DefaultSource::with_args`.
+        with_args();
+        /// See `DefaultSource::fallback_unprefixed`.
+        fallback_unprefixed();
+        /// See `DefaultSource::numbered_sequences`.
+        numbered_sequences();
+        /// See `DefaultSource::finite_floats`.
+        finite_floats();
+        /// See `DefaultSource::keep_list_whitespace`.
+        keep_list_whitespace();
+        /// See `DefaultSource::enum_indexes`.
+        enum_indexes();
+        /// See `DefaultSource::file_references`.
+        file_references();
+        /// See `DefaultSource::interpolate`.
+        interpolate(missing: MissingVars);
+        /// See `DefaultSource::casing`.
+        casing(casing: Casing);
+        /// See `DefaultSource::strict_layers`.
+        strict_layers();
+        /// See `DefaultSource::frozen`.
+        frozen();
+        /// See `DefaultSource::unknown_vars`.
+        unknown_vars(policy: UnknownVars);
+    }
+
+    /// See `DefaultSource::with_args_from`.
+    pub fn with_args_from<I>(mut self, args: I) -> DefaultSourceBuilder
+        where I: IntoIterator,
+              I::Item: Into<String>,
+    {
+        self.source = self.source.with_args_from(args);
+        self
+    }
+
+    /// See `DefaultSource::transform_names`.
+    pub fn transform_names<F>(mut self, transform: F) -> DefaultSourceBuilder
+        where F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        self.source = self.source.transform_names(transform);
+        self
+    }
+
+    /// Build the source, reading its `Cargo.toml`.
+    pub fn build(self) -> DefaultSource {
+        let toml = match self.manifest {
+            Manifest::Cargo             => DefaultSource::toml(),
+            Manifest::Path(path)        => DefaultSource::read_manifest(path),
+            Manifest::Embedded(toml)    => Some(toml),
+            Manifest::None              => None,
+        };
+        DefaultSource { toml: toml.map(Arc::new), ..self.source }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use serde::Deserialize;
+
+    use source::PrepareSource;
+    use super::*;
+
+    #[derive(Deserialize, Debug, Default, PartialEq)]
+    #[serde(default)]
+    struct Config {
+        threads: u32,
+        host: String,
+    }
+
+    #[test]
+    fn test_builder() {
+        let path = env::temp_dir().join(format!("configure-builder-{}.toml", ::std::process::id()));
+        fs::write(&path, "[package.metadata.built]\nthreads = 2\nhost = \"example.com\"\n").unwrap();
+        env::set_var("built-threads", "8");
+
+        let source = DefaultSource::builder().manifest(&path).casing(Casing::Kebab).build();
+        let config = Config::deserialize(source.prepare("built")).unwrap();
+        assert_eq!(config, Config { threads: 8, host: String::from("example.com") });
+
+        let source = DefaultSource::builder().without_toml().casing(Casing::Kebab).build();
+        let config = Config::deserialize(source.prepare("built")).unwrap();
+        assert_eq!(config, Config { threads: 8, host: String::new() });
+
+        let source = DefaultSource::builder().embedded_toml("[built]\nhost = \"localhost\"\n").casing(Casing::Kebab).build();
+        let config = Config::deserialize(source.prepare("built")).unwrap();
+        assert_eq!(config, Config { threads: 8, host: String::from("localhost") });
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,9 +1,75 @@
 use std::borrow::Cow;
+use std::fs;
 
 use serde::de::*; use serde::de::{Error as ErrorTrait};
 use erased_serde::Error;
 
-pub struct EnvDeserializer<'a>(pub Cow<'a, str>);
+use encoding::hex;
+
+pub struct EnvDeserializer<'a>(pub Cow<'a, str>, pub ParseOptions);
+
+/// Options controlling how values are parsed from strings.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ParseOptions {
+    /// Reject floats which are infinite or NaN.
+    pub finite_floats: bool,
+    /// Keep the whitespace around the elements of comma separated lists,
+    /// instead of trimming it.
+    pub keep_whitespace: bool,
+    /// Accept the index of a variant of an enum, as well as its name.
+    pub enum_indexes: bool,
+    /// Read strings and byte strings of the form `@path` from the file at
+    /// `path`.
+    pub file_references: bool,
+}
+
+/// A string or byte string value, which is either the value itself or the
+/// contents of the file it refers to.
+enum Contents<'a> {
+    Value(Cow<'a, str>),
+    File(String, Vec<u8>),
+}
+
+impl<'a> EnvDeserializer<'a> {
+    /// The contents of this value, reading the file it refers to if file
+    /// references are enabled and it is `@path`. A value starting with `@@`
+    /// is the value itself, with the first `@` removed.
+    fn contents(self) -> Result<Contents<'a>, Error> {
+        if !self.1.file_references || !self.0.starts_with('@') {
+            return Ok(Contents::Value(self.0))
+        }
+        if self.0.starts_with("@@") {
+            return Ok(Contents::Value(match self.0 {
+                Cow::Borrowed(s)    => Cow::Borrowed(&s[1..]),
+                Cow::Owned(s)       => Cow::Owned(s[1..].to_owned()),
+            }))
+        }
+        let path = &self.0[1..];
+        match fs::read(path) {
+            Ok(bytes)   => Ok(Contents::File(path.to_owned(), bytes)),
+            Err(err)    => Err(Error::custom(format!("could not read `{}`: {}", path, err))),
+        }
+    }
+
+    /// The contents of this value as a string. A single trailing newline is
+    /// removed from the contents of a file.
+    fn string(self) -> Result<Cow<'a, str>, Error> {
+        match self.contents()? {
+            Contents::Value(s)          => Ok(s),
+            Contents::File(path, bytes) => {
+                let mut s = String::from_utf8(bytes)
+                    .map_err(|_| Error::custom(format!("`{}` is not UTF-8", path)))?;
+                if s.ends_with('\n') {
+                    s.pop();
+                    if s.ends_with('\r') {
+                        s.pop();
+                    }
+                }
+                Ok(Cow::Owned(s))
+            }
+        }
+    }
+}
 
 impl<'a, 'de> IntoDeserializer<'de, Error> for EnvDeserializer<'a> {
     type Deserializer = Self;
@@ -15,7 +81,8 @@ macro_rules! deserialize_number {
         fn $f<V>(self, visitor: V) -> Result<V::Value, Self::Error>
             where V: Visitor<'de>,
         {
-            let x = self.0.parse::<$t>().map_err(|e| Error::custom(e.to_string()))?;
+            let (digits, radix) = radix(&self.0);
+            let x = <$t>::from_str_radix(&digits, radix).map_err(|e| Error::custom(e.to_string()))?;
             visitor.$v(x)
         }
     )*}
@@ -51,8 +118,22 @@ impl<'a, 'de> Deserializer<'de> for EnvDeserializer<'a> {
         deserialize_u16(u16):   visit_u16;
         deserialize_u32(u32):   visit_u32;
         deserialize_u64(u64):   visit_u64;
-        deserialize_f32(f32):   visit_f32;
-        deserialize_f64(f64):   visit_f64;
+    }
+
+    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>,
+    {
+        let x = self.0.parse::<f32>().map_err(|e| Error::custom(e.to_string()))?;
+        check_finite(&self, x.is_finite())?;
+        visitor.visit_f32(x)
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>,
+    {
+        let x = self.0.parse::<f64>().map_err(|e| Error::custom(e.to_string()))?;
+        check_finite(&self, x.is_finite())?;
+        visitor.visit_f64(x)
     }
 
     fn deserialize_char<V>(self, visitor: V) -> Result<V::Value, Self::Error>
@@ -70,32 +151,31 @@ impl<'a, 'de> Deserializer<'de> for EnvDeserializer<'a> {
     fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
         where V: Visitor<'de>
     {
-        visitor.visit_str(&self.0)
+        visitor.visit_str(&self.string()?)
     }
 
     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
         where V: Visitor<'de>
     {
-        visitor.visit_string(self.0.into_owned())
+        visitor.visit_string(self.string()?.into_owned())
     }
 
     fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Self::Error>
         where V: Visitor<'de>
     {
-        if let Some(bytes) = hex(&self.0[..]) {
-            visitor.visit_bytes(&bytes[..])
-        } else {
-            Err(Error::invalid_value(Unexpected::Str(&self.0), &visitor))
-        }
+        self.deserialize_byte_buf(visitor)
     }
 
+    // The contents of a file are the bytes themselves, rather than hex.
     fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Self::Error>
         where V: Visitor<'de>
     {
-        if let Some(bytes) = hex(&self.0[..]) {
-            visitor.visit_byte_buf(bytes)
-        } else {
-            Err(Error::invalid_value(Unexpected::Str(&self.0), &visitor))
+        match self.contents()? {
+            Contents::File(_, bytes)    => visitor.visit_byte_buf(bytes),
+            Contents::Value(s)          => match hex(&s[..]) {
+                Some(bytes) => visitor.visit_byte_buf(bytes),
+                None        => Err(Error::invalid_value(Unexpected::Str(&s), &visitor)),
+            },
         }
     }
 
@@ -134,31 +214,28 @@ impl<'a, 'de> Deserializer<'de> for EnvDeserializer<'a> {
     fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
         where V: Visitor<'de>
     {
-        let seq = self.0.split(',').map(|s| EnvDeserializer(Cow::Borrowed(s)));
-        visitor.visit_seq(value::SeqDeserializer::new(seq))
+        visitor.visit_seq(value::SeqDeserializer::new(elements(&self.0, self.1)))
     }
 
     fn deserialize_tuple<V>(
         self, 
-        _len: usize, 
+        len: usize, 
         visitor: V,
     ) -> Result<V::Value, Self::Error>
         where V: Visitor<'de>
     {
-        let seq = self.0.split(',').map(|s| EnvDeserializer(Cow::Borrowed(s)));
-        visitor.visit_seq(value::SeqDeserializer::new(seq))
+        visitor.visit_seq(TupleAccess::new(elements(&self.0, self.1).collect(), len, &self.0)?)
     }
 
     fn deserialize_tuple_struct<V>(
         self, 
         _name: &'static str, 
-        _len: usize, 
+        len: usize, 
         visitor: V,
     ) -> Result<V::Value, Self::Error>
         where V: Visitor<'de>
     {
-        let seq = self.0.split(',').map(|s| EnvDeserializer(Cow::Borrowed(s)));
-        visitor.visit_seq(value::SeqDeserializer::new(seq))
+        visitor.visit_seq(TupleAccess::new(elements(&self.0, self.1).collect(), len, &self.0)?)
     }
 
     fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
@@ -188,7 +265,8 @@ impl<'a, 'de> Deserializer<'de> for EnvDeserializer<'a> {
     {
         visitor.visit_enum(EnumAccessor {
             env_var: &self.0,
-            variants: variants,
+            variants,
+            indexes: self.1.enum_indexes,
         })
     }
 
@@ -211,9 +289,127 @@ impl<'a, 'de> Deserializer<'de> for EnvDeserializer<'a> {
     }
 }
 
+/// Deserializes a sequence from several env vars, one element per variable,
+/// without splitting any of them on commas. Types which are not sequences
+/// are deserialized from the first variable alone.
+pub struct EnvSeqDeserializer(pub Vec<String>, pub ParseOptions);
+
+impl EnvSeqDeserializer {
+    fn first(self) -> EnvDeserializer<'static> {
+        EnvDeserializer(Cow::Owned(self.0.into_iter().next().unwrap_or_default()), self.1)
+    }
+
+    fn elements(self) -> value::SeqDeserializer<::std::vec::IntoIter<EnvDeserializer<'static>>, Error> {
+        let options = self.1;
+        let elements: Vec<_> = self.0.into_iter().map(|s| EnvDeserializer(Cow::Owned(s), options)).collect();
+        value::SeqDeserializer::new(elements.into_iter())
+    }
+}
+
+macro_rules! forward_to_first {
+    ($($f:ident)*) => {$(
+        fn $f<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where V: Visitor<'de>,
+        {
+            self.first().$f(visitor)
+        }
+    )*}
+}
+
+impl<'de> Deserializer<'de> for EnvSeqDeserializer {
+    type Error = Error;
+
+    forward_to_first! {
+        deserialize_any deserialize_bool
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_f32 deserialize_f64 deserialize_char
+        deserialize_str deserialize_string deserialize_bytes deserialize_byte_buf
+        deserialize_unit deserialize_map deserialize_identifier deserialize_ignored_any
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>
+    {
+        self.first().deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>
+    {
+        visitor.visit_seq(self.elements())
+    }
+
+    fn deserialize_tuple<V>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>
+    {
+        let list = self.0.join(",");
+        let elements = self.0.iter().map(|s| EnvDeserializer(Cow::Borrowed(&s[..]), self.1)).collect();
+        visitor.visit_seq(TupleAccess::new(elements, len, &list)?)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>
+    {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>
+    {
+        self.first().deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>
+    {
+        self.first().deserialize_enum(name, variants, visitor)
+    }
+}
+
 struct EnumAccessor<'a> {
     env_var: &'a str,
     variants: &'static [&'static str],
+    indexes: bool,
 }
 
 impl<'a, 'de> EnumAccess<'de> for EnumAccessor<'a> {
@@ -226,7 +422,11 @@ impl<'a, 'de> EnumAccess<'de> for EnumAccessor<'a> {
     ) -> Result<(V::Value, Self::Variant), Self::Error>
         where V: DeserializeSeed<'de>
     {
-        if let Some(&variant) = self.variants.iter().find(|&&v| v == self.env_var) {
+        let index = match self.indexes {
+            true    => self.env_var.parse::<usize>().ok().and_then(|idx| self.variants.get(idx)),
+            false   => None,
+        };
+        if let Some(variant) = index.cloned().or_else(|| find_variant(self.variants, self.env_var)) {
             let value = seed.deserialize(variant.into_deserializer())?;
             Ok((value, VariantAccessor))
         } else {
@@ -235,6 +435,27 @@ impl<'a, 'de> EnumAccess<'de> for EnumAccessor<'a> {
     }
 }
 
+/// The variant named `name`. Names which are not exactly the name of a
+/// variant are compared in lowercase without separators, so `read-only`,
+/// `read_only` and `READ_ONLY` all name the variant `ReadOnly`. The names of
+/// the variants are as serde sees them, after any `rename` or `rename_all`.
+pub fn find_variant(variants: &'static [&'static str], name: &str) -> Option<&'static str> {
+    fn normalize(name: &str) -> String {
+        name.chars().filter(|&c| c != '-' && c != '_').flat_map(char::to_lowercase).collect()
+    }
+
+    if let Some(&variant) = variants.iter().find(|&&variant| variant == name) {
+        return Some(variant)
+    }
+    let name = normalize(name);
+    let mut matches = variants.iter().filter(|variant| normalize(variant) == name);
+    match (matches.next(), matches.next()) {
+        (Some(&variant), None)  => Some(variant),
+        // A name which matches several variants is not guessed at.
+        _                       => None,
+    }
+}
+
 struct VariantAccessor;
 
 impl<'de> VariantAccess<'de> for VariantAccessor {
@@ -272,25 +493,73 @@ impl<'de> VariantAccess<'de> for VariantAccessor {
     
 }
 
-fn hex(s: &str) -> Option<Vec<u8>> {
-    let s = if s.starts_with("0x") { &s[2..] } else { s };
+/// The elements of a tuple, which must be exactly as many as the tuple has.
+struct TupleAccess<'a> {
+    elements: ::std::vec::IntoIter<EnvDeserializer<'a>>,
+}
 
-    let mut bytes = Vec::with_capacity(s.len() / 2);
+impl<'a> TupleAccess<'a> {
+    fn new(elements: Vec<EnvDeserializer<'a>>, len: usize, list: &str) -> Result<TupleAccess<'a>, Error> {
+        if elements.len() != len {
+            let msg = format!("expected {} comma separated values, found {}: `{}`", len, elements.len(), list);
+            return Err(Error::custom(msg))
+        }
+        Ok(TupleAccess { elements: elements.into_iter() })
+    }
+}
 
-    let mut char_indices = s.char_indices();
+impl<'a, 'de> SeqAccess<'de> for TupleAccess<'a> {
+    type Error = Error;
 
-    while let Some((init, _)) = char_indices.next() {
-        if let Some(_) = char_indices.next() {
-            match u8::from_str_radix(&s[init..(init + 2)], 16) {
-                Ok(byte)    => bytes.push(byte),
-                Err(_)      => return None,
-            }
-        } else {
-            return None
-        }
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+        where T: DeserializeSeed<'de>
+    {
+        self.elements.next().map(|element| seed.deserialize(element)).transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.elements.len())
     }
+}
 
-    Some(bytes)
+/// Split a comma separated list into its elements. An empty list has no
+/// elements, rather than one empty element.
+fn elements<'s>(list: &'s str, options: ParseOptions) -> impl Iterator<Item = EnvDeserializer<'s>> {
+    let is_empty = if options.keep_whitespace { list.is_empty() } else { list.trim().is_empty() };
+    let list = if is_empty { None } else { Some(list.split(',')) };
+    list.into_iter().flatten().map(move |s| {
+        let s = if options.keep_whitespace { s } else { s.trim() };
+        EnvDeserializer(Cow::Borrowed(s), options)
+    })
+}
+
+/// Split the `0x`, `0o` or `0b` prefix from an integer, returning its
+/// digits with their sign and the radix to parse them in.
+fn radix(s: &str) -> (Cow<'_, str>, u32) {
+    let (sign, unsigned) = match s.chars().next() {
+        Some(sign @ '-') | Some(sign @ '+') => (&s[..1], &s[sign.len_utf8()..]),
+        _                                   => ("", s),
+    };
+    let (prefix, digits) = unsigned.split_at(unsigned.len().min(2));
+    let radix = match prefix {
+        "0x"    => 16,
+        "0o"    => 8,
+        "0b"    => 2,
+        _       => return (Cow::Borrowed(s), 10),
+    };
+    if digits.starts_with(['-', '+']) {
+        // Signs go before the prefix, so `0x-1` is not a number.
+        return (Cow::Borrowed(s), 10)
+    }
+    (Cow::Owned(format!("{}{}", sign, digits)), radix)
+}
+
+fn check_finite(deserializer: &EnvDeserializer, is_finite: bool) -> Result<(), Error> {
+    if deserializer.1.finite_floats && !is_finite {
+        Err(Error::custom(format!("`{}` is not a finite number", deserializer.0)))
+    } else {
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -298,19 +567,7 @@ mod tests {
     use super::*;
 
     fn deserializer(s: &'static str) -> EnvDeserializer<'static> {
-        EnvDeserializer(Cow::Borrowed(s))
-    }
-
-    #[test]
-    fn test_hex_parser() {
-        assert_eq!(hex(""), Some(vec![]));
-        assert_eq!(hex("01"), Some(vec![0x1]));
-        assert_eq!(hex("ff"), Some(vec![0xff]));
-        assert_eq!(hex("01ff70"), Some(vec![0x1, 0xff, 0x70]));
-        assert_eq!(hex("0x04"), Some(vec![0x4]));
-        assert_eq!(hex("0xdeadbeef"), Some(vec![0xde, 0xad, 0xbe, 0xef]));
-        assert_eq!(hex("1"), None);
-        assert_eq!(hex("not hexadecimal"), None);
+        EnvDeserializer(Cow::Borrowed(s), ParseOptions::default())
     }
 
     #[test]
@@ -326,6 +583,73 @@ mod tests {
         assert!(Foo::deserialize(deserializer("Foo")).is_err());
     }
 
+    #[test]
+    fn test_variant_forms() {
+        #[derive(Deserialize, Eq, PartialEq, Debug)]
+        enum Mode {
+            ReadOnly,
+            ReadWrite,
+        }
+
+        #[derive(Deserialize, Eq, PartialEq, Debug)]
+        #[serde(rename_all = "kebab-case")]
+        enum Renamed {
+            ReadOnly,
+            #[serde(rename = "rw")]
+            ReadWrite,
+        }
+
+        for form in &["ReadOnly", "read-only", "read_only", "READ_ONLY", "readonly"] {
+            assert_eq!(Mode::deserialize(deserializer(form)).unwrap(), Mode::ReadOnly);
+            assert_eq!(Renamed::deserialize(deserializer(form)).unwrap(), Renamed::ReadOnly);
+        }
+        assert_eq!(Renamed::deserialize(deserializer("RW")).unwrap(), Renamed::ReadWrite);
+        assert!(Renamed::deserialize(deserializer("read-write")).is_err());
+        assert!(Mode::deserialize(deserializer("read-only-mode")).is_err());
+    }
+
+    #[test]
+    fn test_ambiguous_variant() {
+        static VARIANTS: &[&str] = &["read_only", "read-only"];
+        assert_eq!(find_variant(VARIANTS, "read-only"), Some("read-only"));
+        assert_eq!(find_variant(VARIANTS, "ReadOnly"), None);
+    }
+
+    #[test]
+    fn test_enum_indexes() {
+        #[derive(Deserialize, Eq, PartialEq, Debug)]
+        enum Level {
+            Low,
+            Medium,
+            High,
+        }
+
+        let indexes = ParseOptions { enum_indexes: true, ..ParseOptions::default() };
+        let deserializer = |s| EnvDeserializer(Cow::Borrowed(s), indexes);
+        assert_eq!(Level::deserialize(deserializer("0")).unwrap(), Level::Low);
+        assert_eq!(Level::deserialize(deserializer("1")).unwrap(), Level::Medium);
+        assert_eq!(Level::deserialize(deserializer("2")).unwrap(), Level::High);
+        assert_eq!(Level::deserialize(deserializer("High")).unwrap(), Level::High);
+        assert!(Level::deserialize(deserializer("3")).is_err());
+
+        assert!(Level::deserialize(self::deserializer("2")).is_err());
+    }
+
+    #[test]
+    fn test_tuple_arity() {
+        let tuple = <(u16, String, bool)>::deserialize(deserializer("8080, localhost, true")).unwrap();
+        assert_eq!(tuple, (8080, String::from("localhost"), true));
+
+        let err = <(u16, String, bool)>::deserialize(deserializer("8080, localhost")).unwrap_err();
+        assert_eq!(err.to_string(), "expected 3 comma separated values, found 2: `8080, localhost`");
+        let err = <(u16, String, bool)>::deserialize(deserializer("8080,localhost,true,false")).unwrap_err();
+        assert_eq!(err.to_string(), "expected 3 comma separated values, found 4: `8080,localhost,true,false`");
+
+        let numbered = EnvSeqDeserializer(vec![String::from("8080"), String::from("localhost")], ParseOptions::default());
+        let err = <(u16, String, bool)>::deserialize(numbered).unwrap_err();
+        assert_eq!(err.to_string(), "expected 3 comma separated values, found 2: `8080,localhost`");
+    }
+
     #[test]
     fn test_numbers() {
         assert_eq!( i8::deserialize(deserializer("-7")).unwrap(), -7i8);
@@ -335,6 +659,63 @@ mod tests {
         assert_eq!(f32::deserialize(deserializer("0.25")).unwrap(), 0.25f32);
     }
 
+    #[test]
+    fn test_integer_radixes() {
+        assert_eq!(u8::deserialize(deserializer("0xFF")).unwrap(), 255u8);
+        assert_eq!(u32::deserialize(deserializer("0xdead")).unwrap(), 0xdeadu32);
+        assert_eq!(u16::deserialize(deserializer("0o755")).unwrap(), 0o755u16);
+        assert_eq!(u8::deserialize(deserializer("0b1010")).unwrap(), 10u8);
+        assert_eq!(u64::deserialize(deserializer("0100")).unwrap(), 100u64);
+        assert_eq!(i8::deserialize(deserializer("-0x80")).unwrap(), -128i8);
+        assert_eq!(i32::deserialize(deserializer("+0b11")).unwrap(), 3i32);
+        assert_eq!(i64::deserialize(deserializer("-42")).unwrap(), -42i64);
+
+        assert!(u8::deserialize(deserializer("0x100")).is_err());
+        assert!(u8::deserialize(deserializer("0b102")).is_err());
+        assert!(i32::deserialize(deserializer("0x-1")).is_err());
+        assert!(u32::deserialize(deserializer("0x")).is_err());
+    }
+
+    #[test]
+    fn test_list_whitespace() {
+        assert_eq!(Vec::<String>::deserialize(deserializer("a, b ,c")).unwrap(), vec!["a", "b", "c"]);
+        assert_eq!(Vec::<u32>::deserialize(deserializer(" 1, 2")).unwrap(), vec![1, 2]);
+        assert_eq!(<(String, String)>::deserialize(deserializer("a , b")).unwrap(),
+                   (String::from("a"), String::from("b")));
+
+        let keep = EnvDeserializer(Cow::Borrowed("a, b ,c"), ParseOptions { keep_whitespace: true, ..ParseOptions::default() });
+        assert_eq!(Vec::<String>::deserialize(keep).unwrap(), vec!["a", " b ", "c"]);
+    }
+
+    #[test]
+    fn test_empty_list() {
+        assert_eq!(Vec::<u16>::deserialize(deserializer("")).unwrap(), Vec::<u16>::new());
+        assert_eq!(Vec::<String>::deserialize(deserializer(" ")).unwrap(), Vec::<String>::new());
+        assert_eq!(Option::<Vec<u16>>::deserialize(deserializer("")).unwrap(), Some(vec![]));
+        assert_eq!(Option::<Vec<u16>>::deserialize(deserializer("7")).unwrap(), Some(vec![7]));
+        assert_eq!(Option::<Vec<u16>>::deserialize(deserializer("7,8")).unwrap(), Some(vec![7, 8]));
+        assert!(Vec::<u16>::deserialize(deserializer("7,")).is_err());
+
+        let keep = EnvDeserializer(Cow::Borrowed(" "), ParseOptions { keep_whitespace: true, ..ParseOptions::default() });
+        assert_eq!(Vec::<String>::deserialize(keep).unwrap(), vec![" "]);
+    }
+
+    #[test]
+    fn test_finite_floats() {
+        let finite = |s| EnvDeserializer(Cow::Borrowed(s), ParseOptions { finite_floats: true, ..ParseOptions::default() });
+
+        assert!(f64::deserialize(deserializer("nan")).unwrap().is_nan());
+        assert_eq!(f64::deserialize(deserializer("inf")).unwrap(), f64::INFINITY);
+        assert_eq!(f32::deserialize(deserializer("-inf")).unwrap(), f32::NEG_INFINITY);
+
+        assert_eq!(f64::deserialize(finite("0.5")).unwrap(), 0.5);
+        assert_eq!(f64::deserialize(finite("nan")).unwrap_err().to_string(),
+                   "`nan` is not a finite number");
+        assert!(f64::deserialize(finite("inf")).is_err());
+        assert!(f32::deserialize(finite("-inf")).is_err());
+        assert!(Vec::<f64>::deserialize(finite("1.0,NaN")).is_err());
+    }
+
     #[test]
     fn test_strings() {
         assert_eq!(String::deserialize(deserializer("Hello world!")).unwrap(),
@@ -342,6 +723,56 @@ mod tests {
     }
 
     #[test]
+    fn test_file_references() {
+        #[derive(Debug)]
+        struct Bytes(Vec<u8>);
+
+        impl<'de> Deserialize<'de> for Bytes {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Bytes, D::Error> {
+                struct BytesVisitor;
+
+                impl<'de> Visitor<'de> for BytesVisitor {
+                    type Value = Bytes;
+
+                    fn expecting(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                        write!(f, "bytes")
+                    }
+
+                    fn visit_byte_buf<E: ErrorTrait>(self, bytes: Vec<u8>) -> Result<Bytes, E> {
+                        Ok(Bytes(bytes))
+                    }
+                }
+
+                deserializer.deserialize_byte_buf(BytesVisitor)
+            }
+        }
+
+        let dir = ::std::env::temp_dir().join(format!("configure-references-{}", ::std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("key.der"), [0x30, 0x82, 0x0a]).unwrap();
+        fs::write(dir.join("password"), "hunter2\n").unwrap();
+        let key = format!("@{}", dir.join("key.der").display());
+        let password = format!("@{}", dir.join("password").display());
+        let missing = format!("@{}", dir.join("missing").display());
+
+        let references = ParseOptions { file_references: true, ..ParseOptions::default() };
+        let reference = |s: &str| EnvDeserializer(Cow::Owned(s.to_owned()), references);
+        assert_eq!(Bytes::deserialize(reference(&key)).unwrap().0, [0x30, 0x82, 0x0a]);
+        assert_eq!(Bytes::deserialize(reference("0a0b")).unwrap().0, [0x0a, 0x0b]);
+        assert_eq!(String::deserialize(reference(&password)).unwrap(), "hunter2");
+        assert_eq!(Option::<String>::deserialize(reference(&password)).unwrap(), Some(String::from("hunter2")));
+        assert_eq!(String::deserialize(reference("@@admin")).unwrap(), "@admin");
+        assert!(String::deserialize(reference(&key)).unwrap_err().to_string().ends_with("is not UTF-8"));
+        assert!(Bytes::deserialize(reference(&missing)).unwrap_err().to_string()
+            .starts_with(&format!("could not read `{}`", &missing[1..])));
+
+        assert_eq!(String::deserialize(EnvDeserializer(Cow::Owned(password.clone()), ParseOptions::default())).unwrap(),
+                   password);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_booleans() {
         assert_eq!(bool::deserialize(deserializer("0")).unwrap(), false);
         assert_eq!(bool::deserialize(deserializer("1")).unwrap(), true);
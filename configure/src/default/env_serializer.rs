@@ -0,0 +1,392 @@
+use serde::ser::*; use serde::ser::{Error as ErrorTrait};
+use erased_serde::Error;
+
+use source::{Casing, StructInfo};
+
+/// Serializes a configuration struct into `PACKAGE_FIELD=value` lines, using
+/// the same formats that `EnvDeserializer` parses. The values of secret
+/// fields are replaced with `<redacted>`.
+pub struct EnvSerializer<'a> {
+    pub package: &'a str,
+    pub info: &'a StructInfo,
+}
+
+pub struct StructSerializer<'a> {
+    package: &'a str,
+    info: &'a StructInfo,
+    lines: Vec<String>,
+}
+
+impl<'a> Serializer for EnvSerializer<'a> {
+    type Ok = Vec<String>;
+    type Error = Error;
+    type SerializeSeq = Impossible<Vec<String>, Error>;
+    type SerializeTuple = Impossible<Vec<String>, Error>;
+    type SerializeTupleStruct = Impossible<Vec<String>, Error>;
+    type SerializeTupleVariant = Impossible<Vec<String>, Error>;
+    type SerializeMap = Impossible<Vec<String>, Error>;
+    type SerializeStruct = StructSerializer<'a>;
+    type SerializeStructVariant = Impossible<Vec<String>, Error>;
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<StructSerializer<'a>, Error> {
+        Ok(StructSerializer {
+            package: self.package,
+            info: self.info,
+            lines: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Vec<String>, Error> {
+        Ok(vec![])
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<Vec<String>, Error>
+        where T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_bool(self, _v: bool) -> Result<Vec<String>, Error> { not_a_struct() }
+    fn serialize_i8(self, _v: i8) -> Result<Vec<String>, Error> { not_a_struct() }
+    fn serialize_i16(self, _v: i16) -> Result<Vec<String>, Error> { not_a_struct() }
+    fn serialize_i32(self, _v: i32) -> Result<Vec<String>, Error> { not_a_struct() }
+    fn serialize_i64(self, _v: i64) -> Result<Vec<String>, Error> { not_a_struct() }
+    fn serialize_u8(self, _v: u8) -> Result<Vec<String>, Error> { not_a_struct() }
+    fn serialize_u16(self, _v: u16) -> Result<Vec<String>, Error> { not_a_struct() }
+    fn serialize_u32(self, _v: u32) -> Result<Vec<String>, Error> { not_a_struct() }
+    fn serialize_u64(self, _v: u64) -> Result<Vec<String>, Error> { not_a_struct() }
+    fn serialize_f32(self, _v: f32) -> Result<Vec<String>, Error> { not_a_struct() }
+    fn serialize_f64(self, _v: f64) -> Result<Vec<String>, Error> { not_a_struct() }
+    fn serialize_char(self, _v: char) -> Result<Vec<String>, Error> { not_a_struct() }
+    fn serialize_str(self, _v: &str) -> Result<Vec<String>, Error> { not_a_struct() }
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Vec<String>, Error> { not_a_struct() }
+    fn serialize_none(self) -> Result<Vec<String>, Error> { not_a_struct() }
+    fn serialize_unit(self) -> Result<Vec<String>, Error> { not_a_struct() }
+
+    fn serialize_some<T>(self, _value: &T) -> Result<Vec<String>, Error>
+        where T: ?Sized + Serialize,
+    {
+        not_a_struct()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<Vec<String>, Error> {
+        not_a_struct()
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Vec<String>, Error>
+        where T: ?Sized + Serialize,
+    {
+        not_a_struct()
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        not_a_struct()
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Error> {
+        not_a_struct()
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        not_a_struct()
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        not_a_struct()
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        not_a_struct()
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        not_a_struct()
+    }
+}
+
+impl<'a> SerializeStruct for StructSerializer<'a> {
+    type Ok = Vec<String>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Error>
+        where T: ?Sized + Serialize,
+    {
+        // Fields which are `None` have no representation as an env var, so
+        // they are left out entirely.
+        if let Some(value) = value.serialize(ValueSerializer)? {
+            let secret = self.info.fields.iter().any(|field| field.secret && field.name == key);
+            let value = if secret { String::from("<redacted>") } else { value };
+            let var_name = self.info.case.unwrap_or(Casing::Upper).var_name(self.package, key);
+            self.lines.push(format!("{}={}", var_name, value));
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<Vec<String>, Error> {
+        Ok(self.lines)
+    }
+}
+
+fn not_a_struct<T>() -> Result<T, Error> {
+    Err(Error::custom("only structs can be converted to environment variables"))
+}
+
+/// The string form of a single field value as an env var, or `None` if it
+/// cannot be represented, like a `None` option.
+pub fn env_value<T: ?Sized + Serialize>(value: &T) -> Result<Option<String>, Error> {
+    value.serialize(ValueSerializer)
+}
+
+/// Serializes a single field value into the string form of an env var.
+/// Returns `None` for values which cannot be represented (`None` options).
+struct ValueSerializer;
+
+struct SeqSerializer {
+    elements: Vec<String>,
+}
+
+macro_rules! serialize_display {
+    ($($f:ident($t:ty);)*) => {$(
+        fn $f(self, v: $t) -> Result<Option<String>, Error> {
+            Ok(Some(v.to_string()))
+        }
+    )*}
+}
+
+impl Serializer for ValueSerializer {
+    type Ok = Option<String>;
+    type Error = Error;
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = Impossible<Option<String>, Error>;
+    type SerializeMap = Impossible<Option<String>, Error>;
+    type SerializeStruct = Impossible<Option<String>, Error>;
+    type SerializeStructVariant = Impossible<Option<String>, Error>;
+
+    serialize_display! {
+        serialize_bool(bool);
+        serialize_i8(i8);
+        serialize_i16(i16);
+        serialize_i32(i32);
+        serialize_i64(i64);
+        serialize_u8(u8);
+        serialize_u16(u16);
+        serialize_u32(u32);
+        serialize_u64(u64);
+        serialize_f32(f32);
+        serialize_f64(f64);
+        serialize_char(char);
+        serialize_str(&str);
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Option<String>, Error> {
+        Ok(Some(v.iter().map(|byte| format!("{:02x}", byte)).collect()))
+    }
+
+    fn serialize_none(self) -> Result<Option<String>, Error> {
+        Ok(None)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Option<String>, Error>
+        where T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Option<String>, Error> {
+        Ok(Some(String::new()))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Option<String>, Error> {
+        Ok(Some(String::new()))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Option<String>, Error> {
+        Ok(Some(variant.to_owned()))
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<Option<String>, Error>
+        where T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Option<String>, Error>
+        where T: ?Sized + Serialize,
+    {
+        Err(Error::custom("only unit variants can be converted to environment variables"))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer, Error> {
+        Ok(SeqSerializer { elements: Vec::with_capacity(len.unwrap_or(0)) })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer, Error> {
+        Ok(SeqSerializer { elements: Vec::with_capacity(len) })
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SeqSerializer, Error> {
+        Ok(SeqSerializer { elements: Vec::with_capacity(len) })
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(Error::custom("only unit variants can be converted to environment variables"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Err(Error::custom("maps cannot be converted to environment variables"))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct, Error> {
+        Err(Error::custom("nested structs cannot be converted to environment variables"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Err(Error::custom("only unit variants can be converted to environment variables"))
+    }
+}
+
+impl SeqSerializer {
+    fn push<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        match value.serialize(ValueSerializer)? {
+            Some(element)   => {
+                self.elements.push(element);
+                Ok(())
+            }
+            None            => Err(Error::custom("sequences cannot contain `None`")),
+        }
+    }
+
+    fn join(self) -> Result<Option<String>, Error> {
+        Ok(Some(self.elements.join(",")))
+    }
+}
+
+impl SerializeSeq for SeqSerializer {
+    type Ok = Option<String>;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Option<String>, Error> {
+        self.join()
+    }
+}
+
+impl SerializeTuple for SeqSerializer {
+    type Ok = Option<String>;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Option<String>, Error> {
+        self.join()
+    }
+}
+
+impl SerializeTupleStruct for SeqSerializer {
+    type Ok = Option<String>;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Option<String>, Error> {
+        self.join()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Example {
+        threads: u16,
+        host: String,
+        verbose: bool,
+        ports: Vec<u16>,
+        cert: Option<String>,
+        key: Option<String>,
+    }
+
+    #[test]
+    fn test_env_lines() {
+        let example = Example {
+            threads: 4,
+            host: String::from("localhost"),
+            verbose: true,
+            ports: vec![80, 443],
+            cert: Some(String::from("etc/cert")),
+            key: None,
+        };
+        assert_eq!(example.serialize(EnvSerializer { package: "example", info: &StructInfo::EMPTY }).unwrap(), vec![
+            "EXAMPLE_THREADS=4",
+            "EXAMPLE_HOST=localhost",
+            "EXAMPLE_VERBOSE=true",
+            "EXAMPLE_PORTS=80,443",
+            "EXAMPLE_CERT=etc/cert",
+        ]);
+    }
+
+    #[test]
+    fn test_not_a_struct() {
+        assert!(vec![1, 2].serialize(EnvSerializer { package: "example", info: &StructInfo::EMPTY }).is_err());
+    }
+}
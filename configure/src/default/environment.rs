@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use std::env::{self, VarError};
+use std::ffi::{OsStr, OsString};
+use std::sync::{Arc, Mutex};
+
+pub type Vars = HashMap<OsString, OsString>;
+
+type Cache = HashMap<String, Result<String, VarError>>;
+
+/// The environment variables a deserializer reads from: either the live
+/// process environment or a snapshot of it.
+#[derive(Clone)]
+pub enum Env {
+    /// The process environment, with the variables read from it so far.
+    /// The cache is shared by the deserializers of a struct's flattened
+    /// fields, so each variable is only read once per `generate`.
+    Live(Arc<Mutex<Cache>>),
+    Snapshot(Arc<Vars>),
+}
+
+impl Env {
+    /// The process environment, with nothing read from it yet.
+    pub fn live() -> Env {
+        Env::Live(Arc::default())
+    }
+
+    /// Look up a variable, with the same results as `std::env::var`.
+    pub fn var(&self, name: &str) -> Result<String, VarError> {
+        match *self {
+            Env::Live(ref cache)        => {
+                let mut cache = cache.lock().unwrap_or_else(|err| err.into_inner());
+                cache.entry(name.to_owned()).or_insert_with(|| read_var(name)).clone()
+            }
+            Env::Snapshot(ref vars)     => match vars.get(OsStr::new(name)) {
+                Some(value) => value.clone().into_string().map_err(VarError::NotUnicode),
+                None        => Err(VarError::NotPresent),
+            },
+        }
+    }
+
+    /// Replace every `${NAME}` in the value of the variable `var_name` with
+    /// the value of the variable `NAME`. `$$` is a literal `$`.
+    ///
+    /// If a referenced variable is not set, it is replaced with the empty
+    /// string when `missing_empty` is true, and is an error otherwise.
+    pub fn interpolate(&self, var_name: &str, value: &str, missing_empty: bool) -> Result<String, String> {
+        let mut result = String::with_capacity(value.len());
+        let mut rest = value;
+        while let Some(idx) = rest.find('$') {
+            result.push_str(&rest[..idx]);
+            rest = &rest[idx..];
+            if rest.starts_with("$$") {
+                result.push('$');
+                rest = &rest[2..];
+            } else if rest.starts_with("${") {
+                let end = match rest.find('}') {
+                    Some(end)   => end,
+                    None        => return Err(format!("`{}` has an unterminated `${{`", var_name)),
+                };
+                let name = &rest[2..end];
+                match self.var(name) {
+                    Ok(value)                                   => result.push_str(&value),
+                    Err(VarError::NotPresent) if missing_empty  => { }
+                    Err(VarError::NotPresent)                   => {
+                        return Err(format!("`{}` refers to `{}`, which is not set", var_name, name))
+                    }
+                    Err(VarError::NotUnicode(_))                => {
+                        return Err(format!("`{}` is not valid unicode", name))
+                    }
+                }
+                rest = &rest[end + 1..];
+            } else {
+                result.push('$');
+                rest = &rest[1..];
+            }
+        }
+        result.push_str(rest);
+        Ok(result)
+    }
+
+    /// The names of every variable which is valid unicode.
+    pub fn names(&self) -> Vec<String> {
+        match *self {
+            Env::Live(_)                => {
+                env::vars_os().filter_map(|(key, _)| key.into_string().ok()).collect()
+            }
+            Env::Snapshot(ref vars)     => {
+                vars.keys().filter_map(|key| key.to_str()).map(String::from).collect()
+            }
+        }
+    }
+}
+
+fn read_var(name: &str) -> Result<String, VarError> {
+    #[cfg(test)]
+    tests::READS.lock().unwrap_or_else(|err| err.into_inner()).push(name.to_owned());
+    env::var(name)
+}
+
+/// A snapshot of the environment shared between every clone of a frozen
+/// source. It is captured lazily, the first time it is needed.
+#[derive(Clone)]
+pub struct Frozen {
+    snapshot: Arc<Mutex<Option<Arc<Vars>>>>,
+    capture: Arc<dyn Fn() -> Vars + Send + Sync>,
+}
+
+impl Default for Frozen {
+    fn default() -> Frozen {
+        Frozen::with_capture(|| env::vars_os().collect())
+    }
+}
+
+impl Frozen {
+    /// A snapshot of the variables returned by `capture` instead of the
+    /// process environment.
+    pub fn with_capture<F>(capture: F) -> Frozen
+        where F: Fn() -> Vars + Send + Sync + 'static,
+    {
+        Frozen {
+            snapshot: Arc::new(Mutex::new(None)),
+            capture: Arc::new(capture),
+        }
+    }
+
+    /// The current snapshot, capturing it if there is none.
+    pub fn env(&self) -> Env {
+        let mut snapshot = self.snapshot.lock().unwrap_or_else(|err| err.into_inner());
+        let vars = snapshot.get_or_insert_with(|| Arc::new((self.capture)()));
+        Env::Snapshot(vars.clone())
+    }
+
+    /// Discard the current snapshot, so that the next one is captured fresh.
+    pub fn clear(&self) {
+        *self.snapshot.lock().unwrap_or_else(|err| err.into_inner()) = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use serde::Deserialize;
+
+    use source::PrepareSource;
+    use super::super::DefaultSource;
+    use super::*;
+
+    /// The name of every variable read from the process environment.
+    pub static READS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    #[derive(Deserialize, Debug, Default, PartialEq)]
+    #[serde(default)]
+    struct Config {
+        host: String,
+        url: String,
+    }
+
+    #[test]
+    fn test_vars_are_read_once() {
+        env::set_var("CACHED_HOST", "example.com");
+        env::set_var("CACHED_URL", "https://${CACHED_HOST}/${CACHED_HOST}");
+        let source = DefaultSource::test(None).interpolate(::source::MissingVars::Error);
+
+        let config = Config::deserialize(source.prepare("cached")).unwrap();
+        assert_eq!(config.url, "https://example.com/example.com");
+        let reads = || READS.lock().unwrap().iter().filter(|name| *name == "CACHED_HOST").count();
+        assert_eq!(reads(), 1);
+
+        // The cache only lasts for one generate.
+        env::set_var("CACHED_HOST", "example.org");
+        assert_eq!(Config::deserialize(source.prepare("cached")).unwrap().host, "example.org");
+        assert_eq!(reads(), 2);
+    }
+}
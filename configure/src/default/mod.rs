@@ -1,61 +1,512 @@
-mod env_deserializer;
+mod builder;
+pub mod env_deserializer;
+mod env_serializer;
+mod environment;
+mod suggest;
+mod toml_deserializer;
 
 use std::borrow::Cow;
 use std::env::{self, VarError};
 use std::fs::File;
-use std::io::Read;
-use std::path::PathBuf;
-use std::slice;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::vec;
 
-use serde::de::{self, Deserializer, IntoDeserializer, MapAccess, Error as ErrorTrait, Visitor};
+use serde::de::{self, Deserializer, EnumAccess, IntoDeserializer, MapAccess, VariantAccess, Error as ErrorTrait, Visitor};
+use serde::ser::Serialize;
 use erased_serde::{Error, Deserializer as DynamicDeserializer};
-use heck::ShoutySnakeCase;
+use heck::{KebabCase, ShoutySnakeCase, SnakeCase};
 use toml;
 
-use source::ConfigSource;
-use self::env_deserializer::EnvDeserializer;
+use overlay;
+use source::{ConfigSource, FieldInfo, PrepareSource, StructInfo};
+pub use self::builder::DefaultSourceBuilder;
+pub(crate) use self::env_serializer::env_value;
+use self::env_deserializer::{EnvDeserializer, EnvSeqDeserializer, ParseOptions};
+use self::env_serializer::EnvSerializer;
+use self::toml_deserializer::TomlDeserializer;
+use self::environment::{Env, Frozen, Vars};
+
+/// A transform of the names of environment variables.
+type Transform = dyn Fn(&str) -> String + Send + Sync;
 
 /// The default source for configuration values. You can set this as the
 /// source of configuration using the `use_default_config!` macro.
+///
+/// Sequences are read from environment variables as comma separated lists.
+/// A variable which is set but empty is an empty sequence, so
+/// `MYAPP_HOSTS=` sets `hosts` to `Some(vec![])` rather than its default.
+/// In the `Cargo.toml`, a sequence can be an array or a string holding a
+/// comma separated list, so `hosts = "a,b"` is the same as
+/// `hosts = ["a", "b"]`.
+///
+/// Structs with `#[serde(flatten)]` fields are supported: the fields of the
+/// flattened struct are read from the variables with the package's prefix
+/// and the keys of its table which are not fields of the outer struct. Since
+/// the types of those fields are not known in advance, the values of their
+/// environment variables are booleans or numbers if they look like them,
+/// and strings otherwise.
+///
+/// Enums are read like structs with one flattened field, named after the
+/// enum: `MYAPP_STORAGE=s3` selects the variant `S3` of the enum `Storage`,
+/// and its fields are read from `MYAPP_STORAGE_BUCKET` and so on. In the
+/// `Cargo.toml`, the `storage` table selects the variant with its `type`
+/// key, or holds a single table named after the variant.
+///
+/// Variants are named in any case: `read-only`, `read_only` and `READ_ONLY`
+/// all select the variant `ReadOnly`, or the variant serde renames to any
+/// of them.
 #[derive(Clone)]
 pub struct DefaultSource {
     toml: Option<Arc<toml::Value>>,
+    unknown_vars: UnknownVars,
+    frozen: Option<Frozen>,
+    fallback_unprefixed: bool,
+    numbered_sequences: bool,
+    parse: ParseOptions,
+    interpolate: Option<MissingVars>,
+    casing: Casing,
+    strict_layers: bool,
+    /// The values of command line arguments, by name without the `--`.
+    args: Option<Arc<Vec<(String, String)>>>,
+    transform: Option<Arc<Transform>>,
+}
+
+/// Merge `other` into `base`: tables are merged key by key, and any other
+/// value in `other` replaces the value in `base`.
+fn merge(base: &mut toml::Value, other: toml::Value) {
+    match (base, other) {
+        (&mut toml::Value::Table(ref mut base), toml::Value::Table(other))  => {
+            for (key, value) in other {
+                match base.get_mut(&key) {
+                    Some(existing)  => merge(existing, value),
+                    None            => { base.insert(key, value); }
+                }
+            }
+        }
+        (base, other)                                                       => *base = other,
+    }
+}
+
+/// What the default source should do with environment variables which have
+/// a package's prefix but do not correspond to any of its fields.
+///
+/// A variable like `MYAPP_TIMEOUT_MS` when the field is called `timeout` is
+/// usually a mistake, but by default it is silently ignored.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum UnknownVars {
+    /// Do not check for unknown variables. This is the default.
+    Ignore,
+    /// Warn about the unknown variables: the warning is logged with the
+    /// `log` feature, and returned by `Configure::generate_with_warnings`.
+    Warn,
+    /// Return an error listing the unknown variables.
+    Deny,
+}
+
+/// How the names of environment variables are cased.
+///
+/// The variable for the field `database_url` of the package `myApp` is
+/// `MY_APP_DATABASE_URL` by default. A source can case the names of every
+/// struct differently, and a struct can choose its own casing with
+/// `#[configure(case = "...")]`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Casing {
+    /// Convert the name to SHOUTY_SNAKE_CASE, like `MY_APP_DATABASE_URL`.
+    /// This is the default.
+    Upper,
+    /// Convert the name to snake_case, like `my_app_database_url`.
+    Snake,
+    /// Convert the name to kebab-case, like `my-app-database-url`.
+    Kebab,
+    /// Lowercase the name without splitting it into words, like
+    /// `myapp_database_url`.
+    Lower,
+    /// Join the package and field names as they are, like
+    /// `myApp_database_url`.
+    Preserve,
+}
+
+impl Casing {
+    /// Convert a name to this casing.
+    pub fn apply(self, name: &str) -> String {
+        match self {
+            Casing::Upper       => name.to_shouty_snake_case(),
+            Casing::Snake       => name.to_snake_case(),
+            Casing::Kebab       => name.to_kebab_case(),
+            Casing::Lower       => name.to_lowercase(),
+            Casing::Preserve    => name.to_owned(),
+        }
+    }
+
+    /// The separator between the prefix and the field in a name.
+    pub(crate) fn separator(self) -> &'static str {
+        match self {
+            Casing::Kebab   => "-",
+            _               => "_",
+        }
+    }
+
+    pub(crate) fn var_name(self, prefix: &str, field: &str) -> String {
+        self.apply(&format!("{}{}{}", prefix, self.separator(), field))
+    }
+}
+
+/// What interpolation should do with references to environment variables
+/// which are not set.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MissingVars {
+    /// Return an error naming the missing variable.
+    Error,
+    /// Replace the reference with the empty string.
+    Empty,
 }
 
 impl ConfigSource for DefaultSource {
     fn init() -> DefaultSource {
-        DefaultSource {
-            toml: DefaultSource::toml().map(Arc::new),
-        }
+        DefaultSource::with_toml(DefaultSource::toml())
+    }
+}
+
+impl PrepareSource for DefaultSource {
+    fn prepare(&self, package: &str) -> Box<dyn DynamicDeserializer<'static>> {
+        self.prepare_struct(package, &StructInfo::EMPTY)
     }
 
-    fn prepare(&self, package: &'static str) -> Box<DynamicDeserializer<'static>> {
+    fn prepare_struct(&self, package: &str, info: &'static StructInfo) -> Box<dyn DynamicDeserializer<'static>> {
+        let env = match self.frozen {
+            Some(ref frozen)    => frozen.env(),
+            None                => Env::live(),
+        };
         let deserializer = DefaultDeserializer {
             source: self.clone(),
-            package: package,
+            package: package.to_owned(),
+            info,
+            env,
+            prefix: None,
+            path: vec![],
+            case: None,
+            separator: None,
+            claimed: vec![],
         };
-        Box::new(DynamicDeserializer::erase(deserializer)) as Box<DynamicDeserializer>
+        Box::new(<dyn DynamicDeserializer>::erase(deserializer)) as Box<dyn DynamicDeserializer>
+    }
+
+    fn refresh(&self) {
+        if let Some(ref frozen) = self.frozen {
+            frozen.clear();
+        }
+    }
+
+    fn describe(&self) -> String {
+        String::from("default")
     }
 }
 
 impl DefaultSource {
     #[cfg(test)]
+    #[allow(missing_docs)]
     pub fn test(toml: Option<toml::Value>) -> DefaultSource {
+        DefaultSource::with_toml(toml)
+    }
+
+    /// A frozen source with no `Cargo.toml`, which reads its variables from
+    /// `capture` instead of the process environment.
+    pub(crate) fn with_vars<F>(capture: F) -> DefaultSource
+        where F: Fn() -> Vars + Send + Sync + 'static,
+    {
+        let mut source = DefaultSource::with_toml(None);
+        source.frozen = Some(Frozen::with_capture(capture));
+        source
+    }
+
+    fn with_toml(toml: Option<toml::Value>) -> DefaultSource {
         DefaultSource {
             toml: toml.map(Arc::new),
+            unknown_vars: UnknownVars::Ignore,
+            frozen: None,
+            fallback_unprefixed: false,
+            numbered_sequences: false,
+            parse: ParseOptions::default(),
+            interpolate: None,
+            casing: Casing::Upper,
+            strict_layers: false,
+            args: None,
+            transform: None,
+        }
+    }
+
+    /// Read configuration from these TOML files, and from the environment,
+    /// instead of from the `Cargo.toml`.
+    ///
+    /// Like the `[package.metadata]` of a `Cargo.toml`, each file holds a
+    /// table for each package, like `[myapp]`. The files are merged in
+    /// order, so a key in a later file overrides the same key in an earlier
+    /// one, while tables are merged key by key. Environment variables still
+    /// override the merged files:
+    ///
+    /// ```rust,ignore
+    /// CONFIGURATION.set(DefaultSource::from_toml_paths(&["defaults.toml", "override.toml"])?);
+    /// ```
+    pub fn from_toml_paths<P: AsRef<Path>>(paths: &[P]) -> io::Result<DefaultSource> {
+        let mut merged = toml::Value::Table(toml::value::Table::new());
+        for path in paths {
+            let mut string = String::new();
+            File::open(path)?.read_to_string(&mut string)?;
+            let toml = toml::from_str(&string).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            merge(&mut merged, toml);
+        }
+        Ok(DefaultSource::with_toml(Some(merged)))
+    }
+
+    /// Read configuration from this TOML, embedded in the program, and from
+    /// the environment, instead of from the `Cargo.toml`.
+    ///
+    /// The TOML is laid out like the files of `from_toml_paths`, with a
+    /// table for each package, and is parsed once, here. Environment
+    /// variables still override it. This lets defaults ship inside the
+    /// binary, where there is no `Cargo.toml` to read:
+    ///
+    /// ```rust,ignore
+    /// CONFIGURATION.set(DefaultSource::with_embedded_toml(include_str!("defaults.toml")));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the TOML is not valid. Since it is part of the program, this is a
+    /// bug in the program rather than a problem with its environment.
+    pub fn with_embedded_toml(toml: &str) -> DefaultSource {
+        DefaultSource::with_toml(Some(DefaultSource::parse_embedded(toml)))
+    }
+
+    fn parse_embedded(toml: &str) -> toml::Value {
+        match toml::from_str(toml) {
+            Ok(toml)    => toml,
+            Err(err)    => panic!("the embedded TOML configuration is not valid: {}", err),
         }
     }
 
+    /// A builder for a source with options, like which `Cargo.toml` it reads
+    /// from.
+    pub fn builder() -> DefaultSourceBuilder {
+        DefaultSourceBuilder::new()
+    }
+
+    /// Read configuration from the command line arguments of the process, as
+    /// well as from the environment and the `Cargo.toml`.
+    ///
+    /// A field is set with `--package-field=value`, where `package-field` is
+    /// the name of its environment variable in kebab-case: the field
+    /// `database_url` of the package `myapp` is set by
+    /// `--myapp-database-url=postgres://localhost`. An argument without a
+    /// value, like `--myapp-verbose`, is `true`. Arguments take precedence
+    /// over environment variables, and other arguments are ignored.
+    ///
+    /// Fields marked `#[configure(env_only)]` or `#[configure(toml_only)]`
+    /// are never read from arguments.
+    pub fn with_args(self) -> DefaultSource {
+        self.with_args_from(env::args().skip(1))
+    }
+
+    /// Read configuration from these arguments, like `with_args` does from
+    /// the command line arguments of the process.
+    pub fn with_args_from<I>(mut self, args: I) -> DefaultSource
+        where I: IntoIterator,
+              I::Item: Into<String>,
+    {
+        let args = args.into_iter().filter_map(|arg| {
+            let arg = arg.into();
+            let arg = arg.strip_prefix("--")?;
+            Some(match arg.find('=') {
+                Some(idx)   => (arg[..idx].to_owned(), arg[idx + 1..].to_owned()),
+                None        => (arg.to_owned(), String::from("true")),
+            })
+        }).collect();
+        self.args = Some(Arc::new(args));
+        self
+    }
+
+    /// Fall back to the unprefixed environment variable for a field when the
+    /// prefixed one is not set.
+    ///
+    /// With this enabled, the field `database_url` of the package `myapp`
+    /// is looked up as `MYAPP_DATABASE_URL`, then as `DATABASE_URL`, and
+    /// only then in the `Cargo.toml`.
+    ///
+    /// The unprefixed variables are shared by every package: if two packages
+    /// both have a `database_url` field, a `DATABASE_URL` variable will
+    /// configure both of them. Set the prefixed variables to configure them
+    /// separately.
+    ///
+    /// Only the fields of the configuration struct itself fall back. The
+    /// fields of flattened structs and of enum variants, which would be
+    /// read from generic names like `URL` or `PORT`, do not.
+    pub fn fallback_unprefixed(mut self) -> DefaultSource {
+        self.fallback_unprefixed = true;
+        self
+    }
+
+    /// Collect sequences from numbered environment variables.
+    ///
+    /// With this enabled, a sequence field `hosts` of the package `myapp`
+    /// is read from `MYAPP_HOSTS`, `MYAPP_HOSTS_1`, `MYAPP_HOSTS_2` and so
+    /// on, up to the first number which is not set. Each variable is one
+    /// element, and is not split on commas. If only `MYAPP_HOSTS` is set, it
+    /// is split on commas as usual.
+    pub fn numbered_sequences(mut self) -> DefaultSource {
+        self.numbered_sequences = true;
+        self
+    }
+
+    /// Reject floating point values from environment variables which are
+    /// infinite or NaN, such as `inf` or `nan`.
+    ///
+    /// These are accepted by default, but are rarely what was intended for
+    /// something like a timeout.
+    pub fn finite_floats(mut self) -> DefaultSource {
+        self.parse.finite_floats = true;
+        self
+    }
+
+    /// Keep the whitespace around the elements of comma separated lists.
+    ///
+    /// By default, `a, b ,c` is the list `["a", "b", "c"]`. With this
+    /// enabled, it is `["a", " b ", "c"]`.
+    pub fn keep_list_whitespace(mut self) -> DefaultSource {
+        self.parse.keep_whitespace = true;
+        self
+    }
+
+    /// Accept the index of a variant of an enum, counting from 0, as well as
+    /// its name.
+    ///
+    /// With this enabled, `MYAPP_LEVEL=2` selects the third variant of the
+    /// enum `Level`. Values which are not the index of a variant are matched
+    /// against the names of the variants as usual, so this should not be
+    /// enabled for enums whose variants are renamed to numbers.
+    pub fn enum_indexes(mut self) -> DefaultSource {
+        self.parse.enum_indexes = true;
+        self
+    }
+
+    /// Read string and byte string values of the form `@path` from the file
+    /// at `path`, like `MYAPP_TLS_KEY=@/etc/myapp/key.pem`.
+    ///
+    /// The contents of the file are the bytes of a byte string, rather than
+    /// hexadecimal, and a single trailing newline is removed from a string.
+    /// A file which cannot be read is an error. Use `@@` for a value which
+    /// starts with a literal `@`. Values from the `Cargo.toml` are never
+    /// read from files.
+    pub fn file_references(mut self) -> DefaultSource {
+        self.parse.file_references = true;
+        self
+    }
+
+    /// Interpolate other environment variables into environment variable
+    /// values.
+    ///
+    /// With this enabled, every `${NAME}` in a value is replaced with the
+    /// value of the variable `NAME`, so `MYAPP_URL` can be set to
+    /// `https://${MYAPP_HOST}:${MYAPP_PORT}`. Use `$$` for a literal `$`.
+    /// References are not interpolated recursively. Values from the
+    /// `Cargo.toml` are never interpolated.
+    pub fn interpolate(mut self, missing: MissingVars) -> DefaultSource {
+        self.interpolate = Some(missing);
+        self
+    }
+
+    /// Change how the names of environment variables are cased before they
+    /// are looked up.
+    ///
+    /// This applies to every name the source derives from a package and a
+    /// field, but not to names set with `#[configure(env = "...")]` or to
+    /// aliases, which are used as they are. Structs which choose their own
+    /// casing with `#[configure(case = "...")]` keep it. The documentation
+    /// generated by `#[configure(generate_docs)]` cannot know the casing of
+    /// the source, so it shows the struct's casing.
+    ///
+    /// ```rust,ignore
+    /// CONFIGURATION.set(DefaultSource::init().casing(Casing::Lower));
+    /// ```
+    pub fn casing(mut self, casing: Casing) -> DefaultSource {
+        self.casing = casing;
+        self
+    }
+
+    /// Return an error when a field marked `#[configure(env_only)]` is set in
+    /// the `Cargo.toml`, or a field marked `#[configure(toml_only)]` is set
+    /// in the environment.
+    ///
+    /// By default, these values are silently ignored.
+    pub fn strict_layers(mut self) -> DefaultSource {
+        self.strict_layers = true;
+        self
+    }
+
+    /// Transform the names of environment variables before they are cased.
+    ///
+    /// The transform is given the name joined from the package and the
+    /// field, like `myapp_db_host`, and its result is cased as usual. A
+    /// transform removing underscores reads the field `db_host` of the
+    /// package `myapp` from `MYAPPDBHOST`:
+    ///
+    /// ```rust,ignore
+    /// CONFIGURATION.set(DefaultSource::init().transform_names(|name| name.replace("_", "")));
+    /// ```
+    ///
+    /// Like `casing`, this does not apply to names set with
+    /// `#[configure(env = "...")]` or to aliases. The fields of flattened
+    /// structs are named after the transformed name of the flattened field.
+    pub fn transform_names<F>(mut self, transform: F) -> DefaultSource
+        where F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        self.transform = Some(Arc::new(transform));
+        self
+    }
+
+    /// Freeze the environment this source reads from.
+    ///
+    /// A frozen source captures a snapshot of the environment variables the
+    /// first time configuration is generated, and every package generated
+    /// afterward sees that same snapshot, even if the environment is changed
+    /// in between. Calling `CONFIGURATION.refresh()` discards the snapshot,
+    /// so that the next generation captures a new one.
+    ///
+    /// ```rust,ignore
+    /// CONFIGURATION.set(DefaultSource::init().frozen());
+    /// ```
+    pub fn frozen(mut self) -> DefaultSource {
+        self.frozen = Some(Frozen::default());
+        self
+    }
+
+    /// Check the environment for variables which have the package's prefix
+    /// but do not correspond to any field, once all of the fields have been
+    /// looked up.
+    ///
+    /// ```rust,ignore
+    /// CONFIGURATION.set(DefaultSource::init().unknown_vars(UnknownVars::Deny));
+    /// ```
+    pub fn unknown_vars(mut self, policy: UnknownVars) -> DefaultSource {
+        self.unknown_vars = policy;
+        self
+    }
+
     fn toml() -> Option<toml::Value> {
-        let path = match env::var_os("CARGO_MANIFEST_DIR") {
-            Some(string)    => {
-                let dir: PathBuf = string.into();
-                dir.join("Cargo.toml")
-            }
-            None            => return None,
-        };
+        let dir = env::var_os("CARGO_MANIFEST_DIR")?;
+        DefaultSource::read_manifest(PathBuf::from(dir).join("Cargo.toml"))
+    }
 
+    // There is no filesystem on wasm32-unknown-unknown.
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+    fn read_manifest(_: PathBuf) -> Option<toml::Value> {
+        None
+    }
+
+    /// The `[package.metadata]` of the `Cargo.toml` at this path.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    fn read_manifest(path: PathBuf) -> Option<toml::Value> {
         let mut file = match File::open(path) {
             Ok(file)    => file,
             Err(_)      => return None,
@@ -69,13 +520,46 @@ impl DefaultSource {
         };
         manifest.get("package")
                 .and_then(|package| package.get("metadata"))
-                .map(|metadata| metadata.clone())
+                .cloned()
     }
 }
 
 struct DefaultDeserializer {
     source: DefaultSource,
-    package: &'static str,
+    package: String,
+    info: &'static StructInfo,
+    env: Env,
+    /// The prefix of a flattened struct, instead of the prefix from `info`.
+    prefix: Option<String>,
+    /// The keys of the flattened fields this struct is nested in, which are
+    /// the path to its table in the `Cargo.toml`.
+    path: Vec<String>,
+    /// The casing of the struct this one is flattened into.
+    case: Option<Casing>,
+    /// The nesting separator of the struct this one is flattened into.
+    separator: Option<&'static str>,
+    /// The variables with this struct's prefix which belong to other fields
+    /// of the struct it is flattened into.
+    claimed: Vec<Claim>,
+}
+
+/// Variables which belong to a field, when they could also belong to a
+/// flattened field whose name is a prefix of that field's: the longer field
+/// name wins.
+enum Claim {
+    /// The variable of an ordinary field.
+    Var(String),
+    /// The prefix of the variables of a flattened field.
+    Prefix(String),
+}
+
+impl Claim {
+    fn covers(&self, name: &str) -> bool {
+        match *self {
+            Claim::Var(ref var)         => name == var,
+            Claim::Prefix(ref prefix)   => name.starts_with(prefix),
+        }
+    }
 }
 
 impl<'de> Deserializer<'de> for DefaultDeserializer {
@@ -96,11 +580,16 @@ impl<'de> Deserializer<'de> for DefaultDeserializer {
     ) -> Result<V::Value, Self::Error>
         where V: Visitor<'de>,
     {
-        visitor.visit_map(MapAccessor {
-            deserializer: self,
-            fields: fields.iter(),
-            next_val: None,
-        })
+        visitor.visit_map(MapAccessor::new(self, fields.to_vec(), false))
+    }
+
+    // Structs with `#[serde(flatten)]` fields are deserialized as maps, with
+    // the fields of the flattened structs among the unknown keys.
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>,
+    {
+        let fields = self.info.fields.iter().map(|info| info.name).collect();
+        visitor.visit_map(MapAccessor::new(self, fields, true))
     }
 
     fn deserialize_unit_struct<V>(
@@ -110,64 +599,615 @@ impl<'de> Deserializer<'de> for DefaultDeserializer {
     ) -> Result<V::Value, Self::Error>
         where V: Visitor<'de>, 
     {
-        self.deserialize_struct(_name, &[], visitor)
+        // A unit struct has no fields to look up.
+        visitor.visit_unit()
+    }
+
+    // The variant of an enum is selected by the variable named after the
+    // enum, like `MYAPP_STORAGE=s3`, or else by the table named after it,
+    // and the fields of the variant are read like those of a flattened
+    // field with that name.
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>,
+    {
+        let accessor = MapAccessor::new(self, vec![], false);
+        let (variant, deserializer) = accessor.variant(name, variants)?;
+        visitor.visit_enum(EnumAccessor { variant, deserializer })
+    }
+
+    // A configuration wrapped in a newtype struct is read like the type it
+    // wraps.
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V
+    ) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
     }
 
     forward_to_deserialize_any! {
         bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit seq
-        bytes byte_buf map tuple_struct newtype_struct
-        tuple ignored_any identifier enum option 
+        bytes byte_buf tuple_struct
+        tuple ignored_any identifier option 
+    }
+}
+
+struct EnumAccessor {
+    variant: &'static str,
+    deserializer: DefaultDeserializer,
+}
+
+impl<'de> EnumAccess<'de> for EnumAccessor {
+    type Error = Error;
+    type Variant = DefaultDeserializer;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+        where V: de::DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(self.variant.into_deserializer())?;
+        Ok((variant, self.deserializer))
+    }
+}
+
+impl<'de> VariantAccess<'de> for DefaultDeserializer {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+        where T: de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, _visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>,
+    {
+        Err(Error::custom("The default configuration deserializer does not support tuple variants."))
+    }
+
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>,
+    {
+        visitor.visit_map(MapAccessor::new(self, fields.to_vec(), false))
     }
 }
 
 struct MapAccessor {
     deserializer: DefaultDeserializer,
-    fields: slice::Iter<'static, &'static str>,
+    all_fields: Vec<&'static str>,
+    fields: vec::IntoIter<&'static str>,
     next_val: Option<Either>,
+    /// For a map, the keys which are not fields of the struct, and their
+    /// values, once the fields have all been looked up.
+    unknown: Option<vec::IntoIter<(String, Either)>>,
+    is_map: bool,
 }
 
 enum Either {
     Env(String),
+    EnvSeq(Vec<String>),
+    /// A value whose type is not known, because it is for a field of a
+    /// flattened struct, which is typed by how it looks.
+    EnvUntyped(String),
     Toml(toml::Value),
+    /// The flattened field with this name.
+    Nested(&'static str),
+    /// A value which could not be read.
+    Invalid(Error),
 }
 
-impl<'de> MapAccess<'de> for MapAccessor {
-    type Error = Error;
+impl MapAccessor {
+    fn new(deserializer: DefaultDeserializer, fields: Vec<&'static str>, is_map: bool) -> MapAccessor {
+        MapAccessor {
+            deserializer,
+            fields: fields.clone().into_iter(),
+            all_fields: fields,
+            next_val: None,
+            unknown: None,
+            is_map,
+        }
+    }
 
-    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
-        where K: de::DeserializeSeed<'de>,
-    {
-        while let Some(field) = self.fields.next() {
-            let var_name = format!("{}_{}", self.deserializer.package, field)
-                                .to_shouty_snake_case();
-            match env::var(&var_name) {
-                Ok(env_var)                     => {
-                    self.next_val = Some(Either::Env(env_var));
+    /// What is known about this field, if anything.
+    fn info(&self, field: &str) -> Option<&'static FieldInfo> {
+        self.deserializer.info.fields.iter().find(|info| info.name == field)
+    }
+
+    /// The prefix of the environment variables for this struct, including
+    /// the separator before the field name.
+    fn prefix(&self) -> String {
+        match self.deserializer.prefix {
+            // The prefix of a flattened struct is already cased.
+            Some(ref prefix)    => prefix.clone(),
+            None                => {
+                let prefix = self.deserializer.info.prefix.unwrap_or(&self.deserializer.package);
+                format!("{}{}", self.casing().apply(prefix), self.casing().separator())
+            }
+        }
+    }
+
+    /// Whether the variable `name` belongs to another field of the struct
+    /// this one is flattened into.
+    fn is_claimed(&self, name: &str) -> bool {
+        self.deserializer.claimed.iter().any(|claim| claim.covers(name))
+    }
+
+    /// If the variable `var_name` of this field is not set, the first of its
+    /// deprecated aliases which is set, and its value. Aliases which are
+    /// used, or which are ignored because `var_name` is set, are warned about.
+    fn alias(&self, info: &FieldInfo, var_name: &str, is_set: bool) -> Option<(&'static str, Result<String, VarError>)> {
+        for &alias in info.aliases {
+            match self.deserializer.env.var(alias) {
+                Err(VarError::NotPresent)   => continue,
+                _ if is_set                 => warn_alias(alias, var_name, true),
+                var                         => {
+                    warn_alias(alias, var_name, false);
+                    return Some((alias, var))
                 }
-                Err(VarError::NotPresent)       => {
-                    let toml = self.deserializer.source.toml.as_ref()
-                        .and_then(|toml| toml.get(self.deserializer.package))
-                        .and_then(|package| package.get(field));
-
-                    match toml {
-                        Some(toml)  => {
-                            self.next_val = Some(Either::Toml(toml.clone()));
+            }
+        }
+        None
+    }
+
+    /// If `key` is not set in the table, the value of the first of its
+    /// deprecated aliases which is.
+    fn toml_alias<'t>(&self, info: &FieldInfo, table: &'t toml::Value, key: &str) -> Option<&'t toml::Value> {
+        info.toml_aliases.iter().filter_map(|&alias| {
+            table.get(alias).inspect(|_| warn_alias(alias, key, false))
+        }).next()
+    }
+
+    /// The table of the `Cargo.toml` for this struct, if it has one.
+    fn table(&self) -> Option<&toml::Value> {
+        let package = self.deserializer.source.toml.as_ref()
+            .and_then(|toml| toml.get(&self.deserializer.package));
+        self.deserializer.path.iter().fold(package, |table, key| table.and_then(|table| table.get(key)))
+    }
+
+    /// Whether anything is set for the flattened field with this key: an
+    /// environment variable with its prefix, or a table in the `Cargo.toml`.
+    fn nested_is_set(&self, field: &'static str) -> bool {
+        let key = self.key(field);
+        let prefix = self.nested_prefix(key);
+        let claimed = self.claims(key);
+        self.deserializer.env.names().iter()
+            .any(|name| name.starts_with(&prefix) && !claimed.iter().any(|claim| claim.covers(name)))
+            || self.table().is_some_and(|table| table.get(self.toml_key(field)).is_some())
+    }
+
+    /// The deserializer for the flattened field.
+    fn nested(&self, field: &'static str) -> DefaultDeserializer {
+        let key = self.key(field);
+        let mut path = self.deserializer.path.clone();
+        path.push(self.toml_key(field).to_owned());
+        DefaultDeserializer {
+            source: self.deserializer.source.clone(),
+            package: self.deserializer.package.clone(),
+            info: &StructInfo::EMPTY,
+            env: self.deserializer.env.clone(),
+            prefix: Some(self.nested_prefix(key)),
+            path,
+            case: Some(self.casing()),
+            separator: Some(self.separator()),
+            claimed: self.claims(key),
+        }
+    }
+
+    /// The variant of the enum `name` which is selected, and the
+    /// deserializer for its fields.
+    ///
+    /// In the `Cargo.toml`, the table named after the enum selects the
+    /// variant with its `type` key, or else has a single key, the variant,
+    /// whose table holds the fields. The enum's table may also just be the
+    /// name of the variant.
+    fn variant(&self, name: &'static str, variants: &'static [&'static str]) -> Result<(&'static str, DefaultDeserializer), Error> {
+        let key = name.to_snake_case();
+        let var_name = self.var_name(&key);
+        let mut path = self.deserializer.path.clone();
+        path.push(key.clone());
+        let selected = match self.deserializer.env.var(&var_name) {
+            Ok(value)                       => self.interpolate(&var_name, value)?,
+            Err(VarError::NotUnicode(_))    => {
+                return Err(Error::custom(format!("`{}` is not valid unicode", var_name)))
+            }
+            Err(VarError::NotPresent)       => match self.table().and_then(|table| table.get(&key)) {
+                Some(toml::Value::String(variant))  => variant.clone(),
+                Some(toml::Value::Table(table))     => {
+                    match table.get("type").and_then(|variant| variant.as_str()) {
+                        Some(variant)                   => variant.to_owned(),
+                        None if table.len() == 1        => {
+                            let variant = table.keys().next().unwrap();
+                            path.push(variant.clone());
+                            variant.clone()
                         }
-                        // If there is neither an env var nor a toml value,
-                        // this field is not set. Skip it.
-                        None        => continue,
+                        None                            => {
+                            let msg = format!("the `{}` table must have a `type` key or a single key naming the variant", key);
+                            return Err(Error::custom(msg))
+                        }
+                    }
+                }
+                Some(_)                             => {
+                    return Err(Error::custom(format!("`{}` must be a variant name or a table", key)))
+                }
+                None                                => {
+                    return Err(Error::custom(format!("no variant of `{}` is selected; set `{}`", name, var_name)))
+                }
+            }
+        };
+
+        let variant = match env_deserializer::find_variant(variants, &selected) {
+            Some(variant)   => variant,
+            None            => return Err(Error::unknown_variant(&selected, variants)),
+        };
+
+        let deserializer = DefaultDeserializer {
+            source: self.deserializer.source.clone(),
+            package: self.deserializer.package.clone(),
+            info: &StructInfo::EMPTY,
+            env: self.deserializer.env.clone(),
+            prefix: Some(self.nested_prefix(&key)),
+            path,
+            case: Some(self.casing()),
+            separator: Some(self.separator()),
+            claimed: vec![],
+        };
+        Ok((variant, deserializer))
+    }
+
+    /// The variables with the prefix of the flattened field with this key
+    /// which belong to fields with longer names.
+    fn claims(&self, key: &str) -> Vec<Claim> {
+        let prefix = self.nested_prefix(key);
+        self.all_fields.iter().map(|field| (self.info(field), self.key(field)))
+            .filter(|&(info, other)| other.len() > key.len() && !info.is_some_and(|info| info.skip))
+            .map(|(info, other)| match info.is_some_and(|info| info.flatten) {
+                true    => Claim::Prefix(self.nested_prefix(other)),
+                false   => Claim::Var(self.var_name(other)),
+            })
+            .filter(|claim| match *claim {
+                Claim::Var(ref name) | Claim::Prefix(ref name) => name.starts_with(&prefix),
+            })
+            .collect()
+    }
+
+    /// The separator between the prefix of a flattened field and the names
+    /// of its fields.
+    fn separator(&self) -> &'static str {
+        let deserializer = &self.deserializer;
+        deserializer.separator.or(deserializer.info.separator).unwrap_or(self.casing().separator())
+    }
+
+    /// How the environment variables for this struct are cased.
+    fn casing(&self) -> Casing {
+        let deserializer = &self.deserializer;
+        deserializer.case.or(deserializer.info.case).unwrap_or(deserializer.source.casing)
+    }
+
+    /// The environment variable for the field with this key.
+    fn var_name(&self, key: &str) -> String {
+        let deserializer = &self.deserializer;
+        match deserializer.source.transform {
+            Some(ref transform) if deserializer.prefix.is_none()    => {
+                let prefix = deserializer.info.prefix.unwrap_or(&deserializer.package);
+                let casing = self.casing();
+                casing.apply(&transform(&format!("{}{}{}", prefix, casing.separator(), key)))
+            }
+            _                                                       => {
+                format!("{}{}", self.prefix(), self.casing().apply(key))
+            }
+        }
+    }
+
+    /// The prefix shared by the variables of the flattened field with this
+    /// key.
+    fn nested_prefix(&self, key: &str) -> String {
+        format!("{}{}", self.var_name(key), self.separator())
+    }
+
+    /// The name this field is looked up by.
+    fn key(&self, field: &'static str) -> &'static str {
+        self.info(field).and_then(|info| info.rename).unwrap_or(field)
+    }
+
+    /// The key this field is looked up by in the `Cargo.toml`.
+    fn toml_key(&self, field: &'static str) -> &'static str {
+        self.info(field).and_then(|info| info.toml_key).unwrap_or_else(|| self.key(field))
+    }
+
+    /// The value of the ordinary field `field`, looked up by `key`, or
+    /// `None` if it is not set.
+    fn value(&self, field: &'static str, key: &str, info: Option<&'static FieldInfo>) -> Result<Option<Either>, Error> {
+        let exact = info.and_then(|info| info.env);
+        let flag = info.is_some_and(|info| info.flag);
+        let env_only = info.is_some_and(|info| info.env_only);
+        let toml_only = info.is_some_and(|info| info.toml_only);
+        if let Some(value) = self.arg(key) {
+            if !env_only && !toml_only {
+                return Ok(Some(Either::Env(value.to_owned())))
+            }
+            self.forbidden(field, &format!("the argument `--{}`", self.arg_name(key)))?;
+        }
+
+        let namespace = info.and_then(|info| info.namespace);
+        let mut var_name = match (exact, namespace) {
+            (Some(name), _)         => name.to_owned(),
+            (None, Some(namespace)) => self.casing().var_name(namespace, key),
+            (None, None)            => self.var_name(key),
+        };
+        let mut var = match self.is_claimed(&var_name) {
+            true    => Err(VarError::NotPresent),
+            false   => self.deserializer.env.var(&var_name),
+        };
+        if let Some(info) = info {
+            if let Some((alias, alias_var)) = self.alias(info, &var_name, var.is_ok()) {
+                var_name = alias.to_owned();
+                var = alias_var;
+            }
+        }
+        if let Err(VarError::NotPresent) = var {
+            let top_level = self.deserializer.prefix.is_none();
+            if self.deserializer.source.fallback_unprefixed && exact.is_none() && top_level {
+                var_name = self.casing().apply(key);
+                var = self.deserializer.env.var(&var_name);
+            }
+        }
+        if toml_only && !matches!(var, Err(VarError::NotPresent)) {
+            self.forbidden(field, &format!("the environment variable `{}`", var_name))?;
+            var = Err(VarError::NotPresent);
+        }
+
+        match var {
+            // A flag is set whatever its value, even if it is not unicode.
+            Ok(_) | Err(VarError::NotUnicode(_)) if flag    => Ok(Some(Either::Env(String::from("true")))),
+            Ok(env_var)                     => self.numbered(&var_name, env_var).map(Some),
+            Err(VarError::NotPresent)       => {
+                let toml_key = self.toml_key(field);
+                let table = match namespace {
+                    Some(namespace) => self.deserializer.source.toml.as_ref().and_then(|toml| toml.get(namespace)),
+                    None            => self.table(),
+                };
+                let toml = table.and_then(|table| {
+                    table.get(toml_key).or_else(|| self.toml_alias(info?, table, toml_key))
+                });
+
+                match toml {
+                    Some(_) if env_only => {
+                        self.forbidden(field, &format!("the key `{}` of the `Cargo.toml`", toml_key))?;
+                        Ok(None)
                     }
+                    Some(toml)  => Ok(Some(Either::Toml(toml.clone()))),
+                    // If there is neither an env var nor a toml value, this
+                    // field is not set.
+                    None        => Ok(None),
                 }
+            }
+            Err(VarError::NotUnicode(_))    => {
+                Err(Error::custom(format!("`{}` is not valid unicode", var_name)))
+            }
+        }
+    }
+
+    /// The name of the command line argument for the field with this key,
+    /// without the `--`.
+    fn arg_name(&self, key: &str) -> String {
+        self.var_name(key).to_kebab_case()
+    }
+
+    /// The value of the command line argument for the field with this key,
+    /// if the source reads arguments and it was passed. If it was passed
+    /// more than once, the last value is used.
+    fn arg(&self, key: &str) -> Option<&str> {
+        let args = self.deserializer.source.args.as_ref()?;
+        let name = self.arg_name(key);
+        args.iter().rev().find(|arg| arg.0 == name).map(|arg| &arg.1[..])
+    }
+
+    /// A value for this field was found in a layer it may not be read from,
+    /// which is an error with strict layers, and ignored otherwise.
+    fn forbidden(&self, field: &str, found: &str) -> Result<(), Error> {
+        match self.deserializer.source.strict_layers {
+            true    => Err(Error::custom(format!("`{}` cannot be set by {}", field, found))),
+            false   => Ok(()),
+        }
+    }
+
+    /// If numbered sequences are enabled and `{var_name}_1` is set, collect
+    /// every numbered variable following this one.
+    fn numbered(&self, var_name: &str, first: String) -> Result<Either, Error> {
+        let first = self.interpolate(var_name, first)?;
+        if !self.deserializer.source.numbered_sequences {
+            return Ok(Either::Env(first))
+        }
+
+        let mut values = vec![first];
+        loop {
+            let numbered = format!("{}_{}", var_name, values.len());
+            match self.deserializer.env.var(&numbered) {
+                Ok(value)                       => values.push(self.interpolate(&numbered, value)?),
+                Err(VarError::NotPresent)       => break,
                 Err(VarError::NotUnicode(_))    => {
-                    return Err(Error::custom(format!("`{}` is not valid unicode", var_name)));
+                    return Err(Error::custom(format!("`{}` is not valid unicode", numbered)));
                 }
             }
+        }
+
+        if values.len() == 1 {
+            Ok(Either::Env(values.pop().unwrap()))
+        } else {
+            Ok(Either::EnvSeq(values))
+        }
+    }
+
+    fn interpolate(&self, var_name: &str, value: String) -> Result<String, Error> {
+        match self.deserializer.source.interpolate {
+            Some(missing)   => {
+                let missing_empty = missing == MissingVars::Empty;
+                self.deserializer.env.interpolate(var_name, &value, missing_empty).map_err(Error::custom)
+            }
+            None            => Ok(value),
+        }
+    }
+
+    /// The variables with this struct's prefix and the keys of its table
+    /// which are not for any of its fields, keyed by field name. Variables
+    /// take precedence over keys of the table.
+    ///
+    /// Serde gives these to the structs it flattens, whose fields are not
+    /// known in advance.
+    fn unknown_keys(&self) -> Result<Vec<(String, Either)>, Error> {
+        let prefix = self.prefix();
+        let mut known_vars: Vec<String> = self.all_fields.iter().map(|field| self.var_name(self.key(field))).collect();
+        known_vars.extend(self.deserializer.info.fields.iter().filter_map(|info| info.env).map(String::from));
+        known_vars.extend(self.deserializer.info.fields.iter().flat_map(|info| info.aliases).map(|&alias| alias.to_owned()));
+        let nested: Vec<String> = self.all_fields.iter()
+            .filter(|field| self.info(field).is_some_and(|info| info.flatten))
+            .map(|field| self.nested_prefix(self.key(field)))
+            .collect();
+        let is_known_key = |key: &str| self.all_fields.iter().any(|&field| {
+            field == key || self.key(field) == key || self.toml_key(field) == key
+        });
 
+        let mut names = self.deserializer.env.names();
+        names.sort();
+        let mut unknown = vec![];
+        for name in names {
+            if !name.starts_with(&prefix) || known_vars.contains(&name) || self.is_claimed(&name)
+                || nested.iter().any(|nested| name.starts_with(nested)) {
+                continue
+            }
+            let key = name[prefix.len()..].to_snake_case();
+            if is_known_key(&key) {
+                continue
+            }
+            match self.deserializer.env.var(&name) {
+                Ok(value)                       => {
+                    let value = self.interpolate(&name, value)?;
+                    unknown.push((key, Either::EnvUntyped(value)));
+                }
+                Err(VarError::NotPresent)       => { }
+                Err(VarError::NotUnicode(_))    => {
+                    return Err(Error::custom(format!("`{}` is not valid unicode", name)));
+                }
+            }
+        }
+
+        if let Some(table) = self.table().and_then(|table| table.as_table()) {
+            for (key, value) in table {
+                if !is_known_key(key) && !unknown.iter().any(|unknown| unknown.0 == *key) {
+                    unknown.push((key.clone(), Either::Toml(value.clone())));
+                }
+            }
+        }
+
+        Ok(unknown)
+    }
+
+    fn check_unknown_vars(&self) -> Result<(), Error> {
+        if self.deserializer.source.unknown_vars == UnknownVars::Ignore {
+            return Ok(())
+        }
+
+        let package = &self.deserializer.package[..];
+        let prefix = self.prefix();
+        let mut known: Vec<String> = self.all_fields.iter().map(|field| self.var_name(self.key(field))).collect();
+        known.extend(self.deserializer.info.fields.iter().flat_map(|info| info.aliases).map(|&alias| alias.to_owned()));
+
+        // The variables of flattened fields are checked by their own structs.
+        let nested: Vec<String> = self.all_fields.iter()
+            .filter(|field| self.info(field).is_some_and(|info| info.flatten))
+            .map(|field| self.nested_prefix(self.key(field)))
+            .collect();
+
+        let mut unknown = unknown_vars(&self.deserializer.env, &prefix, &known);
+        unknown.retain(|var| !nested.iter().any(|nested| var.starts_with(nested)));
+        if self.deserializer.source.numbered_sequences {
+            unknown.retain(|var| !known.iter().any(|known| is_numbered(var, known)));
+        }
+        if unknown.is_empty() {
+            return Ok(())
+        }
+
+        // Compare only the part of each name after the prefix, so the
+        // allowed distance depends on the length of the field name.
+        let unknown: Vec<String> = unknown.into_iter().map(|var| {
+            // Aliases and transformed names need not have the prefix.
+            let candidates = known.iter().filter_map(|known| known.strip_prefix(&prefix[..]));
+            match suggest::closest(&var[prefix.len()..], candidates) {
+                Some(field) => format!("{} (did you mean {}{}?)", var, prefix, field),
+                None        => var,
+            }
+        }).collect();
+
+        let msg = format!("unrecognized environment variables for `{}`: {}",
+                          package, unknown.join(", "));
+        match self.deserializer.source.unknown_vars {
+            UnknownVars::Warn   => {
+                overlay::warn(msg);
+                Ok(())
+            }
+            UnknownVars::Deny   => Err(Error::custom(msg)),
+            UnknownVars::Ignore => Ok(()),
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for MapAccessor {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+        where K: de::DeserializeSeed<'de>,
+    {
+        while let Some(field) = self.fields.next() {
+            let info = self.info(field);
+            if info.is_some_and(|info| info.skip) {
+                continue
+            }
+            let key = self.key(field);
+            if info.is_some_and(|info| info.flatten) {
+                if !self.nested_is_set(field) {
+                    continue
+                }
+                self.next_val = Some(Either::Nested(field));
+                return seed.deserialize(field.into_deserializer()).map(Some);
+            }
+            // An invalid value is an error from `next_value`, rather than
+            // from here, so that the fields after it can still be read.
+            self.next_val = match self.value(field, key, info) {
+                Ok(Some(value)) => Some(value),
+                Ok(None)        => continue,
+                Err(err)        => Some(Either::Invalid(err)),
+            };
             let key = seed.deserialize(field.into_deserializer())?;
             return Ok(Some(key));
         }
 
-        Ok(None)
+        if !self.is_map {
+            self.check_unknown_vars()?;
+            return Ok(None)
+        }
+
+        // Every other variable with the prefix is passed on as an unknown
+        // key, so none of them are unknown variables.
+        if self.unknown.is_none() {
+            self.unknown = Some(self.unknown_keys()?.into_iter());
+        }
+        match self.unknown.as_mut().and_then(|unknown| unknown.next()) {
+            Some((key, value))  => {
+                self.next_val = Some(value);
+                seed.deserialize(key.into_deserializer()).map(Some)
+            }
+            None                => Ok(None),
+        }
     }
 
     fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
@@ -175,14 +1215,736 @@ impl<'de> MapAccess<'de> for MapAccessor {
     {
         match self.next_val.take() {
             Some(Either::Env(env))      => {
-                seed.deserialize(EnvDeserializer(Cow::Owned(env)))
+                seed.deserialize(EnvDeserializer(Cow::Owned(env), self.deserializer.source.parse))
+            }
+            Some(Either::EnvSeq(env))   => {
+                seed.deserialize(EnvSeqDeserializer(env, self.deserializer.source.parse))
+            }
+            Some(Either::EnvUntyped(env)) => {
+                seed.deserialize(untyped(env)).map_err(|e| Error::custom(e.to_string()))
             }
             Some(Either::Toml(toml))    => {
-                seed.deserialize(toml).map_err(|e| Error::custom(e.to_string()))
+                seed.deserialize(TomlDeserializer(toml, ParseOptions {
+                    file_references: false,
+                    ..self.deserializer.source.parse
+                }))
             }
+            Some(Either::Nested(field)) => {
+                seed.deserialize(self.nested(field))
+            }
+            Some(Either::Invalid(err))  => Err(err),
             None                        => {
                 Err(Error::custom("called `next_value` without calling `next_key`"))
             }
         }
     }
 }
+
+/// Render a configuration struct as `PACKAGE_FIELD=value` lines, one for each
+/// field, in the format the default source reads from the environment.
+///
+/// Sequences are joined with commas, byte strings are written as hex, and
+/// fields which are `None` are left out. Maps and nested structs cannot be
+/// represented as environment variables and produce an error.
+///
+/// The derive generates a `to_env_lines` method which calls
+/// `to_env_lines_struct` with the package name of the configuration.
+pub fn to_env_lines<T: Serialize>(package: &str, value: &T) -> Result<Vec<String>, Error> {
+    to_env_lines_struct(package, &StructInfo::EMPTY, value)
+}
+
+/// Render a configuration struct as `PACKAGE_FIELD=value` lines, like
+/// `to_env_lines`, replacing the values of the fields `info` marks as secret
+/// with `<redacted>`.
+pub fn to_env_lines_struct<T: Serialize>(package: &str, info: &StructInfo, value: &T) -> Result<Vec<String>, Error> {
+    value.serialize(EnvSerializer { package, info })
+}
+
+/// The value of an environment variable whose type is not known: a boolean
+/// or a number if it looks like one, and otherwise a string.
+fn untyped(value: String) -> toml::Value {
+    match &value[..] {
+        "true"  => return toml::Value::Boolean(true),
+        "false" => return toml::Value::Boolean(false),
+        _       => { }
+    }
+    if let Ok(integer) = value.parse() {
+        return toml::Value::Integer(integer)
+    }
+    match value.parse() {
+        Ok(float) if f64::is_finite(float)  => toml::Value::Float(float),
+        _                                   => toml::Value::String(value),
+    }
+}
+
+/// Warn that the deprecated `alias` of `name` is used or, if `ignored`, that
+/// it is set but ignored because `name` is set too.
+fn warn_alias(alias: &str, name: &str, ignored: bool) {
+    if ignored {
+        overlay::warn(format!("`{}` is deprecated and is ignored because `{}` is set", alias, name));
+    } else {
+        overlay::warn(format!("`{}` is deprecated; use `{}` instead", alias, name));
+    }
+}
+
+/// Whether `var` is `{name}_{n}` for some number `n`.
+fn is_numbered(var: &str, name: &str) -> bool {
+    var.starts_with(name) && var[name.len()..].starts_with('_')
+        && var[name.len() + 1..].parse::<usize>().is_ok()
+}
+
+/// Every environment variable with the prefix which is not one of the known
+/// variables, sorted by name.
+fn unknown_vars(env: &Env, prefix: &str, known: &[String]) -> Vec<String> {
+    let mut unknown: Vec<String> = env.names().into_iter()
+        .filter(|key| key.starts_with(prefix) && !known.contains(key))
+        .collect();
+    unknown.sort();
+    unknown
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use serde::Deserialize;
+
+    use source::PrepareSource;
+    use super::*;
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Timeout {
+        timeout: u32,
+    }
+
+    #[test]
+    fn test_unknown_vars() {
+        env::set_var("ORPHANS_TIMEOUT", "5");
+        env::set_var("ORPHANS_TIMEOUT_MS", "5000");
+
+        let source = DefaultSource::test(None).unknown_vars(UnknownVars::Deny);
+        let err = Timeout::deserialize(source.prepare("orphans")).unwrap_err();
+        assert_eq!(err.to_string(),
+                   "unrecognized environment variables for `orphans`: \
+                    ORPHANS_TIMEOUT_MS (did you mean ORPHANS_TIMEOUT?)");
+
+        let source = DefaultSource::test(None).unknown_vars(UnknownVars::Warn);
+        let (timeout, warnings) = overlay::collect_warnings(|| Timeout::deserialize(source.prepare("orphans")));
+        assert_eq!(timeout.unwrap().timeout, 5);
+        assert_eq!(warnings, ["unrecognized environment variables for `orphans`: \
+                               ORPHANS_TIMEOUT_MS (did you mean ORPHANS_TIMEOUT?)"]);
+
+        let source = DefaultSource::test(None);
+        assert_eq!(Timeout::deserialize(source.prepare("orphans")).unwrap().timeout, 5);
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct Value {
+        value: u32,
+    }
+
+    #[test]
+    fn test_unknown_vars_with_aliases() {
+        static INFO: StructInfo = StructInfo::new(&[FieldInfo { aliases: &["DB"], ..FieldInfo::new("value") }]);
+        env::set_var("STRAYS_VALUE", "1");
+        env::set_var("STRAYS_VALEU", "2");
+
+        let source = DefaultSource::test(None).unknown_vars(UnknownVars::Deny);
+        let err = Value::deserialize(source.prepare_struct("strays", &INFO)).unwrap_err();
+        assert_eq!(err.to_string(),
+                   "unrecognized environment variables for `strays`: \
+                    STRAYS_VALEU (did you mean STRAYS_VALUE?)");
+    }
+
+    #[test]
+    fn test_newtype_struct() {
+        #[derive(Deserialize, Debug)]
+        struct Wrapper(Value);
+
+        #[derive(Deserialize, Debug)]
+        struct Outer(Wrapper);
+
+        env::set_var("WRAPPED_VALUE", "3");
+        let source = DefaultSource::test(None);
+        assert_eq!(Wrapper::deserialize(source.prepare("wrapped")).unwrap().0.value, 3);
+        assert_eq!((Outer::deserialize(source.prepare("wrapped")).unwrap().0).0.value, 3);
+        assert!(Wrapper::deserialize(source.prepare("unwrapped")).is_err());
+    }
+
+    #[test]
+    fn test_live_environment() {
+        env::set_var("LIVE_A_VALUE", "1");
+        env::set_var("LIVE_B_VALUE", "1");
+        let source = DefaultSource::test(None);
+
+        assert_eq!(Value::deserialize(source.prepare("live_a")).unwrap().value, 1);
+        env::set_var("LIVE_B_VALUE", "2");
+        assert_eq!(Value::deserialize(source.prepare("live_b")).unwrap().value, 2);
+    }
+
+    #[test]
+    fn test_frozen_environment() {
+        env::set_var("FROZEN_A_VALUE", "1");
+        env::set_var("FROZEN_B_VALUE", "1");
+        let source = DefaultSource::test(None).frozen();
+
+        assert_eq!(Value::deserialize(source.prepare("frozen_a")).unwrap().value, 1);
+        env::set_var("FROZEN_B_VALUE", "2");
+        assert_eq!(Value::deserialize(source.prepare("frozen_b")).unwrap().value, 1);
+
+        source.refresh();
+        assert_eq!(Value::deserialize(source.prepare("frozen_b")).unwrap().value, 2);
+    }
+
+    #[test]
+    fn test_fallback_unprefixed() {
+        env::set_var("PREFIXED_VALUE", "1");
+        env::set_var("VALUE", "2");
+        let source = DefaultSource::test(None).fallback_unprefixed();
+
+        assert_eq!(Value::deserialize(source.prepare("prefixed")).unwrap().value, 1);
+        assert_eq!(Value::deserialize(source.prepare("unprefixed")).unwrap().value, 2);
+        assert!(Value::deserialize(DefaultSource::test(None).prepare("unprefixed")).is_err());
+    }
+
+    #[test]
+    fn test_fallback_unprefixed_flattened() {
+        static INFO: StructInfo = StructInfo::new(&[FieldInfo { flatten: true, ..FieldInfo::new("db") }]);
+        env::set_var("NAME", "app");
+        env::set_var("FALLBACK_NESTED_DB_URL", "postgres://localhost");
+        env::set_var("POOL_SIZE", "8");
+        let source = DefaultSource::test(None).fallback_unprefixed();
+
+        let err = Outer::deserialize(source.prepare_struct("fallback_nested", &INFO)).unwrap_err();
+        assert_eq!(err.to_string(), "missing field `pool_size`");
+
+        env::set_var("FALLBACK_NESTED_DB_POOL_SIZE", "4");
+        assert_eq!(Outer::deserialize(source.prepare_struct("fallback_nested", &INFO)).unwrap(), Outer {
+            name: String::from("app"),
+            db: Inner { url: String::from("postgres://localhost"), pool_size: 4 },
+        });
+    }
+
+    #[test]
+    fn test_upper_casing() {
+        env::set_var("CASED_UPPER_VALUE", "1");
+        env::set_var("casedUpper_value", "2");
+        let source = DefaultSource::test(None).casing(Casing::Upper);
+
+        assert_eq!(Value::deserialize(source.prepare("casedUpper")).unwrap().value, 1);
+    }
+
+    #[test]
+    fn test_snake_casing() {
+        env::set_var("cased_snake_value", "1");
+        env::set_var("CASED_SNAKE_VALUE", "2");
+        let source = DefaultSource::test(None).casing(Casing::Snake).unknown_vars(UnknownVars::Deny);
+
+        assert_eq!(Value::deserialize(source.prepare("casedSnake")).unwrap().value, 1);
+
+        env::set_var("cased_snake_valeu", "3");
+        assert_eq!(Value::deserialize(source.prepare("casedSnake")).unwrap_err().to_string(),
+                   "unrecognized environment variables for `casedSnake`: \
+                    cased_snake_valeu (did you mean cased_snake_value?)");
+    }
+
+    #[test]
+    fn test_lower_casing() {
+        env::set_var("casedlower_value", "1");
+        env::set_var("cased_lower_value", "2");
+        let source = DefaultSource::test(None).casing(Casing::Lower);
+
+        assert_eq!(Value::deserialize(source.prepare("casedLower")).unwrap().value, 1);
+    }
+
+    #[test]
+    fn test_kebab_casing() {
+        static INFO: StructInfo = StructInfo { case: Some(Casing::Kebab), ..StructInfo::new(&[]) };
+        env::set_var("cased-kebab-value", "1");
+        env::set_var("CASED_KEBAB_VALUE", "2");
+        let source = DefaultSource::test(None);
+
+        assert_eq!(Value::deserialize(source.prepare_struct("casedKebab", &INFO)).unwrap().value, 1);
+        assert_eq!(Value::deserialize(source.prepare("casedKebab")).unwrap().value, 2);
+    }
+
+    #[test]
+    fn test_preserve_casing() {
+        env::set_var("casedPreserve_value", "1");
+        env::set_var("CASED_PRESERVE_VALUE", "2");
+        let source = DefaultSource::test(None).casing(Casing::Preserve);
+
+        assert_eq!(Value::deserialize(source.prepare("casedPreserve")).unwrap().value, 1);
+
+        env::remove_var("casedPreserve_value");
+        assert!(Value::deserialize(source.prepare("casedPreserve")).is_err());
+    }
+
+    #[test]
+    fn test_exact_env_name() {
+        static INFO: StructInfo = StructInfo::new(&[FieldInfo { env: Some("EXACT_PORT"), ..FieldInfo::new("value") }]);
+        env::set_var("EXACT_PORT", "1");
+        env::set_var("EXACT_VALUE", "2");
+        let source = DefaultSource::test(None).fallback_unprefixed();
+
+        assert_eq!(Value::deserialize(source.prepare_struct("exact", &INFO)).unwrap().value, 1);
+        assert_eq!(Value::deserialize(source.prepare("exact")).unwrap().value, 2);
+    }
+
+    #[test]
+    fn test_rename() {
+        static INFO: StructInfo = StructInfo::new(&[FieldInfo { rename: Some("port"), ..FieldInfo::new("value") }]);
+        env::set_var("RENAMED_PORT", "1");
+        env::set_var("RENAMED_VALUE", "2");
+        let source = DefaultSource::test(None).unknown_vars(UnknownVars::Deny);
+
+        let err = Value::deserialize(source.prepare_struct("renamed", &INFO)).unwrap_err();
+        assert!(err.to_string().contains("RENAMED_VALUE"));
+        assert_eq!(Value::deserialize(source.prepare("renamed")).unwrap_err().to_string(),
+                   "unrecognized environment variables for `renamed`: RENAMED_PORT");
+
+        env::remove_var("RENAMED_VALUE");
+        assert_eq!(Value::deserialize(source.prepare_struct("renamed", &INFO)).unwrap().value, 1);
+    }
+
+    #[test]
+    fn test_prefix() {
+        static INFO: StructInfo = StructInfo { prefix: Some("acme"), ..StructInfo::new(&[]) };
+        env::set_var("ACME_VALUE", "1");
+        env::set_var("PREFIXED_PACKAGE_VALUE", "2");
+        let toml = toml::from_str("[prefixed_package]\nvalue = 3").unwrap();
+        let source = DefaultSource::test(Some(toml));
+
+        assert_eq!(Value::deserialize(source.prepare_struct("prefixed_package", &INFO)).unwrap().value, 1);
+        env::remove_var("ACME_VALUE");
+        assert_eq!(Value::deserialize(source.prepare_struct("prefixed_package", &INFO)).unwrap().value, 3);
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Ports {
+        ports: Vec<u16>,
+        backup_ports: Option<Vec<u16>>,
+    }
+
+    #[test]
+    fn test_toml_string_lists() {
+        let toml = toml::from_str("[string_lists]\nports = \"10,20, 30\"\nbackup_ports = [40, 50]").unwrap();
+        let source = DefaultSource::test(Some(toml));
+        assert_eq!(Ports::deserialize(source.prepare("string_lists")).unwrap(), Ports {
+            ports: vec![10, 20, 30],
+            backup_ports: Some(vec![40, 50]),
+        });
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Logged {
+        name: String,
+        level: String,
+    }
+
+    #[test]
+    fn test_namespace() {
+        static INFO: StructInfo = StructInfo::new(&[FieldInfo { namespace: Some("logging"), ..FieldInfo::new("level") }]);
+        env::set_var("NAMESPACED_NAME", "app");
+        env::set_var("NAMESPACED_LEVEL", "trace");
+        let toml = toml::from_str("[namespaced]\nlevel = \"warn\"\n[logging]\nlevel = \"info\"").unwrap();
+        let source = DefaultSource::test(Some(toml));
+
+        let logged = Logged::deserialize(source.prepare_struct("namespaced", &INFO)).unwrap();
+        assert_eq!(logged, Logged { name: String::from("app"), level: String::from("info") });
+        env::set_var("LOGGING_LEVEL", "debug");
+        let logged = Logged::deserialize(source.prepare_struct("namespaced", &INFO)).unwrap();
+        assert_eq!(logged.level, "debug");
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct Verbose {
+        #[serde(default)]
+        verbose: bool,
+    }
+
+    #[test]
+    fn test_flag() {
+        static INFO: StructInfo = StructInfo::new(&[FieldInfo { flag: true, ..FieldInfo::new("verbose") }]);
+        env::set_var("FLAG_SET_VERBOSE", "");
+        env::set_var("FLAG_FALSE_VERBOSE", "false");
+        let source = DefaultSource::test(None);
+
+        assert!(Verbose::deserialize(source.prepare_struct("flag_set", &INFO)).unwrap().verbose);
+        assert!(Verbose::deserialize(source.prepare_struct("flag_false", &INFO)).unwrap().verbose);
+        assert!(!Verbose::deserialize(source.prepare_struct("flag_unset", &INFO)).unwrap().verbose);
+        assert!(Verbose::deserialize(source.prepare("flag_set")).is_err());
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct Hosts {
+        hosts: Vec<String>,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Outer {
+        name: String,
+        db: Inner,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Inner {
+        url: String,
+        pool_size: u32,
+    }
+
+    #[test]
+    fn test_flatten() {
+        static INFO: StructInfo = StructInfo::new(&[FieldInfo { flatten: true, ..FieldInfo::new("db") }]);
+        env::set_var("FLATTENED_NAME", "app");
+        env::set_var("FLATTENED_DB_POOL_SIZE", "8");
+        let toml = toml::from_str("[flattened.db]\nurl = \"postgres://localhost\"\npool_size = 4").unwrap();
+        let source = DefaultSource::test(Some(toml)).unknown_vars(UnknownVars::Deny);
+
+        assert_eq!(Outer::deserialize(source.prepare_struct("flattened", &INFO)).unwrap(), Outer {
+            name: String::from("app"),
+            db: Inner { url: String::from("postgres://localhost"), pool_size: 8 },
+        });
+
+        env::set_var("FLATTENED_DB_URI", "postgres://localhost");
+        let err = Outer::deserialize(source.prepare_struct("flattened", &INFO)).unwrap_err();
+        assert_eq!(err.to_string(), "unrecognized environment variables for `flattened`: \
+                                     FLATTENED_DB_URI (did you mean FLATTENED_DB_URL?)");
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct SerdeOuter {
+        name: String,
+        #[serde(flatten)]
+        db: Inner,
+    }
+
+    #[test]
+    fn test_serde_flatten() {
+        static INFO: StructInfo = StructInfo::new(&[FieldInfo::new("name"), FieldInfo::new("db")]);
+        env::set_var("SERDE_FLATTENED_NAME", "app");
+        env::set_var("SERDE_FLATTENED_POOL_SIZE", "8");
+        let toml = toml::from_str("[serde_flattened]\nurl = \"postgres://localhost\"\npool_size = 4").unwrap();
+        let source = DefaultSource::test(Some(toml)).unknown_vars(UnknownVars::Deny);
+
+        let expected = SerdeOuter {
+            name: String::from("app"),
+            db: Inner { url: String::from("postgres://localhost"), pool_size: 8 },
+        };
+        assert_eq!(SerdeOuter::deserialize(source.prepare_struct("serde_flattened", &INFO)).unwrap(), expected);
+        assert_eq!(SerdeOuter::deserialize(source.prepare("serde_flattened")).unwrap(), expected);
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    enum Storage {
+        Local,
+        S3 { bucket: String, region: String },
+    }
+
+    #[test]
+    fn test_enum_from_env() {
+        let source = DefaultSource::test(None);
+
+        env::set_var("ENUM_ENV_STORAGE", "local");
+        assert_eq!(Storage::deserialize(source.prepare("enum_env")).unwrap(), Storage::Local);
+
+        env::set_var("ENUM_ENV_STORAGE", "s3");
+        env::set_var("ENUM_ENV_STORAGE_BUCKET", "logs");
+        env::set_var("ENUM_ENV_STORAGE_REGION", "eu-west-1");
+        let expected = Storage::S3 { bucket: String::from("logs"), region: String::from("eu-west-1") };
+        assert_eq!(Storage::deserialize(source.prepare("enum_env")).unwrap(), expected);
+
+        env::set_var("ENUM_ENV_STORAGE", "gcs");
+        let err = Storage::deserialize(source.prepare("enum_env")).unwrap_err();
+        assert_eq!(err.to_string(), "unknown variant `gcs`, expected `Local` or `S3`");
+    }
+
+    #[test]
+    fn test_enum_from_toml() {
+        let expected = Storage::S3 { bucket: String::from("logs"), region: String::from("eu-west-1") };
+        let toml = toml::from_str("[enum_toml_tagged.storage]\ntype = \"s3\"\nbucket = \"logs\"\nregion = \"eu-west-1\"\n\
+                                   [enum_toml_keyed.storage.s3]\nbucket = \"logs\"\nregion = \"eu-west-1\"\n\
+                                   [enum_toml_unit]\nstorage = \"local\"").unwrap();
+        let source = DefaultSource::test(Some(toml));
+
+        assert_eq!(Storage::deserialize(source.prepare("enum_toml_tagged")).unwrap(), expected);
+        assert_eq!(Storage::deserialize(source.prepare("enum_toml_keyed")).unwrap(), expected);
+        assert_eq!(Storage::deserialize(source.prepare("enum_toml_unit")).unwrap(), Storage::Local);
+
+        let err = Storage::deserialize(source.prepare("enum_toml_missing")).unwrap_err();
+        assert_eq!(err.to_string(), "no variant of `Storage` is selected; set `ENUM_TOML_MISSING_STORAGE`");
+    }
+
+    #[test]
+    fn test_aliases() {
+        static INFO: StructInfo = StructInfo::new(&[FieldInfo {
+            aliases: &["OLD_ALIASED_VALUE", "OLDER_ALIASED_VALUE"],
+            ..FieldInfo::new("value")
+        }]);
+        #[cfg(feature = "log")]
+        logger::init();
+        let source = DefaultSource::test(None).unknown_vars(UnknownVars::Deny);
+        let value = || Value::deserialize(source.prepare_struct("aliased", &INFO)).map(|value| value.value);
+
+        env::set_var("OLDER_ALIASED_VALUE", "2");
+        assert_eq!(value().unwrap(), 2);
+        env::set_var("OLD_ALIASED_VALUE", "1");
+        assert_eq!(value().unwrap(), 1);
+        env::set_var("ALIASED_VALUE", "0");
+        assert_eq!(value().unwrap(), 0);
+
+        #[cfg(feature = "log")]
+        assert_eq!(logger::warnings("ALIASED_VALUE"), vec![
+            "`OLDER_ALIASED_VALUE` is deprecated; use `ALIASED_VALUE` instead",
+            "`OLD_ALIASED_VALUE` is deprecated; use `ALIASED_VALUE` instead",
+            "`OLD_ALIASED_VALUE` is deprecated and is ignored because `ALIASED_VALUE` is set",
+            "`OLDER_ALIASED_VALUE` is deprecated and is ignored because `ALIASED_VALUE` is set",
+        ]);
+
+    }
+
+    #[test]
+    fn test_toml_aliases() {
+        static INFO: StructInfo = StructInfo::new(&[FieldInfo { toml_aliases: &["old_value"], ..FieldInfo::new("value") }]);
+        let toml = toml::from_str("[toml_aliased]\nold_value = 1\n[toml_both]\nold_value = 1\nvalue = 2").unwrap();
+        let source = DefaultSource::test(Some(toml));
+
+        assert_eq!(Value::deserialize(source.prepare_struct("toml_aliased", &INFO)).unwrap().value, 1);
+        assert_eq!(Value::deserialize(source.prepare_struct("toml_both", &INFO)).unwrap().value, 2);
+    }
+
+    #[test]
+    fn test_env_only() {
+        static INFO: StructInfo = StructInfo::new(&[FieldInfo { env_only: true, ..FieldInfo::new("value") }]);
+        let toml = toml::from_str("[env_only]\nvalue = 1").unwrap();
+        let source = DefaultSource::test(Some(toml));
+
+        let err = Value::deserialize(source.prepare_struct("env_only", &INFO)).unwrap_err();
+        assert_eq!(err.to_string(), "missing field `value`");
+
+        let err = Value::deserialize(source.strict_layers().prepare_struct("env_only", &INFO)).unwrap_err();
+        assert_eq!(err.to_string(), "`value` cannot be set by the key `value` of the `Cargo.toml`");
+    }
+
+    #[test]
+    fn test_toml_only() {
+        static INFO: StructInfo = StructInfo::new(&[FieldInfo { toml_only: true, ..FieldInfo::new("value") }]);
+        let toml = toml::from_str("[toml_only]\nvalue = 1").unwrap();
+        let source = DefaultSource::test(Some(toml));
+        env::set_var("TOML_ONLY_VALUE", "2");
+
+        assert_eq!(Value::deserialize(source.prepare_struct("toml_only", &INFO)).unwrap().value, 1);
+
+        let err = Value::deserialize(source.strict_layers().prepare_struct("toml_only", &INFO)).unwrap_err();
+        assert_eq!(err.to_string(), "`value` cannot be set by the environment variable `TOML_ONLY_VALUE`");
+    }
+
+    #[test]
+    fn test_args() {
+        let toml = toml::from_str("[args]\nvalue = 1").unwrap();
+        let source = DefaultSource::test(Some(toml));
+        let value = |args: &[&str]| {
+            let source = source.clone().with_args_from(args.iter().cloned());
+            Value::deserialize(source.prepare("args")).unwrap().value
+        };
+
+        assert_eq!(value(&[]), 1);
+        env::set_var("ARGS_VALUE", "2");
+        assert_eq!(value(&[]), 2);
+        assert_eq!(value(&["--args-value=3", "positional", "--other=4"]), 3);
+        assert_eq!(value(&["--args-value=3", "--args-value=5"]), 5);
+    }
+
+    #[test]
+    fn test_transform_names() {
+        let source = DefaultSource::test(None).transform_names(|name| name.replace('_', ""));
+        env::set_var("TRANSFORMED_DB_HOST", "wrong");
+        env::set_var("TRANSFORMEDDBHOST", "localhost");
+
+        #[derive(Deserialize)]
+        struct Config {
+            db_host: String,
+        }
+        assert_eq!(Config::deserialize(source.prepare("transformed")).unwrap().db_host, "localhost");
+    }
+
+    #[test]
+    fn test_toml_paths() {
+        let dir = env::temp_dir();
+        let defaults = dir.join(format!("configure-defaults-{}.toml", ::std::process::id()));
+        let overrides = dir.join(format!("configure-override-{}.toml", ::std::process::id()));
+        fs::write(&defaults, "[merged]\nthreads = 2\nhost = \"localhost\"\n\
+                              [merged.pool]\nsize = 4\ntimeout = 30\n").unwrap();
+        fs::write(&overrides, "[merged]\nhost = \"example.com\"\n[merged.pool]\nsize = 16\n").unwrap();
+        env::set_var("MERGED_THREADS", "8");
+
+        let source = DefaultSource::from_toml_paths(&[&defaults, &overrides]).unwrap();
+        let toml = source.toml.as_ref().unwrap();
+        assert_eq!(toml["merged"]["host"].as_str(), Some("example.com"));
+        assert_eq!(toml["merged"]["pool"]["size"].as_integer(), Some(16));
+        assert_eq!(toml["merged"]["pool"]["timeout"].as_integer(), Some(30));
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Config {
+            threads: u32,
+            host: String,
+        }
+        let config = Config::deserialize(source.prepare("merged")).unwrap();
+        assert_eq!(config, Config { threads: 8, host: String::from("example.com") });
+
+        let missing = dir.join("configure-missing.toml");
+        assert!(DefaultSource::from_toml_paths(&[&defaults, &missing]).is_err());
+
+        fs::remove_file(&defaults).unwrap();
+        fs::remove_file(&overrides).unwrap();
+    }
+
+    #[test]
+    fn test_embedded_toml() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Config {
+            threads: u32,
+            host: String,
+        }
+
+        env::set_var("EMBEDDED_THREADS", "8");
+        let source = DefaultSource::with_embedded_toml("[embedded]\nthreads = 2\nhost = \"localhost\"\n");
+        let config = Config::deserialize(source.prepare("embedded")).unwrap();
+        assert_eq!(config, Config { threads: 8, host: String::from("localhost") });
+    }
+
+    #[test]
+    #[should_panic(expected = "the embedded TOML configuration is not valid")]
+    fn test_invalid_embedded_toml() {
+        DefaultSource::with_embedded_toml("[embedded\n");
+    }
+
+    #[test]
+    fn test_toml_key() {
+        static INFO: StructInfo = StructInfo::new(&[FieldInfo { toml_key: Some("the-value"), ..FieldInfo::new("value") }]);
+        let toml = toml::from_str("[toml_keyed]\nthe-value = 1\n[toml_field_name]\nvalue = 2").unwrap();
+        let source = DefaultSource::test(Some(toml));
+
+        assert_eq!(Value::deserialize(source.prepare_struct("toml_keyed", &INFO)).unwrap().value, 1);
+        assert!(Value::deserialize(source.prepare_struct("toml_field_name", &INFO)).is_err());
+    }
+
+    /// A logger which records warnings, to check them in tests.
+    #[cfg(feature = "log")]
+    mod logger {
+        use std::sync::Mutex;
+
+        use log::{self, Level, LevelFilter, Log, Metadata, Record};
+
+        static WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+        struct Logger;
+
+        impl Log for Logger {
+            fn enabled(&self, metadata: &Metadata) -> bool {
+                metadata.level() <= Level::Warn
+            }
+
+            fn log(&self, record: &Record) {
+                WARNINGS.lock().unwrap().push(record.args().to_string());
+            }
+
+            fn flush(&self) { }
+        }
+
+        pub fn init() {
+            static LOGGER: Logger = Logger;
+            let _ = log::set_logger(&LOGGER);
+            log::set_max_level(LevelFilter::Warn);
+        }
+
+        /// The warnings so far which mention `name`.
+        pub fn warnings(name: &str) -> Vec<String> {
+            let warnings = WARNINGS.lock().unwrap();
+            warnings.iter().filter(|warning| warning.contains(name)).cloned().collect()
+        }
+    }
+
+    #[test]
+    fn test_list_whitespace() {
+        env::set_var("SPACED_HOSTS", "alpha, beta");
+        assert_eq!(Hosts::deserialize(DefaultSource::test(None).prepare("spaced")).unwrap().hosts,
+                   vec!["alpha", "beta"]);
+        assert_eq!(Hosts::deserialize(DefaultSource::test(None).keep_list_whitespace().prepare("spaced")).unwrap().hosts,
+                   vec!["alpha", " beta"]);
+    }
+
+    #[test]
+    fn test_numbered_sequences() {
+        env::set_var("NUMBERED_HOSTS", "alpha");
+        env::set_var("NUMBERED_HOSTS_1", "beta");
+        env::set_var("NUMBERED_HOSTS_2", "gamma,delta");
+        env::set_var("NUMBERED_HOSTS_4", "epsilon");
+
+        let source = DefaultSource::test(None).numbered_sequences().unknown_vars(UnknownVars::Deny);
+        assert_eq!(Hosts::deserialize(source.prepare("numbered")).unwrap().hosts,
+                   vec!["alpha", "beta", "gamma,delta"]);
+
+        env::set_var("SPLIT_HOSTS", "alpha,beta");
+        assert_eq!(Hosts::deserialize(source.prepare("split")).unwrap().hosts,
+                   vec!["alpha", "beta"]);
+    }
+
+    #[test]
+    fn test_runtime_package_name() {
+        env::set_var("PLUGIN_3_VALUE", "3");
+        let package = format!("plugin_{}", 1 + 2);
+        let source = DefaultSource::test(None);
+        assert_eq!(Value::deserialize(source.prepare(&package)).unwrap().value, 3);
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct Url {
+        url: String,
+    }
+
+    #[test]
+    fn test_interpolation() {
+        env::set_var("INTERPOLATED_HOST", "example.com");
+        env::set_var("INTERPOLATED_PORT", "8080");
+        env::set_var("INTERPOLATED_URL", "https://${INTERPOLATED_HOST}:${INTERPOLATED_PORT}/$$5");
+
+        let source = DefaultSource::test(None).interpolate(MissingVars::Error);
+        assert_eq!(Url::deserialize(source.prepare("interpolated")).unwrap().url,
+                   "https://example.com:8080/$5");
+
+        let source = DefaultSource::test(None);
+        assert_eq!(Url::deserialize(source.prepare("interpolated")).unwrap().url,
+                   "https://${INTERPOLATED_HOST}:${INTERPOLATED_PORT}/$$5");
+    }
+
+    #[test]
+    fn test_interpolation_missing_var() {
+        env::remove_var("MISSING_HOST");
+        env::set_var("MISSING_URL", "https://${MISSING_HOST}/");
+
+        let source = DefaultSource::test(None).interpolate(MissingVars::Error);
+        assert_eq!(Url::deserialize(source.prepare("missing")).unwrap_err().to_string(),
+                   "`MISSING_URL` refers to `MISSING_HOST`, which is not set");
+
+        let source = DefaultSource::test(None).interpolate(MissingVars::Empty);
+        assert_eq!(Url::deserialize(source.prepare("missing")).unwrap().url, "https:///");
+
+        env::set_var("UNTERMINATED_URL", "https://${UNTERMINATED_HOST/");
+        assert!(Url::deserialize(source.prepare("unterminated")).is_err());
+    }
+
+    #[test]
+    fn test_unknown_vars_suggestion() {
+        env::set_var("MISSPELLED_TIMOEUT", "5");
+        env::set_var("MISSPELLED_RETRIES", "3");
+
+        let source = DefaultSource::test(None).unknown_vars(UnknownVars::Deny);
+        let err = Timeout::deserialize(source.prepare("misspelled")).unwrap_err();
+        assert_eq!(err.to_string(),
+                   "unrecognized environment variables for `misspelled`: \
+                    MISSPELLED_RETRIES, MISSPELLED_TIMOEUT (did you mean MISSPELLED_TIMEOUT?)");
+    }
+}
@@ -0,0 +1,73 @@
+/// The edit distance between two strings, counting insertions, deletions,
+/// substitutions and transpositions of adjacent characters (the "optimal
+/// string alignment" variant of Damerau-Levenshtein).
+pub fn distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // Three rows of the distance matrix: two back, one back and current.
+    let mut prev2: Vec<usize> = vec![0; b.len() + 1];
+    let mut prev: Vec<usize> = (0..b.len() + 1).collect();
+    let mut row: Vec<usize> = vec![0; b.len() + 1];
+
+    for i in 1..(a.len() + 1) {
+        row[0] = i;
+        for j in 1..(b.len() + 1) {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            row[j] = (prev[j] + 1).min(row[j - 1] + 1).min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(prev2[j - 2] + 1);
+            }
+        }
+        ::std::mem::swap(&mut prev2, &mut prev);
+        ::std::mem::swap(&mut prev, &mut row);
+    }
+
+    prev[b.len()]
+}
+
+/// The candidate closest to `name`, if it is close enough to plausibly be
+/// what was meant. The allowed distance is a third of the length of `name`,
+/// so short names must match more exactly than long ones.
+pub fn closest<'a, I>(name: &str, candidates: I) -> Option<&'a str>
+    where I: IntoIterator<Item = &'a str>,
+{
+    let threshold = (name.chars().count() / 3).max(1);
+    candidates.into_iter()
+        .map(|candidate| (distance(name, candidate), candidate))
+        .filter(|&(distance, _)| distance <= threshold)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(distance("", ""), 0);
+        assert_eq!(distance("threads", "threads"), 0);
+        assert_eq!(distance("threads", ""), 7);
+        assert_eq!(distance("trheads", "threads"), 1);
+        assert_eq!(distance("thread", "threads"), 1);
+        assert_eq!(distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_transposition() {
+        assert_eq!(closest("TRHEADS", vec!["THREADS", "HOST"]), Some("THREADS"));
+    }
+
+    #[test]
+    fn test_unrelated() {
+        assert_eq!(closest("TIMEOUT_MS", vec!["THREADS", "HOST"]), None);
+    }
+
+    #[test]
+    fn test_closest_candidate() {
+        assert_eq!(closest("MAX_CONNS", vec!["MAX_CONN", "MIN_CONNS", "MAX_CONNS_"]),
+                   Some("MAX_CONN"));
+        assert_eq!(closest("MAX_CONS", vec!["MIN_CONNS", "MAX_CONNS"]), Some("MAX_CONNS"));
+    }
+}
@@ -0,0 +1,131 @@
+use std::borrow::Cow;
+
+use serde::de::*; use serde::de::{Error as ErrorTrait};
+use erased_serde::Error;
+
+use super::env_deserializer::{EnvDeserializer, ParseOptions};
+
+/// Deserializes a value from the `Cargo.toml`, like the `toml::Value` itself,
+/// except that a string can be a comma separated list, as it can be in an
+/// environment variable: `ports = "10,20,30"` is the same as
+/// `ports = [10, 20, 30]`.
+pub struct TomlDeserializer(pub toml::Value, pub ParseOptions);
+
+macro_rules! forward_to_toml {
+    ($($f:ident)*) => {$(
+        fn $f<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where V: Visitor<'de>,
+        {
+            self.0.$f(visitor).map_err(toml_error)
+        }
+    )*}
+}
+
+impl<'de> Deserializer<'de> for TomlDeserializer {
+    type Error = Error;
+
+    forward_to_toml! {
+        deserialize_any deserialize_bool
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_f32 deserialize_f64 deserialize_char
+        deserialize_str deserialize_string deserialize_bytes deserialize_byte_buf
+        deserialize_unit deserialize_map deserialize_identifier deserialize_ignored_any
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>,
+    {
+        match self.0 {
+            toml::Value::String(s)  => EnvDeserializer(Cow::Owned(s), self.1).deserialize_seq(visitor),
+            value                   => value.deserialize_seq(visitor).map_err(toml_error),
+        }
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>,
+    {
+        match self.0 {
+            toml::Value::String(s)  => EnvDeserializer(Cow::Owned(s), self.1).deserialize_tuple(len, visitor),
+            value                   => value.deserialize_tuple(len, visitor).map_err(toml_error),
+        }
+    }
+
+    fn deserialize_tuple_struct<V>(self, name: &'static str, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>,
+    {
+        match self.0 {
+            toml::Value::String(s)  => {
+                EnvDeserializer(Cow::Owned(s), self.1).deserialize_tuple_struct(name, len, visitor)
+            }
+            value                   => value.deserialize_tuple_struct(name, len, visitor).map_err(toml_error),
+        }
+    }
+
+    fn deserialize_unit_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>,
+    {
+        self.0.deserialize_unit_struct(name, visitor).map_err(toml_error)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>,
+    {
+        self.0.deserialize_struct(name, fields, visitor).map_err(toml_error)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>,
+    {
+        self.0.deserialize_enum(name, variants, visitor).map_err(toml_error)
+    }
+}
+
+fn toml_error(err: toml::de::Error) -> Error {
+    Error::custom(err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    fn deserializer(toml: &str) -> TomlDeserializer {
+        let value: toml::Value = toml::from_str(&format!("value = {}", toml)).unwrap();
+        TomlDeserializer(value["value"].clone(), ParseOptions::default())
+    }
+
+    #[test]
+    fn test_lists_in_strings() {
+        assert_eq!(Vec::<u16>::deserialize(deserializer("\"10, 20,30\"")).unwrap(), [10, 20, 30]);
+        assert_eq!(Vec::<u16>::deserialize(deserializer("[10, 20, 30]")).unwrap(), [10, 20, 30]);
+        assert_eq!(Option::<Vec<u16>>::deserialize(deserializer("\"10,20\"")).unwrap(), Some(vec![10, 20]));
+        assert_eq!(<(u8, bool)>::deserialize(deserializer("\"1,true\"")).unwrap(), (1, true));
+        assert_eq!(Vec::<u16>::deserialize(deserializer("\"\"")).unwrap(), Vec::<u16>::new());
+        assert_eq!(String::deserialize(deserializer("\"10,20\"")).unwrap(), "10,20");
+        assert!(Vec::<u16>::deserialize(deserializer("\"10,twenty\"")).is_err());
+    }
+}
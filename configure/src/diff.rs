@@ -0,0 +1,142 @@
+//! Differences between two values of a configuration struct.
+use std::fmt;
+
+use serde::Serialize;
+use serde::de::Error as ErrorTrait;
+use erased_serde::Error;
+
+/// A field whose value differs between two values of a configuration
+/// struct, as returned by the `diff` method `#[configure(diff)]` generates.
+///
+/// The values are rendered with `Debug`; the values of secret fields are
+/// replaced with `<redacted>`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct FieldDiff {
+    /// The name of the field.
+    pub field: &'static str,
+    /// The old value of the field.
+    pub old: String,
+    /// The new value of the field.
+    pub new: String,
+}
+
+impl FieldDiff {
+    /// The difference in a field between `old` and `new`, if there is one.
+    #[doc(hidden)]
+    pub fn field<T: PartialEq + fmt::Debug>(field: &'static str, old: &T, new: &T, secret: bool) -> Option<FieldDiff> {
+        if old == new {
+            return None
+        }
+        let render = |value: &T| if secret { String::from("<redacted>") } else { format!("{:?}", value) };
+        Some(FieldDiff { field, old: render(old), new: render(new) })
+    }
+}
+
+/// `field: old -> new`, as in a log line.
+impl fmt::Display for FieldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {} -> {}", self.field, self.old, self.new)
+    }
+}
+
+/// The fields whose values differ between `old` and `new`, as `(field, old
+/// value, new value)`, for example to log what a reload changed.
+///
+/// Both values are serialized, and the serialized fields are compared. The
+/// fields of nested structs are compared one by one and named with their
+/// path, like `database.url`. The values are rendered as TOML, and a field
+/// which one of the values does not serialize, like a `None`, is rendered as
+/// `<unset>`. The fields are in the order of their names.
+///
+/// Unlike the `diff` method `#[configure(diff)]` generates, this works with
+/// any type which implements `Serialize`, but does not know which fields are
+/// secret, so it does not redact them.
+pub fn diff<T: Serialize>(old: &T, new: &T) -> Result<Vec<(String, String, String)>, Error> {
+    let mut diffs = vec![];
+    diff_tables(&mut diffs, "", &table(old)?, &table(new)?);
+    Ok(diffs)
+}
+
+/// A struct serialized as a TOML table.
+fn table<T: Serialize>(value: &T) -> Result<toml::value::Table, Error> {
+    match toml::Value::try_from(value).map_err(Error::custom)? {
+        toml::Value::Table(table)   => Ok(table),
+        _                           => Err(Error::custom("only structs can be diffed")),
+    }
+}
+
+fn diff_tables(diffs: &mut Vec<(String, String, String)>, path: &str, old: &toml::value::Table, new: &toml::value::Table) {
+    let mut keys = old.keys().chain(new.keys()).collect::<Vec<_>>();
+    keys.sort();
+    keys.dedup();
+    for key in keys {
+        let field = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+        match (old.get(key), new.get(key)) {
+            (Some(toml::Value::Table(old)), Some(toml::Value::Table(new)))  => {
+                diff_tables(diffs, &field, old, new)
+            }
+            (old, new) if old != new                                        => {
+                let render = |value: Option<&toml::Value>| {
+                    value.map_or_else(|| String::from("<unset>"), |value| value.to_string())
+                };
+                diffs.push((field, render(old), render(new)))
+            }
+            _                                                               => { }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_diff() {
+        assert_eq!(FieldDiff::field("threads", &4, &4, false), None);
+        let diff = FieldDiff::field("host", &"a", &"b", false).unwrap();
+        assert_eq!(diff.to_string(), "host: \"a\" -> \"b\"");
+        let diff = FieldDiff::field("password", &"a", &"b", true).unwrap();
+        assert_eq!(diff.to_string(), "password: <redacted> -> <redacted>");
+    }
+
+    #[derive(Serialize, Clone)]
+    struct Config {
+        threads: u32,
+        host: String,
+        tls_cert: Option<String>,
+        database: Database,
+    }
+
+    #[derive(Serialize, Clone)]
+    struct Database {
+        url: String,
+        pool: u32,
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = Config {
+            threads: 4,
+            host: String::from("localhost"),
+            tls_cert: None,
+            database: Database { url: String::from("postgres://localhost"), pool: 8 },
+        };
+        assert_eq!(diff(&old, &old.clone()).unwrap(), []);
+
+        let new = Config { threads: 8, ..old.clone() };
+        assert_eq!(diff(&old, &new).unwrap(), [
+            (String::from("threads"), String::from("4"), String::from("8")),
+        ]);
+
+        let new = Config {
+            tls_cert: Some(String::from("cert.pem")),
+            database: Database { pool: 16, ..old.database.clone() },
+            ..old.clone()
+        };
+        assert_eq!(diff(&old, &new).unwrap(), [
+            (String::from("database.pool"), String::from("8"), String::from("16")),
+            (String::from("tls_cert"), String::from("<unset>"), String::from("\"cert.pem\"")),
+        ]);
+        assert!(diff(&4, &5).is_err());
+    }
+}
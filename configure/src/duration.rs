@@ -0,0 +1,168 @@
+//! Signed durations, for fields like clock offsets.
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+
+/// A duration which may be negative.
+///
+/// `std::time::Duration` cannot be negative, so this can be used for fields
+/// like clock offsets instead. It is parsed from a human readable duration
+/// with an optional sign, like `-30s`, `+1m` or `1h30m`. The units are `ns`,
+/// `us`, `ms`, `s`, `m`, `h` and `d`, and each number may have a fraction,
+/// like `1.5s`. A plain number, such as a `Cargo.toml` integer, is a number
+/// of seconds.
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct SignedDuration {
+    negative: bool,
+    duration: Duration,
+}
+
+impl SignedDuration {
+    /// A positive duration.
+    pub fn positive(duration: Duration) -> SignedDuration {
+        SignedDuration { negative: false, duration }
+    }
+
+    /// A negative duration.
+    pub fn negative(duration: Duration) -> SignedDuration {
+        SignedDuration { negative: duration != Duration::from_secs(0), duration }
+    }
+
+    /// Whether this duration is less than zero.
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// The absolute value of this duration.
+    pub fn abs(&self) -> Duration {
+        self.duration
+    }
+
+    fn from_secs(secs: i64) -> SignedDuration {
+        let duration = Duration::from_secs(secs.unsigned_abs());
+        if secs < 0 { SignedDuration::negative(duration) } else { SignedDuration::positive(duration) }
+    }
+}
+
+/// The error returned when a `SignedDuration` cannot be parsed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseDurationError {
+    duration: String,
+}
+
+impl fmt::Display for ParseDurationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}` is not a duration", self.duration)
+    }
+}
+
+impl Error for ParseDurationError { }
+
+impl FromStr for SignedDuration {
+    type Err = ParseDurationError;
+
+    fn from_str(s: &str) -> Result<SignedDuration, ParseDurationError> {
+        let err = || ParseDurationError { duration: s.to_owned() };
+
+        let (negative, mut rest) = match s.trim().as_bytes().first() {
+            Some(&b'-') => (true, &s.trim()[1..]),
+            Some(&b'+') => (false, &s.trim()[1..]),
+            _           => (false, s.trim()),
+        };
+        if rest.is_empty() {
+            return Err(err())
+        }
+        let unsigned = rest;
+
+        let mut total = Duration::from_secs(0);
+        while !rest.is_empty() {
+            let number_len = rest.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(rest.len());
+            let unit_len = rest[number_len..].find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len() - number_len);
+            let number: f64 = rest[..number_len].parse().map_err(|_| err())?;
+            let unit = match &rest[number_len..number_len + unit_len] {
+                "ns"        => 1e-9,
+                "us" | "µs" => 1e-6,
+                "ms"        => 1e-3,
+                // A plain number is a number of seconds, but only on its own.
+                "" if number_len == unsigned.len() => 1.0,
+                "s"         => 1.0,
+                "m"         => 60.0,
+                "h"         => 60.0 * 60.0,
+                "d"         => 24.0 * 60.0 * 60.0,
+                _           => return Err(err()),
+            };
+            let part = Duration::try_from_secs_f64(number * unit).map_err(|_| err())?;
+            total = total.checked_add(part).ok_or_else(err)?;
+            rest = &rest[number_len + unit_len..];
+        }
+
+        Ok(if negative { SignedDuration::negative(total) } else { SignedDuration::positive(total) })
+    }
+}
+
+impl<'de> Deserialize<'de> for SignedDuration {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<SignedDuration, D::Error> {
+        deserializer.deserialize_any(SignedDurationVisitor)
+    }
+}
+
+struct SignedDurationVisitor;
+
+impl<'de> Visitor<'de> for SignedDurationVisitor {
+    type Value = SignedDuration;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a duration, like `-30s` or `+1m`")
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<SignedDuration, E> {
+        Ok(SignedDuration::from_secs(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<SignedDuration, E> {
+        Ok(SignedDuration::positive(Duration::from_secs(v)))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<SignedDuration, E> {
+        v.parse().map_err(E::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::de::IntoDeserializer;
+    use serde::de::value::Error;
+
+    use super::*;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("-30s".parse(), Ok(SignedDuration::negative(secs(30))));
+        assert_eq!("+1m".parse(), Ok(SignedDuration::positive(secs(60))));
+        assert_eq!("1h30m".parse(), Ok(SignedDuration::positive(secs(90 * 60))));
+        assert_eq!("-1.5s".parse(), Ok(SignedDuration::negative(Duration::from_millis(1500))));
+        assert_eq!("250ms".parse(), Ok(SignedDuration::positive(Duration::from_millis(250))));
+        assert_eq!("-10".parse(), Ok(SignedDuration::negative(secs(10))));
+        assert_eq!("-0s".parse(), Ok(SignedDuration::default()));
+        for invalid in &["", "-", "s", "30x", "1m30", "--1s"] {
+            assert!(invalid.parse::<SignedDuration>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let offset = |value: &str| SignedDuration::deserialize(value.into_deserializer()).map_err(|e: Error| e.to_string());
+        assert_eq!(offset("-30s"), Ok(SignedDuration::negative(secs(30))));
+        assert_eq!(offset("soon"), Err(String::from("`soon` is not a duration")));
+
+        let seconds: Result<_, Error> = SignedDuration::deserialize((-30i64).into_deserializer());
+        assert_eq!(seconds, Ok(SignedDuration::negative(secs(30))));
+    }
+}
@@ -0,0 +1,102 @@
+//! Decoding bytes from strings.
+
+/// Decode hexadecimal bytes, optionally prefixed with `0x`.
+pub fn hex(s: &str) -> Option<Vec<u8>> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+
+    let mut bytes = Vec::with_capacity(s.len() / 2);
+
+    let mut char_indices = s.char_indices();
+
+    while let Some((init, _)) = char_indices.next() {
+        if char_indices.next().is_some() {
+            match u8::from_str_radix(&s[init..(init + 2)], 16) {
+                Ok(byte)    => bytes.push(byte),
+                Err(_)      => return None,
+            }
+        } else {
+            return None
+        }
+    }
+
+    Some(bytes)
+}
+
+/// Decode standard base64, with or without padding.
+pub fn base64(s: &str) -> Option<Vec<u8>> {
+    fn sextet(c: u8) -> Option<u32> {
+        match c {
+            b'A'..=b'Z' => Some((c - b'A') as u32),
+            b'a'..=b'z' => Some((c - b'a') as u32 + 26),
+            b'0'..=b'9' => Some((c - b'0') as u32 + 52),
+            b'+'        => Some(62),
+            b'/'        => Some(63),
+            _           => None,
+        }
+    }
+
+    let s = s.trim_end_matches('=').as_bytes();
+    if s.len() % 4 == 1 {
+        return None
+    }
+
+    let mut bytes = Vec::with_capacity(s.len() * 3 / 4);
+    for chunk in s.chunks(4) {
+        let mut n = 0;
+        for (i, &c) in chunk.iter().enumerate() {
+            n |= sextet(c)? << (18 - 6 * i);
+        }
+        bytes.push((n >> 16) as u8);
+        if chunk.len() > 2 { bytes.push((n >> 8) as u8) }
+        if chunk.len() > 3 { bytes.push(n as u8) }
+    }
+
+    Some(bytes)
+}
+
+/// The decoders used by `#[configure(bytes = "...")]`.
+pub mod decode {
+    /// The UTF-8 bytes of the string itself.
+    pub fn utf8(s: &str) -> Result<Vec<u8>, String> {
+        Ok(s.as_bytes().to_vec())
+    }
+
+    /// Hexadecimal bytes.
+    pub fn hex(s: &str) -> Result<Vec<u8>, String> {
+        super::hex(s).ok_or_else(|| format!("`{}` is not hexadecimal", s))
+    }
+
+    /// Base64 encoded bytes.
+    pub fn base64(s: &str) -> Result<Vec<u8>, String> {
+        super::base64(s).ok_or_else(|| format!("`{}` is not base64", s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_parser() {
+        assert_eq!(hex(""), Some(vec![]));
+        assert_eq!(hex("01"), Some(vec![0x1]));
+        assert_eq!(hex("ff"), Some(vec![0xff]));
+        assert_eq!(hex("01ff70"), Some(vec![0x1, 0xff, 0x70]));
+        assert_eq!(hex("0x04"), Some(vec![0x4]));
+        assert_eq!(hex("0xdeadbeef"), Some(vec![0xde, 0xad, 0xbe, 0xef]));
+        assert_eq!(hex("1"), None);
+        assert_eq!(hex("not hexadecimal"), None);
+    }
+
+    #[test]
+    fn test_base64_parser() {
+        assert_eq!(base64(""), Some(vec![]));
+        assert_eq!(base64("Zg=="), Some(b"f".to_vec()));
+        assert_eq!(base64("Zm8="), Some(b"fo".to_vec()));
+        assert_eq!(base64("Zm9v"), Some(b"foo".to_vec()));
+        assert_eq!(base64("Zm9vYg"), Some(b"foob".to_vec()));
+        assert_eq!(base64("3q2+7w=="), Some(vec![0xde, 0xad, 0xbe, 0xef]));
+        assert_eq!(base64("Z"), None);
+        assert_eq!(base64("not base64"), None);
+    }
+}
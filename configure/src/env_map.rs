@@ -0,0 +1,194 @@
+//! A configuration source which reads environment variables with custom
+//! names.
+use std::env;
+use std::sync::Arc;
+
+use serde::de::{self, Deserializer, Error as ErrorTrait, Visitor};
+use erased_serde::{Error, Deserializer as DynamicDeserializer};
+use heck::ShoutySnakeCase;
+
+use default::env_deserializer::{EnvDeserializer, ParseOptions};
+use source::{ConfigSource, PrepareSource, StructInfo};
+
+type Mapping = dyn Fn(&str, &str) -> String + Send + Sync;
+
+/// A source which reads configuration from environment variables, with
+/// names computed by a function of the package and field names.
+///
+/// This is for naming schemes other than the `{PACKAGE}_{FIELD}` used by
+/// the `DefaultSource`:
+///
+/// ```rust,ignore
+/// CONFIGURATION.set(EnvMapSource::new(|package, field| {
+///     format!("{}__{}", package, field).to_uppercase()
+/// }));
+/// ```
+///
+/// Values are parsed the same way the `DefaultSource` parses them. Unlike
+/// the `DefaultSource`, this source does not fall back to the `Cargo.toml`.
+#[derive(Clone)]
+pub struct EnvMapSource {
+    mapping: Arc<Mapping>,
+}
+
+impl ConfigSource for EnvMapSource {
+    /// Uses the same names as the `DefaultSource`.
+    fn init() -> EnvMapSource {
+        EnvMapSource::new(|package, field| format!("{}_{}", package, field).to_shouty_snake_case())
+    }
+}
+
+impl PrepareSource for EnvMapSource {
+    fn prepare(&self, package: &str) -> Box<dyn DynamicDeserializer<'static>> {
+        self.prepare_struct(package, &StructInfo::EMPTY)
+    }
+
+    fn prepare_struct(&self, package: &str, info: &'static StructInfo) -> Box<dyn DynamicDeserializer<'static>> {
+        let deserializer = EnvMapDeserializer {
+            mapping: self.mapping.clone(),
+            package: package.to_owned(),
+            info,
+        };
+        Box::new(<dyn DynamicDeserializer>::erase(deserializer))
+    }
+
+    fn describe(&self) -> String {
+        String::from("env-map")
+    }
+}
+
+impl EnvMapSource {
+    /// Read the field `field` of the package `package` from the environment
+    /// variable named `mapping(package, field)`.
+    ///
+    /// Fields with a `#[configure(env = "...")]` attribute are still read
+    /// from that variable, and fields with a `#[configure(rename = "...")]`
+    /// attribute pass the new name to the mapping.
+    pub fn new<F>(mapping: F) -> EnvMapSource
+        where F: Fn(&str, &str) -> String + Send + Sync + 'static,
+    {
+        EnvMapSource { mapping: Arc::new(mapping) }
+    }
+}
+
+struct EnvMapDeserializer {
+    mapping: Arc<Mapping>,
+    package: String,
+    info: &'static StructInfo,
+}
+
+impl EnvMapDeserializer {
+    fn value(&self, field: &str) -> Result<Option<String>, Error> {
+        let info = self.info.fields.iter().find(|info| info.name == field);
+        let var_name = match info.and_then(|info| info.env) {
+            Some(name)  => name.to_owned(),
+            None        => {
+                let key = info.and_then(|info| info.rename).unwrap_or(field);
+                (self.mapping)(&self.package, key)
+            }
+        };
+
+        match env::var(&var_name) {
+            Ok(_) | Err(env::VarError::NotUnicode(_)) if info.is_some_and(|info| info.flag) => {
+                Ok(Some(String::from("true")))
+            }
+            Ok(value)                           => Ok(Some(value)),
+            Err(env::VarError::NotPresent)      => Ok(None),
+            Err(env::VarError::NotUnicode(_))   => {
+                Err(Error::custom(format!("`{}` is not valid unicode", var_name)))
+            }
+        }
+    }
+}
+
+impl<'de> Deserializer<'de> for EnvMapDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>,
+    {
+        Err(Error::custom("The env map configuration deserializer only supports \
+                           deserializing structs."))
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>,
+    {
+        let mut values = vec![];
+        for &field in fields {
+            if let Some(value) = self.value(field)? {
+                values.push((field, EnvDeserializer(value.into(), ParseOptions::default())));
+            }
+        }
+        visitor.visit_map(de::value::MapDeserializer::new(values.into_iter()))
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V
+    ) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>,
+    {
+        // A unit struct has no fields to look up.
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit seq
+        bytes byte_buf map tuple_struct newtype_struct
+        tuple ignored_any identifier enum option
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use source::FieldInfo;
+    use super::*;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Config {
+        db_host: String,
+        #[serde(default)]
+        db_port: Option<u16>,
+    }
+
+    #[test]
+    fn test_custom_names() {
+        env::set_var("MAPPED.DBHOST", "example.com");
+        env::set_var("MAPPED_DB_HOST", "ignored.example.com");
+        let source = EnvMapSource::new(|package, field| {
+            format!("{}.{}", package.to_uppercase(), field.replace('_', "").to_uppercase())
+        });
+
+        assert_eq!(Config::deserialize(source.prepare("mapped")).unwrap(), Config {
+            db_host: String::from("example.com"),
+            db_port: None,
+        });
+
+        static INFO: StructInfo = StructInfo::new(&[
+            FieldInfo { rename: Some("host"), ..FieldInfo::new("db_host") },
+            FieldInfo { env: Some("MAPPED_PORT"), ..FieldInfo::new("db_port") },
+        ]);
+        env::set_var("MAPPED.HOST", "renamed.example.com");
+        env::set_var("MAPPED_PORT", "5432");
+        assert_eq!(Config::deserialize(source.prepare_struct("mapped", &INFO)).unwrap(), Config {
+            db_host: String::from("renamed.example.com"),
+            db_port: Some(5432),
+        });
+    }
+
+    #[test]
+    fn test_default_names() {
+        env::set_var("DEFAULT_MAPPED_DB_HOST", "example.com");
+        let config = Config::deserialize(EnvMapSource::init().prepare("default_mapped")).unwrap();
+        assert_eq!(config.db_host, "example.com");
+    }
+}
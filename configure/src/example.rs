@@ -0,0 +1,204 @@
+//! Commented TOML and `.env` templates of configuration structs.
+use std::fmt::Write;
+
+use serde::Serialize;
+use serde::de::Error as ErrorTrait;
+use erased_serde::Error;
+
+use default::env_value;
+
+/// What an example TOML template says about one field of a configuration
+/// struct.
+#[doc(hidden)]
+pub struct ExampleField {
+    /// The key of the field in the serialized struct.
+    pub key: &'static str,
+    /// The environment variable the field is read from, if any.
+    pub var_name: Option<&'static str>,
+    /// The doc comment of the field.
+    pub docs: Option<&'static str>,
+    /// Whether the value of the field should not be displayed.
+    pub secret: bool,
+}
+
+/// Render `value` as a commented TOML template, in which each of `fields`
+/// is preceded by its docs and environment variable. Secret fields, and
+/// fields which serialize to nothing, like `None`, are commented out.
+///
+/// Fields whose values are tables are rendered after all the other fields,
+/// as TOML requires. Keys of the value which are not in `fields` are left
+/// out.
+#[doc(hidden)]
+pub fn example_toml_struct<T: Serialize>(fields: &[ExampleField], value: &T) -> Result<String, Error> {
+    let table = table(value)?;
+    let mut keys = String::new();
+    let mut tables = String::new();
+    for field in fields {
+        let value = table.get(field.key);
+        let is_table = match value {
+            Some(toml::Value::Table(_))     => true,
+            Some(toml::Value::Array(array)) => array.iter().any(|value| value.is_table()),
+            _                               => false,
+        };
+        let out = if is_table && !field.secret { &mut tables } else { &mut keys };
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        for line in field.docs.iter().flat_map(|docs| docs.lines()) {
+            let _ = writeln!(out, "# {}", line.trim());
+        }
+        if let Some(var_name) = field.var_name {
+            let _ = writeln!(out, "# Environment variable: {}", var_name);
+        }
+        match value {
+            _ if field.secret   => { let _ = writeln!(out, "# {} = \"<redacted>\"", key(field.key)); }
+            Some(value)         => out.push_str(&render(field.key, value)?),
+            None                => { let _ = writeln!(out, "# {} =", key(field.key)); }
+        }
+    }
+
+    if !keys.is_empty() && !tables.is_empty() {
+        keys.push('\n');
+    }
+    keys.push_str(&tables);
+    Ok(keys)
+}
+
+/// Render `value` as a commented `.env` file, in which each of `fields` is
+/// preceded by its docs. The values are written as they would be read from
+/// the environment, like lists separated with commas.
+///
+/// Secret fields are left blank, and fields which serialize to nothing, like
+/// `None`, are commented out. Fields which are not read from the
+/// environment, and flattened fields, are left out.
+#[doc(hidden)]
+pub fn example_env_file_struct<T: Serialize>(fields: &[ExampleField], value: &T) -> Result<String, Error> {
+    let table = table(value)?;
+    let mut out = String::new();
+    for field in fields {
+        let var_name = match field.var_name {
+            Some(var_name)  => var_name,
+            None            => continue,
+        };
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        for line in field.docs.iter().flat_map(|docs| docs.lines()) {
+            let _ = writeln!(out, "# {}", line.trim());
+        }
+        let value = match table.get(field.key) {
+            Some(value) => env_value(value)?,
+            None        => None,
+        };
+        match value {
+            _ if field.secret   => { let _ = writeln!(out, "{}=", var_name); }
+            Some(value)         => { let _ = writeln!(out, "{}={}", var_name, value); }
+            None                => { let _ = writeln!(out, "# {}=", var_name); }
+        }
+    }
+    Ok(out)
+}
+
+/// The fields of a struct, serialized as a TOML table.
+fn table<T: Serialize>(value: &T) -> Result<toml::value::Table, Error> {
+    match toml::Value::try_from(value).map_err(Error::custom)? {
+        toml::Value::Table(table)   => Ok(table),
+        _                           => Err(Error::custom("only structs can be rendered as examples")),
+    }
+}
+
+/// `key = value`, or the `[key]` table, as TOML.
+fn render(key: &str, value: &toml::Value) -> Result<String, Error> {
+    let mut table = toml::value::Table::new();
+    table.insert(key.to_owned(), value.clone());
+    let rendered = toml::to_string(&table).map_err(Error::custom)?;
+    Ok(rendered.trim_start_matches('\n').to_owned())
+}
+
+/// The key as it is written in TOML, quoted if it is not a bare key.
+fn key(key: &str) -> String {
+    let bare = !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if bare { key.to_owned() } else { format!("{:?}", key) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Config {
+        threads: u32,
+        hosts: Vec<String>,
+        token: String,
+        tls_cert: Option<String>,
+        database: Database,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Database {
+        url: String,
+    }
+
+    #[test]
+    fn test_example_toml() {
+        let fields = [
+            ExampleField { key: "threads", var_name: Some("APP_THREADS"), docs: Some("Worker threads."), secret: false },
+            ExampleField { key: "database", var_name: None, docs: Some("The database."), secret: false },
+            ExampleField { key: "token", var_name: Some("APP_TOKEN"), docs: None, secret: true },
+            ExampleField { key: "tls_cert", var_name: Some("APP_TLS_CERT"), docs: None, secret: false },
+        ];
+        let config = Config {
+            threads: 4,
+            hosts: vec![],
+            token: String::from("hunter2"),
+            tls_cert: None,
+            database: Database { url: String::from("postgres://localhost") },
+        };
+        let example = example_toml_struct(&fields, &config).unwrap();
+        assert_eq!(example, "\
+# Worker threads.
+# Environment variable: APP_THREADS
+threads = 4
+
+# Environment variable: APP_TOKEN
+# token = \"<redacted>\"
+
+# Environment variable: APP_TLS_CERT
+# tls_cert =
+
+# The database.
+[database]
+url = \"postgres://localhost\"
+");
+        assert!(example_toml_struct(&fields, &vec![1, 2]).is_err());
+    }
+
+    #[test]
+    fn test_example_env_file() {
+        let fields = [
+            ExampleField { key: "threads", var_name: Some("APP_THREADS"), docs: Some("Worker threads."), secret: false },
+            ExampleField { key: "hosts", var_name: Some("APP_HOSTS"), docs: None, secret: false },
+            ExampleField { key: "token", var_name: Some("APP_TOKEN"), docs: None, secret: true },
+            ExampleField { key: "tls_cert", var_name: Some("APP_TLS_CERT"), docs: None, secret: false },
+            ExampleField { key: "database", var_name: None, docs: Some("The database."), secret: false },
+        ];
+        let config = Config {
+            threads: 4,
+            hosts: vec![String::from("alpha"), String::from("beta")],
+            token: String::from("hunter2"),
+            tls_cert: None,
+            database: Database { url: String::from("postgres://localhost") },
+        };
+        let example = example_env_file_struct(&fields, &config).unwrap();
+        assert_eq!(example, "\
+# Worker threads.
+APP_THREADS=4
+
+APP_HOSTS=alpha,beta
+
+APP_TOKEN=
+
+# APP_TLS_CERT=
+");
+    }
+}
@@ -0,0 +1,44 @@
+//! What configuration structs say about their fields, for tooling.
+
+/// A field of a configuration struct, as described by the `FIELDS` constant
+/// `#[derive(Configure)]` generates.
+///
+/// This is everything the derive knows about the field after its
+/// attributes are applied, so tools like admin pages or linters do not have
+/// to parse the struct's docs.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct FieldMetadata {
+    /// The name of the field.
+    pub name: &'static str,
+    /// The environment variable the field is read from, if it is read from
+    /// the environment. Flattened fields, which are read from the variables
+    /// of their own fields, have none.
+    pub env_var: Option<&'static str>,
+    /// The key the field is read from in the `Cargo.toml`, if it is read
+    /// from there.
+    pub toml_key: Option<&'static str>,
+    /// The type of the field, as it is written in the struct.
+    pub ty: &'static str,
+    /// The doc comment of the field, or its `docs` attribute.
+    pub docs: Option<&'static str>,
+    /// Whether the value of the field should never be displayed.
+    pub secret: bool,
+    /// Whether generating the configuration fails if the field is not set.
+    pub required: bool,
+    /// Whether the field is skipped, and never read from the source.
+    pub skip: bool,
+    /// Whether the fields of the field are read as if they were fields of
+    /// the struct.
+    pub flatten: bool,
+}
+
+/// The keys of the fields of a configuration struct which are read from
+/// the environment when the struct is flattened into another one.
+///
+/// This is implemented by `#[derive(Configure)]`, and is required of the
+/// types of flattened fields by the derived `env_vars` function.
+#[doc(hidden)]
+pub trait FlattenedKeys {
+    /// The keys, in the order of the fields.
+    const FLATTENED_KEYS: &'static [&'static str];
+}
@@ -0,0 +1,113 @@
+//! Overriding the configuration source on one thread, for tests.
+use std::cell::RefCell;
+use std::marker::PhantomData;
+
+use source::PrepareSource;
+
+thread_local! {
+    static OVERRIDE: RefCell<Option<Box<dyn PrepareSource>>> = const { RefCell::new(None) };
+}
+
+/// Call `f` with the source overriding the active configuration on this
+/// thread. If there is none, `f` is returned instead.
+pub(crate) fn with_override<R, F>(f: F) -> Result<R, F>
+    where F: FnOnce(&dyn PrepareSource) -> R,
+{
+    OVERRIDE.with(|slot| match *slot.borrow() {
+        Some(ref source)    => Ok(f(&**source)),
+        None                => Err(f),
+    })
+}
+
+/// Overrides the active configuration source on the current thread for as
+/// long as it is alive.
+///
+/// `CONFIGURATION` is global to the process and can only be set once, so
+/// tests which each need a different source would interfere with each other.
+/// Instead, each test can hold a guard; configuration generated on that
+/// test's thread comes from the guard's source, while other threads are
+/// unaffected. When the guard is dropped, the thread goes back to whatever
+/// source it used before.
+///
+/// ```rust,ignore
+/// #[test]
+/// fn reads_threads() {
+///     let _guard = ConfigurationGuard::new(StaticMapSource::new(vec![("MYAPP_THREADS", "4")]));
+///     assert_eq!(Config::generate().unwrap().threads, 4);
+/// }
+/// ```
+///
+/// This requires the `test-util` feature.
+pub struct ConfigurationGuard {
+    previous: Option<Box<dyn PrepareSource>>,
+    // The override belongs to this thread, so the guard must stay on it.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl ConfigurationGuard {
+    /// Use `source` on this thread until the guard is dropped.
+    pub fn new<T: PrepareSource>(source: T) -> ConfigurationGuard {
+        let previous = OVERRIDE.with(|slot| slot.borrow_mut().replace(Box::new(source)));
+        ConfigurationGuard { previous, _not_send: PhantomData }
+    }
+}
+
+impl Drop for ConfigurationGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        OVERRIDE.with(|slot| *slot.borrow_mut() = previous);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use serde::Deserialize;
+
+    use source::CONFIGURATION;
+    use static_map::StaticMapSource;
+    use super::*;
+
+    #[derive(Deserialize, Debug, Default, PartialEq)]
+    #[serde(default)]
+    struct Config {
+        threads: u32,
+    }
+
+    fn generate() -> Config {
+        Config::deserialize(CONFIGURATION.get("guarded")).unwrap()
+    }
+
+    fn source(threads: &str) -> StaticMapSource {
+        StaticMapSource::new(vec![("GUARDED_THREADS", threads)])
+    }
+
+    #[test]
+    fn test_concurrent_guards() {
+        let threads: Vec<_> = (1..5).map(|n| thread::spawn(move || {
+            let _guard = ConfigurationGuard::new(source(&n.to_string()));
+            for _ in 0..100 {
+                assert_eq!(generate(), Config { threads: n });
+            }
+        })).collect();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_nested_guards() {
+        let outer = ConfigurationGuard::new(source("1"));
+        {
+            let _inner = ConfigurationGuard::new(source("2"));
+            assert_eq!(generate(), Config { threads: 2 });
+            assert_eq!(CONFIGURATION.describe(), "static-map");
+        }
+        assert_eq!(generate(), Config { threads: 1 });
+        drop(outer);
+
+        assert!(with_override(|_| ()).is_err());
+    }
+}
@@ -0,0 +1,84 @@
+//! The docs of every configuration in the program, for a `--help-env` flag.
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+/// The package and docs of each configuration, in the order they were
+/// registered.
+static SECTIONS: Mutex<Vec<(&'static str, &'static str)>> = Mutex::new(Vec::new());
+
+/// Add the docs of a configuration of this package to those printed by
+/// `print_env_help`. Docs which are already registered are ignored.
+///
+/// This is called by the code generated with `generate_docs`.
+#[doc(hidden)]
+pub fn register_env_docs(package: &'static str, docs: &'static str) {
+    let mut sections = SECTIONS.lock().unwrap_or_else(|err| err.into_inner());
+    if !sections.contains(&(package, docs)) {
+        sections.push((package, docs));
+    }
+}
+
+/// Write the environment variables of every configuration whose docs are
+/// registered, under a heading for each package, for example in response
+/// to a `--help-env` flag:
+///
+/// ```rust,ignore
+/// if env::args().any(|arg| arg == "--help-env") {
+///     Config::register_docs();
+///     configure::print_env_help(&mut io::stdout())?;
+///     return Ok(())
+/// }
+/// ```
+///
+/// Configuration derived with `generate_docs` is registered when it is
+/// first generated, or with its `register_docs` function. The packages are
+/// in the order their first configuration was registered, and so are the
+/// configurations of each package.
+pub fn print_env_help<W: Write>(out: &mut W) -> io::Result<()> {
+    let sections = SECTIONS.lock().unwrap_or_else(|err| err.into_inner()).clone();
+    let mut packages: Vec<&str> = vec![];
+    for &(package, _) in &sections {
+        if !packages.contains(&package) {
+            packages.push(package);
+        }
+    }
+    for (idx, package) in packages.into_iter().enumerate() {
+        if idx > 0 {
+            writeln!(out)?;
+        }
+        writeln!(out, "# {}", package)?;
+        for &(_, docs) in sections.iter().filter(|section| section.0 == package) {
+            writeln!(out, "\n{}", docs)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_help() {
+        register_env_docs("help_server", "The server variables.");
+        register_env_docs("help_db", "The database variables.");
+        register_env_docs("help_server", "More server variables.");
+        register_env_docs("help_db", "The database variables.");
+
+        let mut out = vec![];
+        print_env_help(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("\
+# help_server
+
+The server variables.
+
+More server variables.
+
+# help_db
+
+The database variables.
+"), "{}", out);
+        assert_eq!(out.matches("The database variables.").count(), 1);
+    }
+}
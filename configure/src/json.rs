@@ -0,0 +1,157 @@
+//! A configuration source which reads a JSON document.
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use erased_serde::Deserializer as DynamicDeserializer;
+use serde_json::{self, Map, Value};
+
+use source::{ConfigSource, PrepareSource};
+
+/// A source which reads configuration from a JSON document.
+///
+/// By default, the configuration for each package is the member of the
+/// top-level object named after the package. For other layouts, use
+/// `with_pointer` to say where each package's configuration lives.
+///
+/// If there is no configuration for a package, it is treated as an empty
+/// object, so every field falls back to its default.
+#[derive(Clone)]
+pub struct JsonFileSource {
+    document: Arc<Value>,
+    pointer: String,
+    /// The file the document was read from, if any.
+    path: Option<PathBuf>,
+}
+
+impl ConfigSource for JsonFileSource {
+    /// Reads `config.json` from the current directory. If there is no such
+    /// file, or it is not valid JSON, no configuration is provided.
+    fn init() -> JsonFileSource {
+        JsonFileSource::open("config.json")
+            .unwrap_or_else(|_| JsonFileSource::from_value(Value::Object(Map::new())))
+    }
+}
+
+impl PrepareSource for JsonFileSource {
+    fn prepare(&self, package: &str) -> Box<dyn DynamicDeserializer<'static>> {
+        let value = self.document.pointer(&self.pointer(package)).cloned()
+            .unwrap_or_else(|| Value::Object(Map::new()));
+        Box::new(<dyn DynamicDeserializer>::erase(value))
+    }
+
+    /// `json-file:`, followed by the path of the file, or `json` if the
+    /// document was not read from a file.
+    fn describe(&self) -> String {
+        match self.path {
+            Some(ref path)  => format!("json-file:{}", path.display()),
+            None            => String::from("json"),
+        }
+    }
+}
+
+impl JsonFileSource {
+    /// Read the JSON document at this path.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<JsonFileSource> {
+        let mut string = String::new();
+        File::open(path.as_ref())?.read_to_string(&mut string)?;
+        let document = serde_json::from_str(&string)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(JsonFileSource {
+            path: Some(path.as_ref().to_owned()),
+            ..JsonFileSource::from_value(document)
+        })
+    }
+
+    /// Use a JSON document which has already been parsed.
+    pub fn from_value(document: Value) -> JsonFileSource {
+        JsonFileSource {
+            document: Arc::new(document),
+            pointer: String::from("/{package}"),
+            path: None,
+        }
+    }
+
+    /// Locate each package's configuration with a JSON Pointer (RFC 6901).
+    ///
+    /// Every occurrence of `{package}` in the template is replaced with the
+    /// package name. For example, with the template `/services/{package}`,
+    /// the configuration for `myapp` is read from
+    /// `{ "services": { "myapp": { ... } } }`.
+    pub fn with_pointer(mut self, template: &str) -> JsonFileSource {
+        self.pointer = template.to_owned();
+        self
+    }
+
+    fn pointer(&self, package: &str) -> String {
+        let escaped = package.replace('~', "~0").replace('/', "~1");
+        self.pointer.replace("{package}", &escaped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use source::PrepareSource;
+    use super::*;
+
+    #[derive(Deserialize, Debug, Eq, PartialEq)]
+    #[serde(default)]
+    struct Service {
+        port: u16,
+        hosts: Vec<String>,
+    }
+
+    impl Default for Service {
+        fn default() -> Service {
+            Service { port: 80, hosts: vec![] }
+        }
+    }
+
+    fn document() -> Value {
+        serde_json::from_str(r#"{
+            "frontend": { "port": 1 },
+            "services": {
+                "frontend": { "port": 8080, "hosts": ["a", "b"] },
+                "backend/v2": { "port": 9090 }
+            }
+        }"#).unwrap()
+    }
+
+    #[test]
+    fn test_top_level_package() {
+        let source = JsonFileSource::from_value(document());
+        assert_eq!(Service::deserialize(source.prepare("frontend")).unwrap(),
+                   Service { port: 1, hosts: vec![] });
+    }
+
+    #[test]
+    fn test_pointer_template() {
+        let source = JsonFileSource::from_value(document()).with_pointer("/services/{package}");
+        assert_eq!(Service::deserialize(source.prepare("frontend")).unwrap(), Service {
+            port: 8080,
+            hosts: vec![String::from("a"), String::from("b")],
+        });
+        assert_eq!(Service::deserialize(source.prepare("backend/v2")).unwrap(),
+                   Service { port: 9090, hosts: vec![] });
+    }
+
+    #[test]
+    fn test_missing_package() {
+        let source = JsonFileSource::from_value(document()).with_pointer("/services/{package}");
+        assert_eq!(Service::deserialize(source.prepare("missing")).unwrap(), Service::default());
+    }
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(JsonFileSource::from_value(document()).describe(), "json");
+
+        let path = ::std::env::temp_dir().join(format!("configure-describe-{}.json", ::std::process::id()));
+        ::std::fs::write(&path, "{}").unwrap();
+        let source = JsonFileSource::open(&path).unwrap();
+        ::std::fs::remove_file(&path).unwrap();
+        assert_eq!(source.describe(), format!("json-file:{}", path.display()));
+    }
+}
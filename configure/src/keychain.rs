@@ -0,0 +1,184 @@
+//! A configuration source which reads secrets from the system keyring.
+use std::sync::Arc;
+
+use serde::de::{self, Deserializer, Error as ErrorTrait, Visitor};
+use erased_serde::{Error, Deserializer as DynamicDeserializer};
+use keyring::Entry;
+
+use default::env_deserializer::{EnvDeserializer, ParseOptions};
+use source::{ConfigSource, PrepareSource};
+
+/// Somewhere a `KeyringSource` can look up secrets.
+///
+/// The system keyring is used by default. Other backends, such as an
+/// in-memory store for tests, can be used with `KeyringSource::with_backend`.
+pub trait KeyringBackend: Send + Sync + 'static {
+    /// The secret stored under this key, if there is one.
+    fn get(&self, key: &str) -> Option<String>;
+}
+
+/// A source which reads configuration from the system keyring: the
+/// Keychain on macOS, the Credential Manager on Windows and the kernel
+/// keyring on Linux.
+///
+/// The value of the field `password` of the package `myapp` is the secret
+/// stored under the key `myapp/password`, and is parsed the same way an
+/// environment variable is. Fields which are not in the keyring are not set,
+/// so this source is usually a layer of a `MultiSource`, providing only the
+/// secret fields.
+///
+/// This source is only available with the `keyring` feature.
+#[derive(Clone)]
+pub struct KeyringSource {
+    backend: Arc<dyn KeyringBackend>,
+}
+
+impl ConfigSource for KeyringSource {
+    /// Reads from the system keyring, in the service `configure`.
+    fn init() -> KeyringSource {
+        KeyringSource::new("configure")
+    }
+}
+
+impl PrepareSource for KeyringSource {
+    fn prepare(&self, package: &str) -> Box<dyn DynamicDeserializer<'static>> {
+        let deserializer = KeyringDeserializer {
+            backend: self.backend.clone(),
+            package: package.to_owned(),
+        };
+        Box::new(<dyn DynamicDeserializer>::erase(deserializer))
+    }
+}
+
+impl KeyringSource {
+    /// Read from the system keyring, in this service. Each key is the user
+    /// name of an entry in the service.
+    pub fn new(service: &str) -> KeyringSource {
+        KeyringSource::with_backend(SystemKeyring { service: service.to_owned() })
+    }
+
+    /// Read from another backend instead of the system keyring.
+    pub fn with_backend<B: KeyringBackend>(backend: B) -> KeyringSource {
+        KeyringSource { backend: Arc::new(backend) }
+    }
+}
+
+struct SystemKeyring {
+    service: String,
+}
+
+impl KeyringBackend for SystemKeyring {
+    fn get(&self, key: &str) -> Option<String> {
+        Entry::new(&self.service, key).and_then(|entry| entry.get_password()).ok()
+    }
+}
+
+struct KeyringDeserializer {
+    backend: Arc<dyn KeyringBackend>,
+    package: String,
+}
+
+impl<'de> Deserializer<'de> for KeyringDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>,
+    {
+        Err(Error::custom("The keyring configuration deserializer only supports \
+                           deserializing structs."))
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>,
+    {
+        // Only the requested fields are looked up, since a keyring cannot
+        // list its entries.
+        let values = fields.iter().filter_map(|&field| {
+            let value = self.backend.get(&format!("{}/{}", self.package, field))?;
+            Some((field, EnvDeserializer(value.into(), ParseOptions::default())))
+        });
+        visitor.visit_map(de::value::MapDeserializer::new(values))
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V
+    ) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>,
+    {
+        // A unit struct has no fields to look up.
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit seq
+        bytes byte_buf map tuple_struct newtype_struct
+        tuple ignored_any identifier enum option
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    use multi::MultiSource;
+    use static_map::StaticMapSource;
+    use super::*;
+
+    struct FakeKeyring(HashMap<String, String>);
+
+    impl KeyringBackend for FakeKeyring {
+        fn get(&self, key: &str) -> Option<String> {
+            self.0.get(key).cloned()
+        }
+    }
+
+    fn keyring(entries: &[(&str, &str)]) -> KeyringSource {
+        let entries = entries.iter().map(|&(key, value)| (key.to_owned(), value.to_owned()));
+        KeyringSource::with_backend(FakeKeyring(entries.collect()))
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Config {
+        user: String,
+        #[serde(default)]
+        password: Option<String>,
+    }
+
+    #[test]
+    fn test_keyring() {
+        let source = keyring(&[("db/user", "admin"), ("db/password", "hunter2"), ("other/user", "guest")]);
+        assert_eq!(Config::deserialize(source.prepare("db")).unwrap(), Config {
+            user: String::from("admin"),
+            password: Some(String::from("hunter2")),
+        });
+        assert!(Config::deserialize(source.prepare("missing")).is_err());
+    }
+
+    #[test]
+    fn test_keyring_layer() {
+        let source = MultiSource::new()
+            .layer(StaticMapSource::new(vec![("DB_USER", "admin"), ("DB_PASSWORD", "")]))
+            .layer(keyring(&[("db/user", "root"), ("db/password", "hunter2")]));
+        assert_eq!(Config::deserialize(source.prepare("db")).unwrap(), Config {
+            user: String::from("admin"),
+            password: Some(String::from("")),
+        });
+
+        let source = MultiSource::new()
+            .layer(StaticMapSource::new(vec![("DB_USER", "admin")]))
+            .layer(keyring(&[("db/user", "root"), ("db/password", "hunter2")]));
+        assert_eq!(Config::deserialize(source.prepare("db")).unwrap(), Config {
+            user: String::from("admin"),
+            password: Some(String::from("hunter2")),
+        });
+    }
+}
@@ -0,0 +1,234 @@
+//! A configuration source which reads a key/value store, like etcd or
+//! Consul.
+use std::collections::HashMap;
+use std::iter::FromIterator;
+use std::sync::Arc;
+
+use serde::de::{self, Deserializer, Error as ErrorTrait, Visitor};
+use erased_serde::{Error, Deserializer as DynamicDeserializer};
+
+use default::env_deserializer::{EnvDeserializer, ParseOptions};
+use source::{ConfigSource, PrepareSource, StructInfo};
+
+/// A key/value store a `KvSource` can read, such as a client of etcd,
+/// Consul or Redis.
+pub trait KvBackend: Send + Sync + 'static {
+    /// The value stored under this key, if there is one.
+    fn get(&self, key: &str) -> Option<String>;
+}
+
+/// A source which reads configuration from a key/value store.
+///
+/// The value of the field `threads` of the package `myapp` is the value
+/// stored under the key `myapp/threads`, and is parsed the same way an
+/// environment variable is. Fields with a `#[configure(rename = "...")]`
+/// attribute are read from the key with the new name. Fields which are not
+/// in the store are not set, so this source can be a layer of a
+/// `MultiSource`:
+///
+/// ```rust,ignore
+/// CONFIGURATION.set(MultiSource::new()
+///     .layer(DefaultSource::init())
+///     .layer(KvSource::new(ConsulBackend::connect("localhost:8500"))));
+/// ```
+///
+/// Each value is looked up every time configuration is generated, so the
+/// backend should cache them if that is expensive.
+pub struct KvSource<B> {
+    backend: Arc<B>,
+}
+
+impl<B> Clone for KvSource<B> {
+    fn clone(&self) -> KvSource<B> {
+        KvSource { backend: self.backend.clone() }
+    }
+}
+
+impl<B: KvBackend + Default> ConfigSource for KvSource<B> {
+    /// Reads from the default backend.
+    fn init() -> KvSource<B> {
+        KvSource::new(B::default())
+    }
+}
+
+impl<B: KvBackend> PrepareSource for KvSource<B> {
+    fn prepare(&self, package: &str) -> Box<dyn DynamicDeserializer<'static>> {
+        self.prepare_struct(package, &StructInfo::EMPTY)
+    }
+
+    fn prepare_struct(&self, package: &str, info: &'static StructInfo) -> Box<dyn DynamicDeserializer<'static>> {
+        let deserializer = KvDeserializer {
+            backend: self.backend.clone(),
+            package: package.to_owned(),
+            info,
+        };
+        Box::new(<dyn DynamicDeserializer>::erase(deserializer))
+    }
+
+    fn describe(&self) -> String {
+        String::from("kv")
+    }
+}
+
+impl<B: KvBackend> KvSource<B> {
+    /// A source which reads from this backend.
+    pub fn new(backend: B) -> KvSource<B> {
+        KvSource { backend: Arc::new(backend) }
+    }
+}
+
+/// A `KvBackend` holding its values in memory, for tests.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryBackend {
+    values: HashMap<String, String>,
+}
+
+impl MemoryBackend {
+    /// An empty store.
+    pub fn new() -> MemoryBackend {
+        MemoryBackend::default()
+    }
+
+    /// Store this value under this key, replacing any value it had.
+    pub fn insert<K: Into<String>, V: Into<String>>(&mut self, key: K, value: V) {
+        self.values.insert(key.into(), value.into());
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for MemoryBackend {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(pairs: I) -> MemoryBackend {
+        let values = pairs.into_iter().map(|(key, value)| (key.into(), value.into())).collect();
+        MemoryBackend { values }
+    }
+}
+
+impl KvBackend for MemoryBackend {
+    fn get(&self, key: &str) -> Option<String> {
+        self.values.get(key).cloned()
+    }
+}
+
+struct KvDeserializer<B> {
+    backend: Arc<B>,
+    package: String,
+    info: &'static StructInfo,
+}
+
+impl<B: KvBackend> KvDeserializer<B> {
+    fn value(&self, field: &str) -> Option<String> {
+        let info = self.info.fields.iter().find(|info| info.name == field);
+        let key = info.and_then(|info| info.rename).unwrap_or(field);
+        let value = self.backend.get(&format!("{}/{}", self.package, key))?;
+        // As with environment variables, a flag is set by being present.
+        if info.is_some_and(|info| info.flag) { Some(String::from("true")) } else { Some(value) }
+    }
+}
+
+impl<'de, B: KvBackend> Deserializer<'de> for KvDeserializer<B> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>,
+    {
+        Err(Error::custom("The key/value configuration deserializer only supports \
+                           deserializing structs."))
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>,
+    {
+        // Only the requested fields are looked up, since a store may not be
+        // able to list its keys.
+        let values = fields.iter().filter_map(|&field| {
+            let value = self.value(field)?;
+            Some((field, EnvDeserializer(value.into(), ParseOptions::default())))
+        });
+        visitor.visit_map(de::value::MapDeserializer::new(values))
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V
+    ) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>,
+    {
+        // A unit struct has no fields to look up.
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit seq
+        bytes byte_buf map tuple_struct newtype_struct
+        tuple ignored_any identifier enum option
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use multi::MultiSource;
+    use source::FieldInfo;
+    use static_map::StaticMapSource;
+    use super::*;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Config {
+        host: String,
+        #[serde(default)]
+        ports: Vec<u16>,
+        #[serde(default)]
+        verbose: bool,
+    }
+
+    fn kv(pairs: &[(&'static str, &'static str)]) -> KvSource<MemoryBackend> {
+        KvSource::new(pairs.iter().cloned().collect())
+    }
+
+    #[test]
+    fn test_kv() {
+        let source = kv(&[("app/host", "example.com"), ("app/ports", "80,443"), ("other/host", "ignored")]);
+        assert_eq!(Config::deserialize(source.prepare("app")).unwrap(), Config {
+            host: String::from("example.com"),
+            ports: vec![80, 443],
+            verbose: false,
+        });
+        assert!(Config::deserialize(source.prepare("missing")).is_err());
+        assert_eq!(source.describe(), "kv");
+    }
+
+    #[test]
+    fn test_kv_field_info() {
+        static INFO: StructInfo = StructInfo::new(&[
+            FieldInfo { rename: Some("hostname"), ..FieldInfo::new("host") },
+            FieldInfo { flag: true, ..FieldInfo::new("verbose") },
+        ]);
+
+        let source = kv(&[("app/hostname", "example.com"), ("app/host", "ignored"), ("app/verbose", "")]);
+        assert_eq!(Config::deserialize(source.prepare_struct("app", &INFO)).unwrap(), Config {
+            host: String::from("example.com"),
+            ports: vec![],
+            verbose: true,
+        });
+    }
+
+    #[test]
+    fn test_kv_layer() {
+        let mut backend = MemoryBackend::new();
+        backend.insert("app/host", "kv.example.com");
+        let source = MultiSource::new()
+            .layer(StaticMapSource::new(vec![("APP_HOST", "env.example.com"), ("APP_PORTS", "8080")]))
+            .layer(KvSource::new(backend));
+        assert_eq!(Config::deserialize(source.prepare("app")).unwrap(), Config {
+            host: String::from("env.example.com"),
+            ports: vec![8080],
+            verbose: false,
+        });
+    }
+}
@@ -56,6 +56,23 @@ extern crate erased_serde;
 extern crate heck;
 extern crate toml;
 
+#[cfg(feature = "log")]
+#[macro_use] extern crate log;
+#[cfg(feature = "json")]
+extern crate serde_json;
+#[cfg(feature = "keyring")]
+extern crate keyring;
+#[cfg(all(windows, feature = "windows"))]
+extern crate winreg;
+/// The `clap` crate, for the `augment_args` and `from_matches` methods
+/// `#[derive(Configure)]` generates with the `clap` feature.
+#[cfg(feature = "clap")]
+pub extern crate clap;
+/// The `serde` crate, for the types `#[derive(Configure)]` generates, so
+/// that they don't depend on the user's imports.
+#[doc(hidden)]
+pub extern crate serde as __serde;
+
 #[allow(unused_imports)]
 #[macro_use] extern crate configure_derive;
 
@@ -65,8 +82,51 @@ extern crate toml;
 pub mod source;
 mod null_deserializer;
 mod default;
+mod diff;
+mod encoding;
+mod example;
+mod fields;
+mod help;
+mod duration;
+mod percent;
+mod secret;
+mod secret_dir;
+mod time_of_day;
+#[doc(hidden)]
+pub mod overlay;
+mod static_map;
+mod env_map;
+mod kv;
+mod multi;
+mod recording;
+mod skip;
+#[cfg(feature = "timing")]
+pub mod timing;
+#[cfg(feature = "test-util")]
+mod guard;
+#[cfg(feature = "keyring")]
+mod keychain;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(all(windows, feature = "windows"))]
+mod registry;
 
 pub use erased_serde::Error as DeserializeError;
+pub use default::{to_env_lines, to_env_lines_struct};
+pub use diff::{diff, FieldDiff};
+pub use duration::{ParseDurationError, SignedDuration};
+#[doc(hidden)]
+pub use example::{example_env_file_struct, example_toml_struct, ExampleField};
+pub use fields::FieldMetadata;
+#[doc(hidden)]
+pub use fields::FlattenedKeys;
+pub use help::print_env_help;
+#[doc(hidden)]
+pub use help::register_env_docs;
+pub use percent::parse_percent;
+pub use secret::Secret;
+pub use source::generated_packages;
+pub use time_of_day::parse_time_of_day;
 
 #[doc(hidden)]
 pub use configure_derive::*;
@@ -91,24 +151,61 @@ pub trait Configure: Sized {
     /// Generate this configuration from the ambient environment.
     fn generate() -> Result<Self, DeserializeError>;
 
-    /// Regenerate this configuration.
+    /// Regenerate this configuration. Every field is replaced, so fields the
+    /// active source does not provide are reset to their defaults; the
+    /// derived `regenerate_present` overwrites only the provided fields.
+    ///
+    /// If the active source has frozen the environment, call
+    /// `CONFIGURATION.refresh()` first to pick up changes made since the
+    /// configuration was last generated.
     fn regenerate(&mut self) -> Result<(), DeserializeError> {
         *self = Self::generate()?;
         Ok(())
     }
+
+    /// Generate this configuration, also returning the warnings about it,
+    /// like those about deprecated fields and aliases which are set. With
+    /// the `log` feature, the warnings are logged as well.
+    fn generate_with_warnings() -> Result<(Self, Vec<String>), DeserializeError> {
+        let (result, warnings) = overlay::collect_warnings(Self::generate);
+        result.map(|cfg| (cfg, warnings))
+    }
 }
 
-/// 
+/// Use a custom source of configuration.
+///
+/// This takes a type which implements `ConfigSource` and sets it as the
+/// active configuration source. It should only be invoked once, in the final
+/// binary, as early as possible.
+///
+/// To choose the source at runtime, pass a `Box<dyn PrepareSource>` value
+/// after the `boxed` keyword instead:
+///
+/// ```rust,ignore
+/// use_config_from!(boxed choose_source(&args));
+/// ```
 #[macro_export]
 macro_rules! use_config_from {
-    ($source:ty)  => {
+    (boxed $source:expr)    => {
+        $crate::source::CONFIGURATION.set_boxed($source)
+    };
+    ($source:ty)            => {
         $crate::source::CONFIGURATION.set(<$source as $crate::source::ConfigSource>::init())
-    }
+    };
 }
 
+/// Use the default configuration source.
+///
+/// This should be invoked once, at the beginning of main.
+///
+/// On `wasm32-unknown-unknown`, which has no process environment, this uses
+/// the `StaticMapSource` instead of the `DefaultSource`.
 #[macro_export]
 macro_rules! use_default_config {
-    ()  => {
-        use_config_from!($crate::source::DefaultSource)
-    }
+    ()  => {{
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        use_config_from!($crate::source::DefaultSource);
+        #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+        use_config_from!($crate::source::StaticMapSource);
+    }}
 }
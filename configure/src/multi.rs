@@ -0,0 +1,370 @@
+//! A configuration source which layers other sources.
+use std::fmt;
+use std::sync::Arc;
+use std::vec;
+
+use serde::de::{self, Deserializer, DeserializeSeed, Error as ErrorTrait, IgnoredAny,
+                IntoDeserializer, MapAccess, Visitor};
+use erased_serde::{Error, Deserializer as DynamicDeserializer};
+
+use source::{ConfigSource, PrepareSource, StructInfo};
+
+/// A source which layers several other sources.
+///
+/// Each field is read from the first layer which provides a value for it;
+/// a field which no layer provides is not set. For example, to keep secrets
+/// out of the environment and the `Cargo.toml`, with the `keyring` feature:
+///
+/// ```rust,ignore
+/// CONFIGURATION.set(MultiSource::new()
+///     .layer(DefaultSource::init())
+///     .layer(KeyringSource::init()));
+/// ```
+#[derive(Clone, Default)]
+pub struct MultiSource {
+    layers: Arc<Vec<Box<dyn PrepareSource>>>,
+}
+
+impl PrepareSource for MultiSource {
+    fn prepare(&self, package: &str) -> Box<dyn DynamicDeserializer<'static>> {
+        self.prepare_struct(package, &StructInfo::EMPTY)
+    }
+
+    fn prepare_struct(&self, package: &str, info: &'static StructInfo) -> Box<dyn DynamicDeserializer<'static>> {
+        let deserializer = MultiDeserializer {
+            layers: self.layers.clone(),
+            package: package.to_owned(),
+            info,
+        };
+        Box::new(<dyn DynamicDeserializer>::erase(deserializer))
+    }
+
+    fn refresh(&self) {
+        for layer in self.layers.iter() {
+            layer.refresh();
+        }
+    }
+
+    /// `multi[...]`, with the description of each layer in order.
+    fn describe(&self) -> String {
+        describe_layers(&self.layers)
+    }
+}
+
+impl MultiSource {
+    /// A source with no layers, which provides no configuration.
+    pub fn new() -> MultiSource {
+        MultiSource::default()
+    }
+
+    /// Add a layer below the layers already added, so that it only provides
+    /// the fields they do not.
+    pub fn layer<T: PrepareSource>(mut self, source: T) -> MultiSource {
+        Arc::get_mut(&mut self.layers)
+            .expect("a MultiSource cannot be changed after it has been cloned")
+            .push(Box::new(source));
+        self
+    }
+}
+
+/// A source which reads each field from one source, falling back to another
+/// for the fields it does not provide.
+///
+/// This is a `MultiSource` with exactly two layers, which does not need to
+/// box them:
+///
+/// ```rust,ignore
+/// use_config_from!(OrSource<KeyringSource, DefaultSource>);
+/// ```
+pub struct OrSource<A, B> {
+    layers: Arc<(A, B)>,
+}
+
+impl<A, B> Clone for OrSource<A, B> {
+    fn clone(&self) -> OrSource<A, B> {
+        OrSource { layers: self.layers.clone() }
+    }
+}
+
+impl<A: ConfigSource, B: ConfigSource> ConfigSource for OrSource<A, B> {
+    fn init() -> OrSource<A, B> {
+        OrSource::new(A::init(), B::init())
+    }
+}
+
+impl<A: PrepareSource, B: PrepareSource> PrepareSource for OrSource<A, B> {
+    fn prepare(&self, package: &str) -> Box<dyn DynamicDeserializer<'static>> {
+        self.prepare_struct(package, &StructInfo::EMPTY)
+    }
+
+    fn prepare_struct(&self, package: &str, info: &'static StructInfo) -> Box<dyn DynamicDeserializer<'static>> {
+        let deserializer = MultiDeserializer {
+            layers: self.layers.clone(),
+            package: package.to_owned(),
+            info,
+        };
+        Box::new(<dyn DynamicDeserializer>::erase(deserializer))
+    }
+
+    fn refresh(&self) {
+        self.layers.0.refresh();
+        self.layers.1.refresh();
+    }
+
+    /// `multi[...]`, with the description of each layer in order.
+    fn describe(&self) -> String {
+        describe_layers(&self.layers)
+    }
+}
+
+impl<A: PrepareSource, B: PrepareSource> OrSource<A, B> {
+    /// A source which reads each field from `first`, or else from `second`.
+    pub fn new(first: A, second: B) -> OrSource<A, B> {
+        OrSource { layers: Arc::new((first, second)) }
+    }
+}
+
+/// The sources a layered deserializer reads from, in order.
+trait Layers: 'static {
+    fn len(&self) -> usize;
+    fn get(&self, idx: usize) -> &dyn PrepareSource;
+}
+
+fn describe_layers<L: Layers>(layers: &L) -> String {
+    let layers = (0..layers.len()).map(|idx| layers.get(idx).describe()).collect::<Vec<_>>();
+    format!("multi[{}]", layers.join(","))
+}
+
+impl Layers for Arc<Vec<Box<dyn PrepareSource>>> {
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+
+    fn get(&self, idx: usize) -> &dyn PrepareSource {
+        &*self[idx]
+    }
+}
+
+impl<A: PrepareSource, B: PrepareSource> Layers for Arc<(A, B)> {
+    fn len(&self) -> usize {
+        2
+    }
+
+    fn get(&self, idx: usize) -> &dyn PrepareSource {
+        match idx {
+            0   => &self.0,
+            _   => &self.1,
+        }
+    }
+}
+
+struct MultiDeserializer<L> {
+    layers: L,
+    package: String,
+    info: &'static StructInfo,
+}
+
+impl<L: Layers> MultiDeserializer<L> {
+    fn layer(&self, idx: usize) -> Box<dyn DynamicDeserializer<'static>> {
+        self.layers.get(idx).prepare_struct(&self.package, self.info)
+    }
+}
+
+impl<L: Layers> Deserializer<'static> for MultiDeserializer<L> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'static>,
+    {
+        Err(Error::custom("The layered configuration deserializer only supports \
+                           deserializing structs."))
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+        where V: Visitor<'static>,
+    {
+        // Find out which fields each layer provides, then read each field
+        // from the first layer which provides it.
+        let mut provided: Vec<(&'static str, usize)> = vec![];
+        for idx in 0..self.layers.len() {
+            let keys = self.layer(idx).deserialize_struct(name, fields, KeysVisitor)?;
+            for key in keys {
+                if let Some(&field) = fields.iter().find(|&&field| field == key) {
+                    if !provided.iter().any(|&(provided, _)| provided == field) {
+                        provided.push((field, idx));
+                    }
+                }
+            }
+        }
+        provided.sort_by_key(|&(field, _)| fields.iter().position(|&f| f == field));
+
+        visitor.visit_map(MultiMapAccess {
+            deserializer: self,
+            name,
+            fields,
+            provided: provided.into_iter(),
+            current: None,
+        })
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V
+    ) -> Result<V::Value, Self::Error>
+        where V: Visitor<'static>,
+    {
+        // A unit struct has no fields to look up.
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        <W: Visitor<'static>>
+        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit seq
+        bytes byte_buf map tuple_struct newtype_struct
+        tuple ignored_any identifier enum option
+    }
+}
+
+struct MultiMapAccess<L> {
+    deserializer: MultiDeserializer<L>,
+    name: &'static str,
+    fields: &'static [&'static str],
+    provided: vec::IntoIter<(&'static str, usize)>,
+    current: Option<(&'static str, usize)>,
+}
+
+impl<L: Layers> MapAccess<'static> for MultiMapAccess<L> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+        where K: DeserializeSeed<'static>,
+    {
+        match self.provided.next() {
+            Some((field, idx))  => {
+                self.current = Some((field, idx));
+                seed.deserialize(field.into_deserializer()).map(Some)
+            }
+            None                => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+        where V: DeserializeSeed<'static>,
+    {
+        let (field, idx) = self.current.take().expect("next_value_seed called before next_key_seed");
+        let visitor = ValueVisitor { field, seed };
+        self.deserializer.layer(idx).deserialize_struct(self.name, self.fields, visitor)
+    }
+}
+
+/// Collects the keys a layer provides, ignoring their values.
+struct KeysVisitor;
+
+impl<'de> Visitor<'de> for KeysVisitor {
+    type Value = Vec<String>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a configuration struct")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where A: MapAccess<'de>,
+    {
+        let mut keys = vec![];
+        while let Some(key) = map.next_key::<String>()? {
+            map.next_value::<IgnoredAny>()?;
+            keys.push(key);
+        }
+        Ok(keys)
+    }
+}
+
+/// Deserializes the value of one field from a layer, ignoring the others.
+struct ValueVisitor<S> {
+    field: &'static str,
+    seed: S,
+}
+
+impl<'de, S: DeserializeSeed<'de>> Visitor<'de> for ValueVisitor<S> {
+    type Value = S::Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a configuration struct")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where A: MapAccess<'de>,
+    {
+        while let Some(key) = map.next_key::<String>()? {
+            if key == self.field {
+                return map.next_value_seed(self.seed)
+            }
+            map.next_value::<IgnoredAny>()?;
+        }
+        Err(de::Error::missing_field(self.field))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use default::DefaultSource;
+    use source::ConfigSource;
+    use static_map::StaticMapSource;
+    use super::*;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Config {
+        host: String,
+        port: u16,
+        #[serde(default)]
+        user: Option<String>,
+    }
+
+    #[test]
+    fn test_layers() {
+        let source = MultiSource::new()
+            .layer(StaticMapSource::new(vec![("LAYERED_HOST", "example.com")]))
+            .layer(StaticMapSource::new(vec![("LAYERED_HOST", "localhost"), ("LAYERED_PORT", "80")]));
+
+        assert_eq!(Config::deserialize(source.prepare("layered")).unwrap(), Config {
+            host: String::from("example.com"),
+            port: 80,
+            user: None,
+        });
+    }
+
+    #[test]
+    fn test_or_source() {
+        let source = OrSource::new(
+            StaticMapSource::new(vec![("EITHER_HOST", "example.com")]),
+            StaticMapSource::new(vec![("EITHER_HOST", "localhost"), ("EITHER_PORT", "80"), ("EITHER_USER", "admin")]),
+        );
+
+        assert_eq!(Config::deserialize(source.prepare("either")).unwrap(), Config {
+            host: String::from("example.com"),
+            port: 80,
+            user: Some(String::from("admin")),
+        });
+    }
+
+    #[test]
+    fn test_no_layers() {
+        assert!(Config::deserialize(MultiSource::new().prepare("layered")).is_err());
+    }
+
+    #[test]
+    fn test_describe() {
+        let source = MultiSource::new()
+            .layer(DefaultSource::init())
+            .layer(OrSource::new(StaticMapSource::new(Vec::<(String, String)>::new()), MultiSource::new()));
+        assert_eq!(source.describe(), "multi[default,multi[static-map,multi[]]]");
+    }
+}
@@ -1,6 +1,22 @@
 pub struct NullDeserializer;
 use serde::de::{self, Deserializer, MapAccess, Error as ErrorTrait, Visitor};
-use erased_serde::Error;
+use erased_serde::{Error, Deserializer as DynamicDeserializer};
+
+use source::PrepareSource;
+
+/// The source used when no source has been set; it never provides any
+/// values.
+pub struct NullSource;
+
+impl PrepareSource for NullSource {
+    fn prepare(&self, _package: &str) -> Box<dyn DynamicDeserializer<'static>> {
+        Box::new(<dyn DynamicDeserializer>::erase(NullDeserializer))
+    }
+
+    fn describe(&self) -> String {
+        String::from("none")
+    }
+}
 
 impl<'de> Deserializer<'de> for NullDeserializer {
     type Error = Error;
@@ -50,7 +66,8 @@ impl<'de> Deserializer<'de> for NullDeserializer {
     ) -> Result<V::Value, Self::Error>
         where V: Visitor<'de>, 
     {
-        visitor.visit_map(NullMapAccessor)
+        // A unit struct has no fields to look up.
+        visitor.visit_unit()
     }
 
     forward_to_deserialize_any! {
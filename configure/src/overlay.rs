@@ -0,0 +1,851 @@
+//! Support for deserializing configuration over an existing value, for
+//! finding which fields a source provided, and for parsing fields from
+//! strings instead of deserializing them.
+//!
+//! This is used by the code generated by `#[derive(Configure)]`.
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::fmt;
+
+use serde::de::{self, Deserialize, DeserializeSeed, Deserializer, IgnoredAny, IntoDeserializer, MapAccess, Visitor};
+use erased_serde::Error;
+
+use default::env_deserializer::{EnvDeserializer, ParseOptions};
+use skip::SkipFields;
+use source::StructInfo;
+
+pub use erased_serde::Deserializer as DynamicDeserializer;
+pub use encoding::decode;
+
+/// Erase the type of `deserializer`, ignoring the values it provides for the
+/// fields `info` marks as skipped, as if it were the deserializer of a
+/// source.
+pub fn erase<D>(deserializer: D, info: &'static StructInfo) -> Box<dyn DynamicDeserializer<'static>>
+    where D: Deserializer<'static> + 'static,
+{
+    SkipFields::wrap(Box::new(<dyn DynamicDeserializer>::erase(deserializer)), info)
+}
+
+/// Assigns one field of `T` by deserializing it from the deserializer.
+pub type Assign<'a, T> = &'a dyn Fn(&mut T, &mut dyn DynamicDeserializer<'static>) -> Result<(), Error>;
+
+/// Deserialize a struct from `deserializer`, assigning only the fields it
+/// provides into `target` and leaving the others as they were.
+///
+/// `assign[i]` assigns the field named `fields[i]`.
+pub fn overlay<D, T>(
+    deserializer: D,
+    target: &mut T,
+    fields: &'static [&'static str],
+    assign: &[Assign<T>],
+) -> Result<Vec<&'static str>, D::Error>
+    where D: Deserializer<'static>,
+{
+    deserializer.deserialize_struct("", fields, OverlayVisitor { target, fields, assign })
+}
+
+struct OverlayVisitor<'a, 'b, T: 'a> {
+    target: &'a mut T,
+    fields: &'static [&'static str],
+    assign: &'b [Assign<'b, T>],
+}
+
+impl<'a, 'b, T> Visitor<'static> for OverlayVisitor<'a, 'b, T> {
+    type Value = Vec<&'static str>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a configuration struct")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where A: MapAccess<'static>,
+    {
+        let mut assigned = vec![];
+        while let Some(key) = map.next_key::<String>()? {
+            match self.fields.iter().position(|&field| field == key) {
+                Some(idx)   => {
+                    map.next_value_seed(AssignSeed {
+                        target: &mut *self.target,
+                        assign: self.assign[idx],
+                    })?;
+                    assigned.push(self.fields[idx]);
+                }
+                None        => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(assigned)
+    }
+}
+
+struct AssignSeed<'a, 'b, T: 'a> {
+    target: &'a mut T,
+    assign: Assign<'b, T>,
+}
+
+impl<'a, 'b, T> DeserializeSeed<'static> for AssignSeed<'a, 'b, T> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<(), D::Error>
+        where D: Deserializer<'static>,
+    {
+        let mut erased = <dyn DynamicDeserializer>::erase(deserializer);
+        (self.assign)(self.target, &mut erased).map_err(de::Error::custom)
+    }
+}
+
+/// Checks that one field of a struct can be deserialized from the
+/// deserializer, discarding its value.
+pub type Check<'a> = &'a dyn Fn(&mut dyn DynamicDeserializer<'static>) -> Result<(), Error>;
+
+/// Having failed to deserialize a struct with `error`, deserialize it again
+/// from `deserializer` without stopping at the first invalid field, and
+/// return an error listing every invalid field.
+///
+/// `check[i]` checks the field named `fields[i]`. If `error` is the only
+/// error, it is returned as it is.
+pub fn collect_errors<D>(error: Error, deserializer: D, fields: &'static [&'static str], check: &[Check]) -> Error
+    where D: Deserializer<'static>,
+{
+    let error = error.to_string();
+    let mut errors = deserializer.deserialize_struct("", fields, CollectVisitor { fields, check })
+        .unwrap_or_else(|err| vec![(None, err.to_string())]);
+    // The first error may not be about any one field, like a missing
+    // required field, in which case the check does not find it again.
+    if !errors.iter().any(|found| found.1 == error) {
+        errors.insert(0, (None, error.clone()));
+    }
+
+    if errors.len() == 1 {
+        return de::Error::custom(error)
+    }
+    let errors = errors.into_iter().map(|(field, msg)| match field {
+        Some(field) => format!("`{}`: {}", field, msg),
+        None        => msg,
+    }).collect::<Vec<_>>();
+    de::Error::custom(format!("{} configuration errors: {}", errors.len(), errors.join("; ")))
+}
+
+struct CollectVisitor<'a> {
+    fields: &'static [&'static str],
+    check: &'a [Check<'a>],
+}
+
+impl<'a> Visitor<'static> for CollectVisitor<'a> {
+    type Value = Vec<(Option<&'static str>, String)>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a configuration struct")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where A: MapAccess<'static>,
+    {
+        let mut errors = vec![];
+        loop {
+            // An error from the key leaves no way to continue to the next.
+            let key = match map.next_key::<String>() {
+                Ok(Some(key))   => key,
+                Ok(None)        => break,
+                Err(err)        => {
+                    errors.push((None, err.to_string()));
+                    break
+                }
+            };
+            let result = match self.fields.iter().position(|&field| field == key) {
+                Some(idx)   => map.next_value_seed(CheckSeed(self.check[idx])).map_err(|err| (Some(self.fields[idx]), err)),
+                None        => map.next_value::<IgnoredAny>().map(|_| ()).map_err(|err| (None, err)),
+            };
+            if let Err((field, err)) = result {
+                errors.push((field, err.to_string()));
+            }
+        }
+        Ok(errors)
+    }
+}
+
+struct CheckSeed<'a>(Check<'a>);
+
+impl<'a> DeserializeSeed<'static> for CheckSeed<'a> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<(), D::Error>
+        where D: Deserializer<'static>,
+    {
+        let mut erased = <dyn DynamicDeserializer>::erase(deserializer);
+        (self.0)(&mut erased).map_err(de::Error::custom)
+    }
+}
+
+/// Deserialize `T` from `deserializer`, also returning the keys of the
+/// fields it provided.
+pub fn with_present<D, T>(deserializer: D) -> Result<(T, Vec<String>), D::Error>
+    where D: Deserializer<'static>,
+          T: Deserialize<'static>,
+{
+    let present = RefCell::new(vec![]);
+    let value = T::deserialize(RecordKeys { deserializer, present: &present })?;
+    Ok((value, present.into_inner()))
+}
+
+struct RecordKeys<'a, D> {
+    deserializer: D,
+    present: &'a RefCell<Vec<String>>,
+}
+
+impl<'a, D: Deserializer<'static>> Deserializer<'static> for RecordKeys<'a, D> {
+    type Error = D::Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'static>,
+    {
+        self.deserializer.deserialize_any(visitor)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+        where V: Visitor<'static>,
+    {
+        let visitor = RecordVisitor { visitor, present: self.present };
+        self.deserializer.deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'static>,
+    {
+        let visitor = RecordVisitor { visitor, present: self.present };
+        self.deserializer.deserialize_map(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        <W: Visitor<'static>>
+        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit seq
+        bytes byte_buf tuple_struct newtype_struct unit_struct
+        tuple ignored_any identifier enum option
+    }
+}
+
+struct RecordVisitor<'a, V> {
+    visitor: V,
+    present: &'a RefCell<Vec<String>>,
+}
+
+impl<'a, 'de, V: Visitor<'de>> Visitor<'de> for RecordVisitor<'a, V> {
+    type Value = V::Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.visitor.expecting(f)
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+        where A: MapAccess<'de>,
+    {
+        self.visitor.visit_map(RecordMapAccess { map, present: self.present })
+    }
+}
+
+struct RecordMapAccess<'a, A> {
+    map: A,
+    present: &'a RefCell<Vec<String>>,
+}
+
+impl<'a, 'de, A: MapAccess<'de>> MapAccess<'de> for RecordMapAccess<'a, A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+        where K: DeserializeSeed<'de>,
+    {
+        match self.map.next_key::<String>()? {
+            Some(key)   => {
+                self.present.borrow_mut().push(key.clone());
+                seed.deserialize(key.into_deserializer()).map(Some)
+            }
+            None        => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+        where V: DeserializeSeed<'de>,
+    {
+        self.map.next_value_seed(seed)
+    }
+}
+
+/// Wrap `deserializer`, failing if it does not provide all of the `required`
+/// fields. Each field is paired with a hint saying how to set it.
+///
+/// The error lists every missing field, and is returned before the struct
+/// being deserialized could complain about the first of them itself.
+pub fn require<D>(deserializer: D, required: &'static [(&'static str, &'static str)]) -> Required<D>
+    where D: Deserializer<'static>,
+{
+    Required { deserializer, required }
+}
+
+/// A deserializer which fails if required fields are missing.
+pub struct Required<D> {
+    deserializer: D,
+    required: &'static [(&'static str, &'static str)],
+}
+
+impl<D: Deserializer<'static>> Deserializer<'static> for Required<D> {
+    type Error = D::Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'static>,
+    {
+        self.deserializer.deserialize_any(visitor)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+        where V: Visitor<'static>,
+    {
+        let visitor = RequiredVisitor { visitor, required: self.required };
+        self.deserializer.deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'static>,
+    {
+        let visitor = RequiredVisitor { visitor, required: self.required };
+        self.deserializer.deserialize_map(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        <W: Visitor<'static>>
+        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit seq
+        bytes byte_buf tuple_struct newtype_struct unit_struct
+        tuple ignored_any identifier enum option
+    }
+}
+
+struct RequiredVisitor<V> {
+    visitor: V,
+    required: &'static [(&'static str, &'static str)],
+}
+
+impl<'de, V: Visitor<'de>> Visitor<'de> for RequiredVisitor<V> {
+    type Value = V::Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.visitor.expecting(f)
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+        where A: MapAccess<'de>,
+    {
+        let provided = vec![false; self.required.len()];
+        self.visitor.visit_map(RequiredMapAccess { map, required: self.required, provided })
+    }
+}
+
+struct RequiredMapAccess<A> {
+    map: A,
+    required: &'static [(&'static str, &'static str)],
+    provided: Vec<bool>,
+}
+
+impl<'de, A: MapAccess<'de>> MapAccess<'de> for RequiredMapAccess<A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+        where K: DeserializeSeed<'de>,
+    {
+        match self.map.next_key::<String>()? {
+            Some(key)   => {
+                if let Some(idx) = self.required.iter().position(|&(field, _)| field == key) {
+                    self.provided[idx] = true;
+                }
+                seed.deserialize(key.into_deserializer()).map(Some)
+            }
+            None        => {
+                let missing = self.required.iter().zip(&self.provided)
+                    .filter(|&(_, &provided)| !provided)
+                    .map(|(&(_, hint), _)| hint)
+                    .collect::<Vec<_>>();
+                if missing.is_empty() {
+                    Ok(None)
+                } else {
+                    Err(de::Error::custom(format!("missing required configuration: {}", missing.join("; "))))
+                }
+            }
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+        where V: DeserializeSeed<'de>,
+    {
+        self.map.next_value_seed(seed)
+    }
+}
+
+thread_local! {
+    /// The warnings reported on this thread, while `collect_warnings` is
+    /// collecting them.
+    static WARNINGS: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Report a warning about the configuration being generated: it is logged
+/// with the `log` feature, and returned by `Configure::generate_with_warnings`.
+pub fn warn(msg: String) {
+    #[cfg(feature = "log")]
+    warn!("{}", msg);
+    WARNINGS.with(|warnings| {
+        if let Some(ref mut warnings) = *warnings.borrow_mut() {
+            warnings.push(msg);
+        }
+    });
+}
+
+/// Call `f`, returning the warnings reported on this thread while it ran,
+/// as well as its result.
+pub(crate) fn collect_warnings<T, F: FnOnce() -> T>(f: F) -> (T, Vec<String>) {
+    let outer = WARNINGS.with(|warnings| warnings.borrow_mut().replace(vec![]));
+    let result = f();
+    let collected = WARNINGS.with(|warnings| warnings.replace(outer)).unwrap_or_default();
+    // Warnings collected by an outer call include those of this one.
+    WARNINGS.with(|warnings| {
+        if let Some(ref mut outer) = *warnings.borrow_mut() {
+            outer.extend(collected.iter().cloned());
+        }
+    });
+    (result, collected)
+}
+
+/// Wrap `deserializer`, warning about each of the `deprecated` fields which
+/// it provides. Each field is paired with its warning.
+pub fn deprecated<D>(deserializer: D, deprecated: &'static [(&'static str, &'static str)]) -> Deprecated<D>
+    where D: Deserializer<'static>,
+{
+    Deprecated { deserializer, deprecated }
+}
+
+/// A deserializer which warns when deprecated fields are provided.
+pub struct Deprecated<D> {
+    deserializer: D,
+    deprecated: &'static [(&'static str, &'static str)],
+}
+
+impl<D: Deserializer<'static>> Deserializer<'static> for Deprecated<D> {
+    type Error = D::Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'static>,
+    {
+        self.deserializer.deserialize_any(visitor)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+        where V: Visitor<'static>,
+    {
+        let visitor = DeprecatedVisitor { visitor, deprecated: self.deprecated };
+        self.deserializer.deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'static>,
+    {
+        let visitor = DeprecatedVisitor { visitor, deprecated: self.deprecated };
+        self.deserializer.deserialize_map(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        <W: Visitor<'static>>
+        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit seq
+        bytes byte_buf tuple_struct newtype_struct unit_struct
+        tuple ignored_any identifier enum option
+    }
+}
+
+struct DeprecatedVisitor<V> {
+    visitor: V,
+    deprecated: &'static [(&'static str, &'static str)],
+}
+
+impl<'de, V: Visitor<'de>> Visitor<'de> for DeprecatedVisitor<V> {
+    type Value = V::Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.visitor.expecting(f)
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+        where A: MapAccess<'de>,
+    {
+        self.visitor.visit_map(DeprecatedMapAccess { map, deprecated: self.deprecated })
+    }
+}
+
+struct DeprecatedMapAccess<A> {
+    map: A,
+    deprecated: &'static [(&'static str, &'static str)],
+}
+
+impl<'de, A: MapAccess<'de>> MapAccess<'de> for DeprecatedMapAccess<A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+        where K: DeserializeSeed<'de>,
+    {
+        match self.map.next_key::<String>()? {
+            Some(key)   => {
+                if let Some(&(_, warning)) = self.deprecated.iter().find(|&&(field, _)| field == key) {
+                    warn(warning.to_owned());
+                }
+                seed.deserialize(key.into_deserializer()).map(Some)
+            }
+            None        => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+        where V: DeserializeSeed<'de>,
+    {
+        self.map.next_value_seed(seed)
+    }
+}
+
+/// Assigns one field of `T` by parsing it from strings: the elements of a
+/// list, or else a single string.
+pub type Parse<'a, T> = &'a dyn Fn(&mut T, &[String]) -> Result<(), String>;
+
+/// Fields which are parsed from strings rather than deserialized.
+///
+/// The deserializer returned by `wrap` hides these fields from the struct
+/// being deserialized, keeping their values as strings; `assign` then parses
+/// them into the struct.
+pub struct Parsed {
+    fields: &'static [(&'static str, bool)],
+    all: &'static [&'static str],
+    values: RefCell<Vec<(usize, Vec<String>)>>,
+}
+
+impl Parsed {
+    /// Parse `fields`, out of `all` the fields of the struct. Each field is
+    /// paired with whether it is a list.
+    ///
+    /// The parsed fields are usually `#[serde(skip)]`, so the struct does not
+    /// ask for them when it is deserialized; the source is asked for `all`
+    /// of the fields instead.
+    pub fn new(fields: &'static [(&'static str, bool)], all: &'static [&'static str]) -> Parsed {
+        Parsed { fields, all, values: RefCell::new(vec![]) }
+    }
+
+    /// Wrap `deserializer`, hiding the parsed fields from whatever is
+    /// deserialized from it.
+    pub fn wrap<D: Deserializer<'static>>(&self, deserializer: D) -> ParseFields<'_, D> {
+        ParseFields { deserializer, parsed: self }
+    }
+
+    /// Parse the values of the fields which were provided into `target`.
+    ///
+    /// `parse[i]` parses the field named `fields[i]`.
+    pub fn assign<T>(self, target: &mut T, parse: &[Parse<T>]) -> Result<(), Error> {
+        for (idx, values) in self.values.into_inner() {
+            parse[idx](target, &values).map_err(de::Error::custom)?;
+        }
+        Ok(())
+    }
+}
+
+/// A deserializer which hides the fields of a `Parsed`.
+pub struct ParseFields<'a, D> {
+    deserializer: D,
+    parsed: &'a Parsed,
+}
+
+impl<'a, D: Deserializer<'static>> Deserializer<'static> for ParseFields<'a, D> {
+    type Error = D::Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'static>,
+    {
+        self.deserializer.deserialize_any(visitor)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+        where V: Visitor<'static>,
+    {
+        let visitor = ParseVisitor { visitor, parsed: self.parsed, fields };
+        self.deserializer.deserialize_struct(name, self.parsed.all, visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'static>,
+    {
+        let visitor = ParseVisitor { visitor, parsed: self.parsed, fields: self.parsed.all };
+        self.deserializer.deserialize_map(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        <W: Visitor<'static>>
+        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit seq
+        bytes byte_buf tuple_struct newtype_struct unit_struct
+        tuple ignored_any identifier enum option
+    }
+}
+
+struct ParseVisitor<'a, V> {
+    visitor: V,
+    parsed: &'a Parsed,
+    fields: &'static [&'static str],
+}
+
+impl<'a, 'de, V: Visitor<'de>> Visitor<'de> for ParseVisitor<'a, V> {
+    type Value = V::Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.visitor.expecting(f)
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+        where A: MapAccess<'de>,
+    {
+        self.visitor.visit_map(ParseMapAccess { map, parsed: self.parsed, fields: self.fields })
+    }
+}
+
+struct ParseMapAccess<'a, A> {
+    map: A,
+    parsed: &'a Parsed,
+    fields: &'static [&'static str],
+}
+
+impl<'a, 'de, A: MapAccess<'de>> MapAccess<'de> for ParseMapAccess<'a, A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+        where K: DeserializeSeed<'de>,
+    {
+        while let Some(key) = self.map.next_key::<String>()? {
+            if let Some(idx) = self.parsed.fields.iter().position(|&(field, _)| field == key) {
+                let RawValues(values) = self.map.next_value_seed(RawValues(self.parsed.fields[idx].1))?;
+                self.parsed.values.borrow_mut().push((idx, values));
+                continue
+            }
+            // A field the struct skips, which it was only asked for because
+            // it was one of `all` the fields.
+            if self.parsed.all.contains(&&key[..]) && !self.fields.contains(&&key[..]) {
+                self.map.next_value::<IgnoredAny>()?;
+                continue
+            }
+            return seed.deserialize(key.into_deserializer()).map(Some)
+        }
+        Ok(None)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+        where V: DeserializeSeed<'de>,
+    {
+        self.map.next_value_seed(seed)
+    }
+}
+
+/// A deserializer of a command line argument, which reads it like an
+/// environment variable.
+pub fn arg(value: &str) -> Box<dyn DynamicDeserializer<'static>> {
+    let deserializer = EnvDeserializer(Cow::Owned(value.to_owned()), ParseOptions::default());
+    Box::new(<dyn DynamicDeserializer>::erase(deserializer))
+}
+
+/// Deserialize the string form of a value, for parsing it: the elements of
+/// a list, or else a single string.
+pub fn raw_values<'de, D: Deserializer<'de>>(deserializer: D, list: bool) -> Result<Vec<String>, D::Error> {
+    RawValues(list).deserialize(deserializer).map(|RawValues(values)| values)
+}
+
+/// Seeds deserializing the string forms of a value. A list is split into its
+/// elements by the source.
+struct RawValues<T>(T);
+
+impl<'de> DeserializeSeed<'de> for RawValues<bool> {
+    type Value = RawValues<Vec<String>>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        let values = match self.0 {
+            true    => Vec::<RawString>::deserialize(deserializer)?.into_iter().map(|RawString(s)| s).collect(),
+            false   => vec![RawString::deserialize(deserializer)?.0],
+        };
+        Ok(RawValues(values))
+    }
+}
+
+/// The string form of a value: an environment variable as it was set, or a
+/// toml value rendered as a string.
+struct RawString(String);
+
+impl<'de> Deserialize<'de> for RawString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<RawString, D::Error> {
+        deserializer.deserialize_string(RawStringVisitor)
+    }
+}
+
+struct RawStringVisitor;
+
+impl<'de> Visitor<'de> for RawStringVisitor {
+    type Value = RawString;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a string, number or boolean")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<RawString, E> {
+        Ok(RawString(v.to_string()))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<RawString, E> {
+        Ok(RawString(v.to_string()))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<RawString, E> {
+        Ok(RawString(v.to_string()))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<RawString, E> {
+        Ok(RawString(v.to_string()))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<RawString, E> {
+        Ok(RawString(v.to_owned()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<RawString, E> {
+        Ok(RawString(v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use default::DefaultSource;
+    use source::PrepareSource;
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Config {
+        threads: u32,
+        host: String,
+    }
+
+    #[test]
+    fn test_overlay() {
+        ::std::env::set_var("OVERLAY_HOST", "example.com");
+        let source = DefaultSource::test(None);
+
+        let mut cfg = Config { threads: 4, host: String::from("localhost") };
+        let assigned = overlay(source.prepare("overlay"), &mut cfg, &["threads", "host"], &[
+            &|cfg, d| { cfg.threads = Deserialize::deserialize(d)?; Ok(()) },
+            &|cfg, d| { cfg.host = Deserialize::deserialize(d)?; Ok(()) },
+        ]).unwrap();
+
+        assert_eq!(assigned, vec!["host"]);
+        assert_eq!(cfg, Config { threads: 4, host: String::from("example.com") });
+    }
+
+    #[derive(Deserialize, Debug, Default, PartialEq)]
+    #[serde(default)]
+    struct Defaulted {
+        threads: u32,
+        host: String,
+    }
+
+    #[test]
+    fn test_with_present() {
+        ::std::env::set_var("PRESENT_THREADS", "8");
+        let source = DefaultSource::test(None);
+
+        let (cfg, present) = with_present::<_, Defaulted>(source.prepare("present")).unwrap();
+        assert_eq!(cfg, Defaulted { threads: 8, host: String::new() });
+        assert_eq!(present, vec!["threads"]);
+    }
+
+    #[test]
+    fn test_require() {
+        ::std::env::set_var("REQUIRE_THREADS", "8");
+        let source = DefaultSource::test(None);
+
+        let required = &[("threads", "set REQUIRE_THREADS")];
+        let cfg = Defaulted::deserialize(require(source.prepare("require"), required)).unwrap();
+        assert_eq!(cfg, Defaulted { threads: 8, host: String::new() });
+
+        let required = &[("threads", "set REQUIRE_THREADS"), ("host", "set REQUIRE_HOST")];
+        let err = Defaulted::deserialize(require(source.prepare("require"), required)).unwrap_err();
+        assert_eq!(err.to_string(), "missing required configuration: set REQUIRE_HOST");
+    }
+
+    #[test]
+    fn test_deprecated() {
+        ::std::env::set_var("DEPRECATED_THREADS", "8");
+        let source = DefaultSource::test(None);
+        let deprecated_fields = &[("threads", "threads is deprecated"), ("host", "host is deprecated")];
+
+        let (cfg, warnings) = collect_warnings(|| {
+            Defaulted::deserialize(deprecated(source.prepare("deprecated"), deprecated_fields)).unwrap()
+        });
+        assert_eq!(cfg, Defaulted { threads: 8, host: String::new() });
+        assert_eq!(warnings, ["threads is deprecated"]);
+
+        // Nested collections each see the warnings reported within them.
+        let ((_, inner), outer) = collect_warnings(|| {
+            warn(String::from("outer"));
+            collect_warnings(|| warn(String::from("inner")))
+        });
+        assert_eq!(inner, ["inner"]);
+        assert_eq!(outer, ["outer", "inner"]);
+    }
+
+    #[test]
+    fn test_parsed() {
+        ::std::env::set_var("PARSED_THREADS", "8");
+        ::std::env::set_var("PARSED_HOST", "example.com");
+        let source = DefaultSource::test(None);
+
+        let parsed = Parsed::new(&[("host", false)], &["threads", "host"]);
+        let mut cfg = Defaulted::deserialize(parsed.wrap(source.prepare("parsed"))).unwrap();
+        assert_eq!(cfg, Defaulted { threads: 8, host: String::new() });
+
+        parsed.assign(&mut cfg, &[&|cfg, values| { cfg.host = values[0].to_uppercase(); Ok(()) }]).unwrap();
+        assert_eq!(cfg, Defaulted { threads: 8, host: String::from("EXAMPLE.COM") });
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_collect_errors() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        ::std::env::set_var("COLLECT_THREADS", "eight");
+        ::std::env::set_var("COLLECT_HOST", OsStr::from_bytes(b"\xff"));
+        let source = DefaultSource::test(None);
+
+        let err = Defaulted::deserialize(source.prepare("collect")).unwrap_err();
+        let err = collect_errors(err, source.prepare("collect"), &["threads", "host"], &[
+            &|d| { u32::deserialize(d)?; Ok(()) },
+            &|d| { String::deserialize(d)?; Ok(()) },
+        ]);
+        assert_eq!(err.to_string(), "2 configuration errors: \
+                                     `threads`: invalid digit found in string; \
+                                     `host`: `COLLECT_HOST` is not valid unicode");
+    }
+}
@@ -0,0 +1,40 @@
+//! Parsing percentages, for fields like a share of the CPU.
+use std::fmt::Display;
+use std::ops::Div;
+use std::str::FromStr;
+
+/// Parse a fraction, which is divided by 100 if it ends with `%`: both
+/// `50%` and `0.5` are `0.5`.
+///
+/// This is the parser of `#[configure(percent)]` fields, and can also be
+/// used with `parse_with`.
+pub fn parse_percent<T>(s: &str) -> Result<T, String>
+    where T: FromStr + Div<Output = T> + From<u8>,
+          T::Err: Display,
+{
+    let s = s.trim();
+    match s.strip_suffix('%') {
+        Some(percent)   => {
+            let percent = T::from_str(percent.trim_end()).map_err(|err| format!("`{}` is not a percentage: {}", s, err))?;
+            Ok(percent / T::from(100))
+        }
+        None            => T::from_str(s).map_err(|err| err.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percent() {
+        assert_eq!(parse_percent::<f64>("50%"), Ok(0.5));
+        assert_eq!(parse_percent::<f64>(" 12.5 % "), Ok(0.125));
+        assert_eq!(parse_percent::<f32>("150%"), Ok(1.5));
+        assert_eq!(parse_percent::<f64>("0.5"), Ok(0.5));
+        assert_eq!(parse_percent::<f64>("2"), Ok(2.0));
+        assert_eq!(parse_percent::<f64>("half%"), Err(String::from("`half%` is not a percentage: invalid float literal")));
+        assert!(parse_percent::<f64>("%").is_err());
+        assert!(parse_percent::<f64>("50%%").is_err());
+    }
+}
@@ -0,0 +1,172 @@
+//! A configuration source which records what is read from another source.
+use std::sync::{Arc, Mutex};
+
+use serde::de::{Deserializer, Visitor};
+use erased_serde::{Error, Deserializer as DynamicDeserializer};
+
+use source::{ConfigSource, PrepareSource, StructInfo};
+
+/// A source which wraps another source, recording the fields of every
+/// configuration struct generated from it.
+///
+/// Clones of a recording source share their record, so a binary can keep a
+/// clone to inspect after generating its configuration, for example to
+/// write out an example `.env` file:
+///
+/// ```rust,ignore
+/// let recording = RecordingSource::new(DefaultSource::init());
+/// CONFIGURATION.set(recording.clone());
+/// let cfg = Config::generate()?;
+/// for (package, field) in recording.recorded() {
+///     println!("{}.{}", package, field);
+/// }
+/// ```
+///
+/// Each field is recorded whether or not the wrapped source provides it,
+/// and only once however many times it is generated. Skipped fields are
+/// never read, so they are not recorded.
+pub struct RecordingSource<S> {
+    source: Arc<S>,
+    recorded: Arc<Mutex<Vec<(String, String)>>>,
+}
+
+impl<S> Clone for RecordingSource<S> {
+    fn clone(&self) -> RecordingSource<S> {
+        RecordingSource {
+            source: self.source.clone(),
+            recorded: self.recorded.clone(),
+        }
+    }
+}
+
+impl<S: ConfigSource> ConfigSource for RecordingSource<S> {
+    fn init() -> RecordingSource<S> {
+        RecordingSource::new(S::init())
+    }
+}
+
+impl<S: PrepareSource> PrepareSource for RecordingSource<S> {
+    fn prepare(&self, package: &str) -> Box<dyn DynamicDeserializer<'static>> {
+        self.prepare_struct(package, &StructInfo::EMPTY)
+    }
+
+    fn prepare_struct(&self, package: &str, info: &'static StructInfo) -> Box<dyn DynamicDeserializer<'static>> {
+        let deserializer = RecordingDeserializer {
+            deserializer: self.source.prepare_struct(package, info),
+            package: package.to_owned(),
+            info,
+            recorded: self.recorded.clone(),
+        };
+        Box::new(<dyn DynamicDeserializer>::erase(deserializer))
+    }
+
+    fn refresh(&self) {
+        self.source.refresh()
+    }
+
+    /// `recording[...]`, with the description of the recorded source.
+    fn describe(&self) -> String {
+        format!("recording[{}]", self.source.describe())
+    }
+}
+
+impl<S: PrepareSource> RecordingSource<S> {
+    /// A source which reads from `source`, recording what is read.
+    pub fn new(source: S) -> RecordingSource<S> {
+        RecordingSource {
+            source: Arc::new(source),
+            recorded: Arc::default(),
+        }
+    }
+
+    /// The package and name of every field read so far, in the order they
+    /// were first read.
+    pub fn recorded(&self) -> Vec<(String, String)> {
+        self.recorded.lock().unwrap_or_else(|err| err.into_inner()).clone()
+    }
+}
+
+struct RecordingDeserializer {
+    deserializer: Box<dyn DynamicDeserializer<'static>>,
+    package: String,
+    info: &'static StructInfo,
+    recorded: Arc<Mutex<Vec<(String, String)>>>,
+}
+
+impl Deserializer<'static> for RecordingDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'static>,
+    {
+        self.deserializer.deserialize_any(visitor)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+        where V: Visitor<'static>,
+    {
+        let mut recorded = self.recorded.lock().unwrap_or_else(|err| err.into_inner());
+        for &field in fields {
+            let skipped = self.info.fields.iter().any(|info| info.skip && info.name == field);
+            let is_recorded = recorded.iter().any(|(package, recorded)| {
+                *package == self.package && recorded == field
+            });
+            if !skipped && !is_recorded {
+                recorded.push((self.package.clone(), field.to_owned()));
+            }
+        }
+        drop(recorded);
+        self.deserializer.deserialize_struct(name, fields, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        <W: Visitor<'static>>
+        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit seq
+        bytes byte_buf map tuple_struct newtype_struct unit_struct
+        tuple ignored_any identifier enum option
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use source::FieldInfo;
+    use static_map::StaticMapSource;
+    use super::*;
+
+    #[derive(Deserialize, Debug, Default, PartialEq)]
+    #[serde(default)]
+    struct Config {
+        threads: u32,
+        timeout: u64,
+        internal: bool,
+    }
+
+    #[test]
+    fn test_recorded_fields() {
+        static INFO: StructInfo = StructInfo::new(&[FieldInfo { skip: true, ..FieldInfo::new("internal") }]);
+        let source = RecordingSource::new(StaticMapSource::new(vec![("RECORDED_THREADS", "4")]));
+        let recording = source.clone();
+
+        let config = Config::deserialize(source.prepare_struct("recorded", &INFO)).unwrap();
+        assert_eq!(config, Config { threads: 4, ..Config::default() });
+        Config::deserialize(source.prepare_struct("recorded", &INFO)).unwrap();
+        Config::deserialize(source.prepare("other")).unwrap();
+
+        let recorded = recording.recorded();
+        let recorded = recorded.iter().map(|(package, field)| (&package[..], &field[..])).collect::<Vec<_>>();
+        assert_eq!(recorded, [
+            ("recorded", "threads"),
+            ("recorded", "timeout"),
+            ("other", "threads"),
+            ("other", "timeout"),
+            ("other", "internal"),
+        ]);
+    }
+}
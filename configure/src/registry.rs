@@ -0,0 +1,201 @@
+//! A configuration source which reads the Windows registry.
+use std::io;
+
+use serde::de::{self, Deserializer, Error as ErrorTrait, IntoDeserializer, Visitor};
+use erased_serde::{Error, Deserializer as DynamicDeserializer};
+use winreg::{RegKey, RegValue};
+use winreg::enums::*;
+use winreg::types::FromRegValue;
+
+use default::env_deserializer::{EnvDeserializer, EnvSeqDeserializer, ParseOptions};
+use source::{ConfigSource, PrepareSource};
+
+/// The registry hive a `RegistrySource` reads from.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Hive {
+    /// `HKEY_LOCAL_MACHINE`, for settings which apply to every user.
+    LocalMachine,
+    /// `HKEY_CURRENT_USER`, for settings which apply to the current user.
+    CurrentUser,
+}
+
+/// A source which reads configuration from the Windows registry, where
+/// settings pushed through Group Policy usually live.
+///
+/// Each package's configuration is read from a subkey of the source's key
+/// named after the package, and each field from the value with the field's
+/// name. String values are parsed the same way environment variables are,
+/// `REG_DWORD` and `REG_QWORD` values are integers, and `REG_MULTI_SZ`
+/// values are sequences with one element per string.
+///
+/// This source is only available on Windows, with the `windows` feature.
+#[derive(Clone, Debug)]
+pub struct RegistrySource {
+    hive: Hive,
+    key_path: String,
+}
+
+impl ConfigSource for RegistrySource {
+    /// Reads from `HKEY_LOCAL_MACHINE\SOFTWARE\Policies`.
+    fn init() -> RegistrySource {
+        RegistrySource::new(Hive::LocalMachine, "SOFTWARE\\Policies")
+    }
+}
+
+impl PrepareSource for RegistrySource {
+    fn prepare(&self, package: &str) -> Box<dyn DynamicDeserializer<'static>> {
+        let deserializer = RegistryDeserializer {
+            values: self.values(package).unwrap_or_default(),
+        };
+        Box::new(<dyn DynamicDeserializer>::erase(deserializer))
+    }
+}
+
+impl RegistrySource {
+    /// Read configuration from subkeys of this key.
+    pub fn new(hive: Hive, key_path: &str) -> RegistrySource {
+        RegistrySource {
+            hive,
+            key_path: key_path.to_owned(),
+        }
+    }
+
+    fn values(&self, package: &str) -> io::Result<Vec<(String, Value)>> {
+        let hive = match self.hive {
+            Hive::LocalMachine  => RegKey::predef(HKEY_LOCAL_MACHINE),
+            Hive::CurrentUser   => RegKey::predef(HKEY_CURRENT_USER),
+        };
+        let key = hive.open_subkey(format!("{}\\{}", self.key_path, package))?;
+
+        let mut values = vec![];
+        for value in key.enum_values() {
+            let (name, value) = value?;
+            if let Some(value) = Value::from_reg_value(&value)? {
+                values.push((name, value));
+            }
+        }
+        Ok(values)
+    }
+}
+
+enum Value {
+    String(String),
+    Multi(Vec<String>),
+}
+
+impl Value {
+    fn from_reg_value(value: &RegValue) -> io::Result<Option<Value>> {
+        Ok(Some(match value.vtype {
+            REG_SZ | REG_EXPAND_SZ  => Value::String(String::from_reg_value(value)?),
+            REG_DWORD               => Value::String(u32::from_reg_value(value)?.to_string()),
+            REG_QWORD               => Value::String(u64::from_reg_value(value)?.to_string()),
+            REG_MULTI_SZ            => Value::Multi(Vec::<String>::from_reg_value(value)?),
+            _                       => return Ok(None),
+        }))
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = Box<dyn DynamicDeserializer<'de>>;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        match self {
+            Value::String(string)   => {
+                Box::new(<dyn DynamicDeserializer>::erase(EnvDeserializer(string.into(), ParseOptions::default())))
+            }
+            Value::Multi(strings)   => {
+                Box::new(<dyn DynamicDeserializer>::erase(EnvSeqDeserializer(strings, ParseOptions::default())))
+            }
+        }
+    }
+}
+
+struct RegistryDeserializer {
+    values: Vec<(String, Value)>,
+}
+
+impl<'de> Deserializer<'de> for RegistryDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>,
+    {
+        Err(Error::custom("The registry configuration deserializer only supports \
+                           deserializing structs."))
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>,
+    {
+        // Registry value names are case insensitive.
+        let values = self.values.into_iter().filter_map(|(name, value)| {
+            fields.iter().find(|field| field.eq_ignore_ascii_case(&name)).map(|&field| (field, value))
+        });
+        visitor.visit_map(de::value::MapDeserializer::new(values))
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V
+    ) -> Result<V::Value, Self::Error>
+        where V: Visitor<'de>,
+    {
+        self.deserialize_struct(name, &[], visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit seq
+        bytes byte_buf map tuple_struct newtype_struct
+        tuple ignored_any identifier enum option
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use winreg::RegKey;
+    use winreg::enums::HKEY_CURRENT_USER;
+
+    use source::PrepareSource;
+    use super::*;
+
+    const TEST_KEY: &str = "Software\\configure-test";
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Config {
+        name: String,
+        threads: u32,
+        limit: u64,
+        hosts: Vec<String>,
+        ports: Vec<u16>,
+    }
+
+    #[test]
+    fn test_registry_source() {
+        let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+        let (key, _) = hkcu.create_subkey(format!("{}\\registry", TEST_KEY)).unwrap();
+        key.set_value("name", &"example").unwrap();
+        key.set_value("threads", &4u32).unwrap();
+        key.set_value("limit", &(1u64 << 40)).unwrap();
+        key.set_value("hosts", &vec!["alpha", "beta,gamma"]).unwrap();
+        key.set_value("Ports", &"80,443").unwrap();
+
+        let source = RegistrySource::new(Hive::CurrentUser, TEST_KEY);
+        let result = Config::deserialize(source.prepare("registry"));
+        hkcu.delete_subkey_all(TEST_KEY).unwrap();
+
+        assert_eq!(result.unwrap(), Config {
+            name: String::from("example"),
+            threads: 4,
+            limit: 1 << 40,
+            hosts: vec![String::from("alpha"), String::from("beta,gamma")],
+            ports: vec![80, 443],
+        });
+    }
+}
@@ -0,0 +1,95 @@
+//! Secret values, which are never displayed.
+use std::fmt;
+
+use serde::de::{Deserialize, Deserializer};
+
+/// A value, like a password, which is never displayed.
+///
+/// A secret is deserialized just like the value it holds, from any source,
+/// but its `Debug` and `Display` implementations print `***` instead of the
+/// value, so that logging a configuration struct does not leak it:
+///
+/// ```rust,ignore
+/// #[derive(Configure, Deserialize, Debug)]
+/// pub struct Config {
+///     user: String,
+///     password: Secret<String>,
+/// }
+/// ```
+///
+/// The value can only be read with `expose` or `into_inner`. Secrets do not
+/// implement `Serialize`, for the same reason; mark the field with
+/// `#[configure(secret)]` to redact it from `to_env_lines` instead.
+#[derive(Copy, Clone, Default, Eq, Hash, PartialEq)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    /// A secret holding this value.
+    pub fn new(value: T) -> Secret<T> {
+        Secret(value)
+    }
+
+    /// The value of this secret.
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+
+    /// The value of this secret, consuming it.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Secret<T> {
+    fn from(value: T) -> Secret<T> {
+        Secret(value)
+    }
+}
+
+impl<T> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("***")
+    }
+}
+
+impl<T> fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("***")
+    }
+}
+
+// Secrets are transparent, rather than newtype structs, so that they are
+// read the same way as their value by every deserializer.
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Secret<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Secret<T>, D::Error> {
+        T::deserialize(deserializer).map(Secret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use default::env_deserializer::{EnvDeserializer, ParseOptions};
+    use super::*;
+
+    fn deserializer(s: &'static str) -> EnvDeserializer<'static> {
+        EnvDeserializer(Cow::Borrowed(s), ParseOptions::default())
+    }
+
+    #[test]
+    fn test_secret_values() {
+        let password = Secret::<String>::deserialize(deserializer("hunter2")).unwrap();
+        assert_eq!(password.expose(), "hunter2");
+        assert_eq!(format!("{:?}", password), "***");
+        assert_eq!(format!("{}", password), "***");
+
+        let port = Secret::<u16>::deserialize(deserializer("5432")).unwrap();
+        assert_eq!(port.into_inner(), 5432);
+        assert!(Secret::<u16>::deserialize(deserializer("none")).is_err());
+
+        let token = Option::<Secret<String>>::deserialize(toml::Value::from("abc")).unwrap();
+        assert_eq!(token, Some(Secret::new(String::from("abc"))));
+        assert_eq!(format!("{:?}", token), "Some(***)");
+    }
+}
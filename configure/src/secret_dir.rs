@@ -0,0 +1,148 @@
+//! A configuration source which reads a directory of secret files.
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use erased_serde::Deserializer as DynamicDeserializer;
+
+use default::DefaultSource;
+use source::{ConfigSource, PrepareSource, StructInfo};
+
+/// A source which reads configuration from a directory with one file per
+/// value, like the secrets Docker and Kubernetes mount into containers.
+///
+/// Each file is read like an environment variable with the same name, after
+/// the name is uppercased and its dashes and dots are replaced with
+/// underscores, so the field `password` of the package `myapp` is read from
+/// the file `MYAPP_PASSWORD`, `myapp_password` or `myapp-password`:
+///
+/// ```rust,ignore
+/// CONFIGURATION.set(OrSource::new(
+///     DefaultSource::init(),
+///     SecretDirSource::new("/var/run/secrets/myapp"),
+/// ));
+/// ```
+///
+/// A single trailing newline is removed from each value. Hidden files and
+/// subdirectories, like the `..data` links Kubernetes creates, are ignored,
+/// and a directory which does not exist provides no values. The directory
+/// is read when configuration is first generated, and again after
+/// `CONFIGURATION.refresh()`.
+#[derive(Clone)]
+pub struct SecretDirSource {
+    inner: DefaultSource,
+    dir: PathBuf,
+}
+
+impl ConfigSource for SecretDirSource {
+    /// Reads `/run/secrets`, where Docker mounts secrets.
+    fn init() -> SecretDirSource {
+        SecretDirSource::new("/run/secrets")
+    }
+}
+
+impl PrepareSource for SecretDirSource {
+    fn prepare(&self, package: &str) -> Box<dyn DynamicDeserializer<'static>> {
+        self.inner.prepare(package)
+    }
+
+    fn prepare_struct(&self, package: &str, info: &'static StructInfo) -> Box<dyn DynamicDeserializer<'static>> {
+        self.inner.prepare_struct(package, info)
+    }
+
+    fn refresh(&self) {
+        self.inner.refresh()
+    }
+
+    /// `secret-dir:`, followed by the path of the directory.
+    fn describe(&self) -> String {
+        format!("secret-dir:{}", self.dir.display())
+    }
+}
+
+impl SecretDirSource {
+    /// A source which reads the files in this directory.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> SecretDirSource {
+        let dir = dir.into();
+        let read = dir.clone();
+        SecretDirSource {
+            inner: DefaultSource::with_vars(move || read_dir(&read)),
+            dir,
+        }
+    }
+}
+
+/// The values of the files in `dir`, keyed by their variable names. Files
+/// which cannot be read as UTF-8 are skipped.
+fn read_dir(dir: &Path) -> HashMap<OsString, OsString> {
+    let mut vars = HashMap::new();
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_)      => return vars,
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let name = match entry.file_name().into_string() {
+            Ok(ref name) if !name.starts_with('.')  => name.to_uppercase().replace(['-', '.'], "_"),
+            _                                       => continue,
+        };
+        // Follow links, which is how Kubernetes mounts each file.
+        let path = entry.path();
+        if !fs::metadata(&path).map(|metadata| metadata.is_file()).unwrap_or(false) {
+            continue
+        }
+        if let Ok(mut value) = fs::read_to_string(&path) {
+            if value.ends_with('\n') {
+                value.pop();
+                if value.ends_with('\r') {
+                    value.pop();
+                }
+            }
+            vars.insert(name.into(), value.into());
+        }
+    }
+    vars
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Deserialize, Debug, Default, PartialEq)]
+    #[serde(default)]
+    struct Config {
+        user: String,
+        password: String,
+        port: u16,
+    }
+
+    #[test]
+    fn test_secret_dir() {
+        let dir = env::temp_dir().join(format!("configure-secrets-{}", ::std::process::id()));
+        fs::create_dir_all(dir.join("..data")).unwrap();
+        fs::write(dir.join("MOUNTED_USER"), "admin").unwrap();
+        fs::write(dir.join("mounted-password"), "hunter2\n").unwrap();
+        fs::write(dir.join(".mounted_port"), "8080").unwrap();
+
+        let source = SecretDirSource::new(&dir);
+        assert_eq!(Config::deserialize(source.prepare("mounted")).unwrap(), Config {
+            user: String::from("admin"),
+            password: String::from("hunter2"),
+            port: 0,
+        });
+
+        fs::write(dir.join("mounted_port"), "8080").unwrap();
+        assert_eq!(Config::deserialize(source.prepare("mounted")).unwrap().port, 0);
+        source.refresh();
+        assert_eq!(Config::deserialize(source.prepare("mounted")).unwrap().port, 8080);
+
+        assert_eq!(source.describe(), format!("secret-dir:{}", dir.display()));
+        fs::remove_dir_all(&dir).unwrap();
+        let source = SecretDirSource::new(&dir);
+        assert_eq!(Config::deserialize(source.prepare("mounted")).unwrap(), Config::default());
+    }
+}
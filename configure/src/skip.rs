@@ -0,0 +1,137 @@
+//! Removing skipped fields from the values a source provides.
+use std::fmt;
+
+use serde::de::{Deserializer, DeserializeSeed, IgnoredAny, IntoDeserializer, MapAccess, Visitor};
+use erased_serde::{Error, Deserializer as DynamicDeserializer};
+
+use source::StructInfo;
+
+/// Wraps the deserializer of a source, ignoring the values it provides for
+/// fields marked `skip`.
+pub struct SkipFields {
+    pub deserializer: Box<dyn DynamicDeserializer<'static>>,
+    pub info: &'static StructInfo,
+}
+
+impl SkipFields {
+    /// Wrap `deserializer` if `info` has any skipped fields.
+    pub fn wrap(deserializer: Box<dyn DynamicDeserializer<'static>>, info: &'static StructInfo) -> Box<dyn DynamicDeserializer<'static>> {
+        if info.fields.iter().any(|field| field.skip) {
+            Box::new(<dyn DynamicDeserializer>::erase(SkipFields { deserializer, info }))
+        } else {
+            deserializer
+        }
+    }
+
+    fn skipped(info: &StructInfo, key: &str) -> bool {
+        info.fields.iter().any(|field| field.skip && field.name == key)
+    }
+}
+
+impl Deserializer<'static> for SkipFields {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'static>,
+    {
+        self.deserializer.deserialize_any(visitor)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+        where V: Visitor<'static>,
+    {
+        let visitor = SkipVisitor { visitor, info: self.info };
+        self.deserializer.deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where V: Visitor<'static>,
+    {
+        let visitor = SkipVisitor { visitor, info: self.info };
+        self.deserializer.deserialize_map(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        <W: Visitor<'static>>
+        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit seq
+        bytes byte_buf tuple_struct newtype_struct unit_struct
+        tuple ignored_any identifier enum option
+    }
+}
+
+struct SkipVisitor<V> {
+    visitor: V,
+    info: &'static StructInfo,
+}
+
+impl<'de, V: Visitor<'de>> Visitor<'de> for SkipVisitor<V> {
+    type Value = V::Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.visitor.expecting(f)
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+        where A: MapAccess<'de>,
+    {
+        self.visitor.visit_map(SkipMapAccess { map, info: self.info })
+    }
+}
+
+struct SkipMapAccess<A> {
+    map: A,
+    info: &'static StructInfo,
+}
+
+impl<'de, A: MapAccess<'de>> MapAccess<'de> for SkipMapAccess<A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+        where K: DeserializeSeed<'de>,
+    {
+        while let Some(key) = self.map.next_key::<String>()? {
+            if SkipFields::skipped(self.info, &key) {
+                self.map.next_value::<IgnoredAny>()?;
+                continue
+            }
+            return seed.deserialize(key.into_deserializer()).map(Some)
+        }
+        Ok(None)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+        where V: DeserializeSeed<'de>,
+    {
+        self.map.next_value_seed(seed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use source::{FieldInfo, PrepareSource};
+    use static_map::StaticMapSource;
+    use super::*;
+
+    #[derive(Deserialize, Debug, Default, PartialEq)]
+    #[serde(default)]
+    struct Config {
+        threads: u32,
+        internal: u32,
+    }
+
+    #[test]
+    fn test_skip_fields() {
+        static INFO: StructInfo = StructInfo::new(&[FieldInfo { skip: true, ..FieldInfo::new("internal") }]);
+        let source = StaticMapSource::new(vec![("SKIPPED_THREADS", "4"), ("SKIPPED_INTERNAL", "1")]);
+        let deserializer = SkipFields { deserializer: source.prepare("skipped"), info: &INFO };
+
+        assert_eq!(Config::deserialize(deserializer).unwrap(), Config { threads: 4, internal: 0 });
+    }
+}
@@ -11,21 +11,52 @@
 //!
 //! Libraries should **never** try to set the configuration source; only
 //! binaries should ever override the default.
-use std::sync::{Once, ONCE_INIT};
-use std::sync::atomic::{AtomicBool, Ordering, ATOMIC_BOOL_INIT};
+use std::collections::BTreeSet;
+use std::sync::{Mutex, Once};
+use std::sync::atomic::{AtomicBool, Ordering};
 
-use erased_serde::Deserializer as DynamicDeserializer;
+use erased_serde::{Error, Deserializer as DynamicDeserializer};
 
-pub use default::DefaultSource;
-use null_deserializer::NullDeserializer;
+pub use default::{Casing, DefaultSource, DefaultSourceBuilder, MissingVars, UnknownVars};
+pub use static_map::StaticMapSource;
+pub use env_map::EnvMapSource;
+pub use kv::{KvBackend, KvSource, MemoryBackend};
+pub use multi::{MultiSource, OrSource};
+pub use recording::RecordingSource;
+pub use secret_dir::SecretDirSource;
+#[cfg(feature = "keyring")]
+pub use keychain::{KeyringBackend, KeyringSource};
+#[cfg(feature = "json")]
+pub use json::JsonFileSource;
+#[cfg(all(windows, feature = "windows"))]
+pub use registry::{Hive, RegistrySource};
+use null_deserializer::NullSource;
+use skip::SkipFields;
+#[cfg(feature = "timing")]
+use timing::Timer;
+#[cfg(feature = "test-util")]
+pub use guard::ConfigurationGuard;
 
 /// The global static holding the active configuration source for this project.
 pub static CONFIGURATION: ActiveConfiguration = ActiveConfiguration {
-    init: ONCE_INIT,
-    is_overriden: ATOMIC_BOOL_INIT,
+    init: Once::new(),
+    is_overriden: AtomicBool::new(false),
 };
 
-static mut SOURCE: Option<&'static (Fn(&'static str) -> Box<DynamicDeserializer> + Send + Sync + 'static)> = None;
+static mut SOURCE: Option<&'static dyn PrepareSource> = None;
+
+static PACKAGES: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+
+/// The name of every package which has pulled configuration through
+/// `CONFIGURATION.get`, in alphabetical order.
+///
+/// This can be used to report the full surface of configuration in a
+/// program, for example in response to a `--show-config` flag. Packages
+/// appear here once their configuration has been generated.
+pub fn generated_packages() -> Vec<&'static str> {
+    let packages = PACKAGES.lock().unwrap_or_else(|err| err.into_inner());
+    packages.iter().cloned().collect()
+}
 
 /// A source for configuration.
 /// 
@@ -35,13 +66,141 @@ static mut SOURCE: Option<&'static (Fn(&'static str) -> Box<DynamicDeserializer>
 ///
 /// This crate ships a default source, called DefaultSource, which implements
 /// this trait.
-pub trait ConfigSource: Send + Sync + 'static {
+pub trait ConfigSource: PrepareSource {
     /// Initialize this source. This will be called once when the program
     /// begins and then never called again.
-    fn init() -> Self;
+    fn init() -> Self where Self: Sized;
+}
+
+/// What a source may need to know about a configuration struct, beyond the
+/// name of its package.
+///
+/// `#[derive(Configure)]` generates one of these from the struct's
+/// `#[configure]` attributes, and passes it to `CONFIGURATION.get_struct`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct StructInfo {
+    /// The prefix of the environment variables for this struct, instead of
+    /// the package name.
+    pub prefix: Option<&'static str>,
+    /// What is known about each field of the struct.
+    pub fields: &'static [FieldInfo],
+    /// How the names of this struct's environment variables are cased,
+    /// instead of however the source cases them.
+    pub case: Option<Casing>,
+    /// The separator between the variable name of a flattened field and the
+    /// names of the fields of the struct it holds, instead of the separator
+    /// of the casing.
+    pub separator: Option<&'static str>,
+}
+
+impl StructInfo {
+    /// A struct about which nothing is known.
+    pub const EMPTY: StructInfo = StructInfo::new(&[]);
+
+    /// A struct with these fields and no attributes.
+    pub const fn new(fields: &'static [FieldInfo]) -> StructInfo {
+        StructInfo {
+            prefix: None,
+            fields,
+            case: None,
+            separator: None,
+        }
+    }
+}
+
+/// What a source may need to know about one field of a configuration struct,
+/// beyond its name.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct FieldInfo {
+    /// The name of the field.
+    pub name: &'static str,
+    /// The exact name of the environment variable to read this field from,
+    /// instead of the name derived from the package and field name.
+    pub env: Option<&'static str>,
+    /// Whether this is a `bool` field which is true whenever its environment
+    /// variable is set, whatever its value.
+    pub flag: bool,
+    /// The name to look this field up by, instead of the name of the field.
+    pub rename: Option<&'static str>,
+    /// Whether this field is never read from any source, so that it always
+    /// takes its default value.
+    pub skip: bool,
+    /// Whether this field is a nested struct, whose fields are read from
+    /// variables prefixed with this field's variable name, and from a table
+    /// named after this field.
+    pub flatten: bool,
+    /// Deprecated names of environment variables to read this field from
+    /// when its variable is not set, in order.
+    pub aliases: &'static [&'static str],
+    /// Deprecated keys to read this field from in the `Cargo.toml` when its
+    /// key is not set, in order.
+    pub toml_aliases: &'static [&'static str],
+    /// The key to look this field up by in the `Cargo.toml`, instead of the
+    /// name it is otherwise looked up by.
+    pub toml_key: Option<&'static str>,
+    /// Whether this field is only read from the environment, ignoring the
+    /// `Cargo.toml`.
+    pub env_only: bool,
+    /// Whether this field is only read from the `Cargo.toml`, ignoring the
+    /// environment.
+    pub toml_only: bool,
+    /// Whether this field holds a secret, like a password, whose value
+    /// should never be displayed. This does not change how it is read.
+    pub secret: bool,
+    /// The package this field is read from, instead of the struct's: its
+    /// variable is prefixed with this name, and it is read from the table
+    /// named after it in the `Cargo.toml`.
+    pub namespace: Option<&'static str>,
+}
+
+impl FieldInfo {
+    /// A field with this name and no attributes.
+    pub const fn new(name: &'static str) -> FieldInfo {
+        FieldInfo {
+            name,
+            env: None,
+            flag: false,
+            rename: None,
+            skip: false,
+            flatten: false,
+            aliases: &[],
+            toml_aliases: &[],
+            toml_key: None,
+            env_only: false,
+            toml_only: false,
+            secret: false,
+            namespace: None,
+        }
+    }
+}
+
+/// The object safe part of a source for configuration.
+///
+/// Every `ConfigSource` implements this trait. Because it is object safe, a
+/// source can be chosen at runtime - for example, based on a command line
+/// flag - and set with `CONFIGURATION.set_boxed`.
+pub trait PrepareSource: Send + Sync + 'static {
     /// Prepare a deserializer for a particular package. This will be called
     /// every time we generate configuration for that package.
-    fn prepare(&self, package: &'static str) -> Box<DynamicDeserializer<'static>>;
+    fn prepare(&self, package: &str) -> Box<dyn DynamicDeserializer<'static>>;
+    /// Prepare a deserializer for a particular package, which may consult
+    /// what is known about its configuration struct. By default, the struct
+    /// info is ignored and this calls `prepare`.
+    fn prepare_struct(&self, package: &str, info: &'static StructInfo) -> Box<dyn DynamicDeserializer<'static>> {
+        let _ = info;
+        self.prepare(package)
+    }
+    /// Discard any state this source has cached about the environment, so
+    /// that configuration generated afterward sees the environment as it is
+    /// now. By default, this does nothing.
+    fn refresh(&self) { }
+    /// A human readable name of this source, for diagnostics like a
+    /// `--config-source` flag: for example, `default`,
+    /// `json-file:config.json` or `multi[default,secret-dir:/run/secrets]`.
+    /// By default, this is the name of the source's type.
+    fn describe(&self) -> String {
+        ::std::any::type_name::<Self>().to_owned()
+    }
 }
 
 /// The active configuration source.
@@ -66,11 +225,27 @@ impl ActiveConfiguration {
     /// If you set the active configuration, you should do so very early in
     /// your program, preferably as close to the beginning of main as possible.
     /// That way, the configuration source is consistent for every dependency.
-    pub fn set<T: ConfigSource>(&'static self, source: T) {
+    pub fn set<T: PrepareSource>(&'static self, source: T) {
+        self.set_boxed(Box::new(source))
+    }
+
+    /// Set the active configuration to a boxed source.
+    ///
+    /// This is the same as `set`, but the type of the source does not need
+    /// to be known at compile time:
+    ///
+    /// ```rust,ignore
+    /// let source: Box<dyn PrepareSource> = if use_json {
+    ///     Box::new(JsonFileSource::open(path)?)
+    /// } else {
+    ///     Box::new(DefaultSource::init())
+    /// };
+    /// CONFIGURATION.set_boxed(source);
+    /// ```
+    pub fn set_boxed(&'static self, source: Box<dyn PrepareSource>) {
         self.init.call_once(||  {
             self.is_overriden.store(true, Ordering::Relaxed);
-            let init = Box::new(move |s| source.prepare(s));
-            unsafe { SOURCE = Some(&*Box::into_raw(init)) }
+            unsafe { SOURCE = Some(Box::leak(source)) }
         });
     }
 
@@ -79,14 +254,88 @@ impl ActiveConfiguration {
     /// Libraries which need to construct configuration can use this to get 
     /// the active source of configuration. Normally they would derive
     /// Configure for their config struct, which will call this method.
-    pub fn get(&'static self, package: &'static str) -> Box<DynamicDeserializer> {
+    ///
+    /// The package name does not need to be known at compile time, so
+    /// configuration can also be generated for sections named at runtime,
+    /// such as one section per loaded plugin.
+    pub fn get(&'static self, package: &str) -> Box<dyn DynamicDeserializer<'static>> {
+        self.get_struct(package, &StructInfo::EMPTY)
+    }
+
+    /// Get the active configuration, telling the source about the
+    /// configuration struct.
+    ///
+    /// This is what `#[derive(Configure)]` calls, so that sources can honor
+    /// attributes like `#[configure(env = "PORT")]`.
+    pub fn get_struct(&'static self, package: &str, info: &'static StructInfo) -> Box<dyn DynamicDeserializer<'static>> {
+        let mut packages = PACKAGES.lock().unwrap_or_else(|err| err.into_inner());
+        if !packages.contains(package) {
+            // Each distinct name is leaked once, so that it can be returned
+            // from `generated_packages`.
+            packages.insert(Box::leak(package.to_owned().into_boxed_str()));
+        }
+        drop(packages);
+        let deserializer = self.with_source(|source| source.prepare_struct(package, info));
+        // Not every source knows about skipped fields, so remove them from
+        // whatever the source provides.
+        SkipFields::wrap(deserializer, info)
+    }
+
+    /// Get the active configuration with `get_struct`, and deserialize it
+    /// with `deserialize`.
+    ///
+    /// This is what the generated `Configure::generate` calls. With the
+    /// `timing` feature, both steps are timed.
+    #[doc(hidden)]
+    pub fn generate_struct<T, F>(&'static self, package: &str, info: &'static StructInfo, deserialize: F) -> Result<T, Error>
+        where F: FnOnce(Box<dyn DynamicDeserializer<'static>>) -> Result<T, Error>,
+    {
+        #[cfg(feature = "timing")]
+        let mut timer = Timer::start();
+        let deserializer = self.get_struct(package, info);
+        #[cfg(feature = "timing")]
+        timer.prepared();
+        let result = deserialize(deserializer);
+        #[cfg(feature = "timing")]
+        timer.finish(package);
+        result
+    }
+
+    /// Refresh the active configuration source.
+    ///
+    /// Sources which capture the environment once, like a frozen
+    /// `DefaultSource`, will capture it again the next time configuration is
+    /// generated. Call this before `Configure::regenerate` to make sure the
+    /// regenerated configuration reflects the current environment.
+    pub fn refresh(&'static self) {
+        self.with_source(|source| source.refresh())
+    }
+
+    /// A human readable name of the active configuration source, from its
+    /// `describe` method. If no source has been set, this is `none`.
+    pub fn describe(&'static self) -> String {
+        self.with_source(|source| source.describe())
+    }
+
+    /// Call `f` with the source overriding this thread's configuration, if
+    /// there is one, or else the active source.
+    fn with_source<R, F>(&'static self, f: F) -> R
+        where F: FnOnce(&dyn PrepareSource) -> R,
+    {
+        #[cfg(feature = "test-util")]
+        let f = match ::guard::with_override(f) {
+            Ok(result)  => return result,
+            Err(f)      => f,
+        };
+        f(self.source())
+    }
+
+    fn source(&'static self) -> &'static dyn PrepareSource {
         self.init.call_once(|| {
-            fn null_deserializer(_package: &'static str) -> Box<DynamicDeserializer> {
-                Box::new(DynamicDeserializer::erase(NullDeserializer))
-            }
-            unsafe { SOURCE = Some(&null_deserializer) }
+            static NULL_SOURCE: NullSource = NullSource;
+            unsafe { SOURCE = Some(&NULL_SOURCE) }
         });
-        unsafe { SOURCE.unwrap()(package) }
+        unsafe { SOURCE.unwrap() }
     }
 
     /// Returns true if the configuration source is the default source.
@@ -0,0 +1,137 @@
+//! A configuration source which reads a fixed set of key/value pairs.
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use erased_serde::Deserializer as DynamicDeserializer;
+
+use default::DefaultSource;
+use source::{ConfigSource, PrepareSource, StructInfo};
+
+static INJECTED: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+/// A source which reads configuration from key/value pairs held in memory,
+/// rather than from the process environment.
+///
+/// The keys are named just like the environment variables read by the
+/// `DefaultSource` - the field `threads` of the package `myapp` is read from
+/// the key `MYAPP_THREADS` - and the values are parsed in the same way.
+///
+/// This is the default source on `wasm32-unknown-unknown`, which has no
+/// process environment or filesystem. There, the embedding application
+/// provides the configuration with `StaticMapSource::inject` before any
+/// configuration is generated:
+///
+/// ```rust,ignore
+/// // For example, with the values from `window.__CONFIG__`:
+/// for (key, value) in config_from_window() {
+///     StaticMapSource::inject(key, value);
+/// }
+/// use_default_config!();
+/// ```
+#[derive(Clone)]
+pub struct StaticMapSource {
+    inner: DefaultSource,
+}
+
+impl ConfigSource for StaticMapSource {
+    /// Reads the pairs provided with `StaticMapSource::inject`. They are
+    /// read when configuration is first generated, and again after
+    /// `CONFIGURATION.refresh()`.
+    fn init() -> StaticMapSource {
+        StaticMapSource {
+            inner: DefaultSource::with_vars(|| {
+                let injected = INJECTED.lock().unwrap_or_else(|err| err.into_inner());
+                injected.iter().map(|(key, value)| (key.into(), value.into())).collect()
+            }),
+        }
+    }
+}
+
+impl PrepareSource for StaticMapSource {
+    fn prepare(&self, package: &str) -> Box<dyn DynamicDeserializer<'static>> {
+        self.inner.prepare(package)
+    }
+
+    fn prepare_struct(&self, package: &str, info: &'static StructInfo) -> Box<dyn DynamicDeserializer<'static>> {
+        self.inner.prepare_struct(package, info)
+    }
+
+    fn refresh(&self) {
+        self.inner.refresh()
+    }
+
+    fn describe(&self) -> String {
+        String::from("static-map")
+    }
+}
+
+impl StaticMapSource {
+    /// A source which reads only these pairs.
+    pub fn new<I, K, V>(pairs: I) -> StaticMapSource
+        where I: IntoIterator<Item = (K, V)>,
+              K: Into<String>,
+              V: Into<String>,
+    {
+        let pairs: Vec<(String, String)> = pairs.into_iter()
+            .map(|(key, value)| (key.into(), value.into()))
+            .collect();
+        StaticMapSource {
+            inner: DefaultSource::with_vars(move || {
+                pairs.iter().map(|(key, value)| (key.into(), value.into())).collect()
+            }),
+        }
+    }
+
+    /// Provide a value for the sources created with `StaticMapSource::init`,
+    /// including the one set by `use_default_config!` on
+    /// `wasm32-unknown-unknown`.
+    ///
+    /// This is how the embedding application passes configuration in. It
+    /// should be called before any configuration is generated; values
+    /// injected afterward are seen after `CONFIGURATION.refresh()`.
+    pub fn inject<K: Into<String>, V: Into<String>>(key: K, value: V) {
+        let mut injected = INJECTED.lock().unwrap_or_else(|err| err.into_inner());
+        injected.insert(key.into(), value.into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(default)]
+    struct Config {
+        threads: u32,
+        hosts: Vec<String>,
+    }
+
+    impl Default for Config {
+        fn default() -> Config {
+            Config { threads: 1, hosts: vec![] }
+        }
+    }
+
+    #[test]
+    fn test_static_map() {
+        let source = StaticMapSource::new(vec![("STATIC_HOSTS", "a,b")]);
+        assert_eq!(Config::deserialize(source.prepare("static")).unwrap(), Config {
+            threads: 1,
+            hosts: vec![String::from("a"), String::from("b")],
+        });
+    }
+
+    #[test]
+    fn test_injected() {
+        StaticMapSource::inject("INJECTED_THREADS", "4");
+        let source = StaticMapSource::init();
+        assert_eq!(Config::deserialize(source.prepare("injected")).unwrap().threads, 4);
+
+        StaticMapSource::inject("INJECTED_THREADS", "8");
+        assert_eq!(Config::deserialize(source.prepare("injected")).unwrap().threads, 4);
+        source.refresh();
+        assert_eq!(Config::deserialize(source.prepare("injected")).unwrap().threads, 8);
+    }
+}
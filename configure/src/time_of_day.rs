@@ -0,0 +1,50 @@
+//! Parsing times of day, for fields like the time a job runs at.
+
+/// Parse a time of day, `HH:MM` or `HH:MM:SS`, into the number of seconds
+/// since midnight.
+///
+/// The hours must be less than 24, and the minutes and seconds less than
+/// 60. This is the parser of `#[configure(time_of_day)]` fields, and can also
+/// be used with `parse_with`.
+pub fn parse_time_of_day(s: &str) -> Result<u32, String> {
+    let err = || format!("`{}` is not a time of day (`HH:MM` or `HH:MM:SS`)", s);
+
+    let mut parts = s.trim().split(':');
+    let mut component = |max: u32| -> Result<u32, String> {
+        match parts.next() {
+            Some(part) if !part.is_empty() && part.len() <= 2 && part.bytes().all(|b| b.is_ascii_digit()) => {
+                let n = part.parse().map_err(|_| err())?;
+                if n < max { Ok(n) } else { Err(format!("`{}` is out of range in time of day `{}`", part, s)) }
+            }
+            _   => Err(err()),
+        }
+    };
+    let hours = component(24)?;
+    let minutes = component(60)?;
+    let seconds = match s.trim().matches(':').count() {
+        1   => 0,
+        2   => component(60)?,
+        _   => return Err(err()),
+    };
+    Ok(hours * 3600 + minutes * 60 + seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_of_day() {
+        assert_eq!(parse_time_of_day("14:30"), Ok(52200));
+        assert_eq!(parse_time_of_day("00:00:30"), Ok(30));
+        assert_eq!(parse_time_of_day("23:59:59"), Ok(86399));
+        assert_eq!(parse_time_of_day(" 7:05 "), Ok(25500));
+        assert_eq!(parse_time_of_day("25:00"), Err(String::from("`25` is out of range in time of day `25:00`")));
+        assert!(parse_time_of_day("12:60").is_err());
+        assert!(parse_time_of_day("12:00:60").is_err());
+        assert!(parse_time_of_day("12").is_err());
+        assert!(parse_time_of_day("12:00:00:00").is_err());
+        assert!(parse_time_of_day("12:-1").is_err());
+        assert!(parse_time_of_day("noon").is_err());
+    }
+}
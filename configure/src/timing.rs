@@ -0,0 +1,105 @@
+//! Measuring how long configuration takes to generate.
+//!
+//! With the `timing` feature, every call to a derived `Configure::generate`
+//! measures how long the active source took to prepare the configuration,
+//! and how long it then took to deserialize, and reports both to the
+//! callback set with `on_generate`. This can help find slow sources, such as
+//! those which read files or make network requests, when a program is slow
+//! to start.
+//!
+//! ```rust,ignore
+//! configure::timing::on_generate(|timing| {
+//!     eprintln!("{}: prepared in {:?}, deserialized in {:?}",
+//!               timing.package, timing.prepare, timing.deserialize);
+//! });
+//! ```
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+type Callback = Box<dyn Fn(&Timing) + Send + Sync>;
+
+static CALLBACK: RwLock<Option<Callback>> = RwLock::new(None);
+
+/// How long generating the configuration for one package took.
+#[derive(Clone, Debug)]
+pub struct Timing {
+    /// The package the configuration was generated for.
+    pub package: String,
+    /// How long the active source took to prepare its deserializer.
+    pub prepare: Duration,
+    /// How long deserializing the configuration took.
+    pub deserialize: Duration,
+}
+
+/// Call `callback` every time configuration is generated, replacing any
+/// callback set before.
+pub fn on_generate<F>(callback: F)
+    where F: Fn(&Timing) + Send + Sync + 'static,
+{
+    let mut slot = CALLBACK.write().unwrap_or_else(|err| err.into_inner());
+    *slot = Some(Box::new(callback));
+}
+
+/// A running measurement, started before the source prepares its
+/// deserializer.
+pub(crate) struct Timer {
+    start: Instant,
+    prepared: Option<Instant>,
+}
+
+impl Timer {
+    pub(crate) fn start() -> Timer {
+        Timer { start: Instant::now(), prepared: None }
+    }
+
+    pub(crate) fn prepared(&mut self) {
+        self.prepared = Some(Instant::now());
+    }
+
+    pub(crate) fn finish(self, package: &str) {
+        let end = Instant::now();
+        let prepared = self.prepared.unwrap_or(end);
+        let callback = CALLBACK.read().unwrap_or_else(|err| err.into_inner());
+        if let Some(ref callback) = *callback {
+            callback(&Timing {
+                package: package.to_owned(),
+                prepare: prepared - self.start,
+                deserialize: end - prepared,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+    use std::thread;
+
+    use serde::Deserialize;
+
+    use source::{CONFIGURATION, StructInfo};
+    use super::*;
+
+    #[derive(Deserialize, Default)]
+    #[serde(default)]
+    struct Config {
+        #[allow(dead_code)]
+        threads: u32,
+    }
+
+    #[test]
+    fn test_on_generate() {
+        static TIMINGS: Mutex<Vec<Timing>> = Mutex::new(Vec::new());
+        on_generate(|timing| TIMINGS.lock().unwrap().push(timing.clone()));
+
+        CONFIGURATION.generate_struct("timed", &StructInfo::EMPTY, |deserializer| {
+            thread::sleep(Duration::from_millis(1));
+            Config::deserialize(deserializer)
+        }).unwrap();
+
+        let timings = TIMINGS.lock().unwrap();
+        assert_eq!(timings.len(), 1);
+        assert_eq!(timings[0].package, "timed");
+        assert!(timings[0].deserialize >= Duration::from_millis(1));
+    }
+}
@@ -0,0 +1,35 @@
+#[macro_use] extern crate configure;
+extern crate serde;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+
+use configure::Configure;
+use configure::source::{ConfigSource, DefaultSource, PrepareSource};
+
+#[derive(Configure, Deserialize, Default)]
+#[configure(name = "boxed")]
+#[serde(default)]
+struct Config {
+    database_url: String,
+}
+
+fn choose_source(name: &str) -> Box<dyn PrepareSource> {
+    match name {
+        "unprefixed"    => Box::new(DefaultSource::init().fallback_unprefixed()),
+        _               => Box::new(DefaultSource::init()),
+    }
+}
+
+#[test]
+fn boxed_source() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    env::set_var("BOXED_SOURCE", "unprefixed");
+    env::set_var("DATABASE_URL", "postgres://localhost");
+
+    let name = env::var("BOXED_SOURCE").unwrap();
+    use_config_from!(boxed choose_source(&name));
+
+    assert!(configure::source::CONFIGURATION.is_overriden());
+    assert_eq!(Config::generate().unwrap().database_url, "postgres://localhost");
+}
@@ -0,0 +1,36 @@
+#[macro_use] extern crate configure;
+extern crate serde;
+extern crate test_setup;
+
+use std::env;
+
+use configure::Configure;
+use serde::Deserialize;
+use test_setup::Configuration;
+
+#[derive(Configure, Deserialize, Default)]
+#[configure(name = "registered")]
+#[serde(default)]
+struct Registered {
+    #[allow(dead_code)]
+    value: u32,
+}
+
+#[test]
+fn generated_packages() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    use_default_config!();
+
+    assert!(configure::generated_packages().is_empty());
+
+    let mut cfg = Configuration::generate().unwrap();
+    let mut registered = Registered::generate().unwrap();
+    cfg.regenerate().unwrap();
+    registered.regenerate().unwrap();
+
+    // Package names can also be assembled at runtime.
+    let plugin = format!("plugin_{}", 1);
+    Registered::deserialize(configure::source::CONFIGURATION.get(&plugin)).unwrap();
+
+    assert_eq!(configure::generated_packages(), vec!["plugin_1", "registered", "test"]);
+}
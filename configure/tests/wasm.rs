@@ -0,0 +1,43 @@
+#![cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+
+extern crate serde;
+extern crate wasm_bindgen_test;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use configure::Configure;
+use configure::source::StaticMapSource;
+use wasm_bindgen_test::*;
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "web")]
+#[serde(default)]
+pub struct Config {
+    api_url: String,
+    retries: u32,
+    features: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            api_url: String::from("http://localhost"),
+            retries: 3,
+            features: vec![],
+        }
+    }
+}
+
+#[wasm_bindgen_test]
+fn generate_from_injected_values() {
+    StaticMapSource::inject("WEB_API_URL", "https://example.com/api");
+    StaticMapSource::inject("WEB_FEATURES", "search,upload");
+    use_default_config!();
+
+    assert_eq!(Config::generate().unwrap(), Config {
+        api_url: String::from("https://example.com/api"),
+        retries: 3,
+        features: vec![String::from("search"), String::from("upload")],
+    });
+}
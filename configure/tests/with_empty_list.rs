@@ -0,0 +1,24 @@
+#[macro_use] extern crate configure;
+extern crate test_setup;
+
+use std::env;
+
+use configure::Configure;
+use test_setup::Configuration;
+
+#[test]
+fn empty_and_absent_lists() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    env::remove_var("TEST_THIRD_FIELD");
+    use_default_config!();
+    assert_eq!(Configuration::generate().unwrap().third_field, Some(vec![]));
+
+    env::set_var("TEST_THIRD_FIELD", "");
+    assert_eq!(Configuration::generate().unwrap().third_field, Some(vec![]));
+
+    env::set_var("TEST_THIRD_FIELD", "3");
+    assert_eq!(Configuration::generate().unwrap().third_field, Some(vec![3]));
+
+    env::set_var("TEST_THIRD_FIELD", "3,4,5");
+    assert_eq!(Configuration::generate().unwrap().third_field, Some(vec![3, 4, 5]));
+}
@@ -0,0 +1,153 @@
+//! Command line arguments overriding the fields of configuration structs,
+//! with clap.
+use heck::KebabCase;
+use proc_macro2::TokenStream;
+use syn::*;
+
+use attrs::FieldAttrs;
+use super::{generic_arg, is_list, parse_value};
+
+/// The `augment_args` method, adding a long flag for each field to a
+/// `clap::Command`, and the `from_matches` method, generating the struct and
+/// then overriding the fields whose flags are present.
+///
+/// Flags are named after the fields in kebab case. Types clap can parse
+/// itself, like the primitive types, `String` and `PathBuf`, or options of
+/// them, are parsed by clap; the values of other fields are read like
+/// environment variables.
+pub fn clap_args(fields: &[&Field], attrs: &[FieldAttrs]) -> TokenStream {
+    let fields = fields.iter().zip(attrs).filter(|&(_, attrs)| !attrs.skip && !attrs.flatten).collect::<Vec<_>>();
+    let args = fields.iter().map(|&(field, attrs)| arg(field, attrs));
+    let apply = fields.iter().map(|&(field, attrs)| apply(field, attrs));
+    let bounds = fields.iter()
+        .filter(|&&(field, attrs)| !attrs.flag && !attrs.is_parsed() && clap_type(&field.ty).is_none())
+        .map(|&(field, _)| {
+            let ty = &field.ty;
+            quote!(#ty: ::serde::Deserialize<'static>)
+        });
+
+    quote! {
+        /// Add an optional long flag for each field to `cmd`, like
+        /// `--socket-addr` for the field `socket_addr`, with the docs of
+        /// the field as its help. The values of secret fields are never
+        /// shown in the help.
+        pub fn augment_args(cmd: ::configure::clap::Command) -> ::configure::clap::Command {
+            cmd #(.arg(#args))*
+        }
+
+        /// Generate this configuration, then override the fields whose
+        /// flags are present in `matches`, which must have been parsed by a
+        /// command augmented with `augment_args`. The overrides are not
+        /// validated.
+        pub fn from_matches(matches: &::configure::clap::ArgMatches) -> ::std::result::Result<Self, ::configure::DeserializeError>
+            where for<'a> Self: ::configure::Configure,
+                  #(#bounds,)*
+        {
+            let mut cfg = <Self as ::configure::Configure>::generate()?;
+            #(#apply)*
+            Ok(cfg)
+        }
+    }
+}
+
+/// The name of the flag of this field, without the `--`.
+fn long(field: &Field, attrs: &FieldAttrs) -> String {
+    attrs.rename.clone().unwrap_or_else(|| field.ident.as_ref().unwrap().to_string()).to_kebab_case()
+}
+
+/// The type clap parses the value of this field as, if it can: the type of
+/// the field, or the type it is an `Option` of.
+fn clap_type(ty: &Type) -> Option<&Type> {
+    let ty = generic_arg(ty, "Option").unwrap_or(ty);
+    let segment = match *ty {
+        Type::Path(ref ty) if ty.qself.is_none()    => ty.path.segments.last()?,
+        _                                           => return None,
+    };
+    if !segment.arguments.is_empty() {
+        return None
+    }
+    match &segment.ident.to_string()[..] {
+        "bool" | "u8" | "u16" | "u32" | "u64" | "usize" | "i8" | "i16" | "i32" | "i64" | "isize"
+            | "f32" | "f64" | "String" | "PathBuf"                                                  => Some(ty),
+        _                                                                                           => None,
+    }
+}
+
+fn arg(field: &Field, attrs: &FieldAttrs) -> TokenStream {
+    let long = long(field, attrs);
+    let mut arg = quote!(::configure::clap::Arg::new(#long).long(#long).required(false));
+    if attrs.flag {
+        arg.extend(quote!(.action(::configure::clap::ArgAction::SetTrue)));
+    } else {
+        let ty = clap_type(&field.ty).filter(|_| !attrs.is_parsed());
+        let value_ty = match ty {
+            Some(ty)    => quote!(#ty),
+            None        => quote!(::std::string::String),
+        };
+        arg.extend(quote!(.value_parser(::configure::clap::value_parser!(#value_ty))));
+        let is_bool = match ty {
+            Some(Type::Path(ty))    => ty.path.is_ident("bool"),
+            _                       => false,
+        };
+        if is_bool {
+            // `--tls` is `--tls true`.
+            arg.extend(quote!(.num_args(0..=1).default_missing_value("true")));
+        }
+    }
+    if let Some(ref docs) = attrs.docs {
+        arg.extend(quote!(.help(#docs)));
+    }
+    if attrs.hide_docs {
+        arg.extend(quote!(.hide(true)));
+    }
+    if attrs.secret {
+        arg.extend(quote!(.hide_default_value(true).hide_possible_values(true)));
+    }
+    arg
+}
+
+/// Statements overriding this field of `cfg` if its flag is present.
+fn apply(field: &Field, attrs: &FieldAttrs) -> TokenStream {
+    let name = field.ident.as_ref().unwrap();
+    let long = long(field, attrs);
+    if attrs.flag {
+        return quote! {
+            if matches.get_flag(#long) {
+                cfg.#name = true;
+            }
+        }
+    }
+
+    let flag = format!("--{}", long);
+    let assign = match clap_type(&field.ty) {
+        _ if attrs.is_parsed()  => {
+            let list = is_list(field, attrs);
+            let parse = parse_value(field, attrs, &flag);
+            quote! {
+                let values = ::configure::overlay::raw_values(::configure::overlay::arg(value), #list)?;
+                cfg.#name = (#parse)(&values).map_err(<::configure::DeserializeError as ::serde::de::Error>::custom)?;
+            }
+        }
+        Some(ty)                => {
+            let value = if generic_arg(&field.ty, "Option").is_some() { quote!(Some(value)) } else { quote!(value) };
+            return quote! {
+                if let Some(value) = matches.get_one::<#ty>(#long).cloned() {
+                    cfg.#name = #value;
+                }
+            }
+        }
+        None                    => {
+            let msg = format!("invalid value for `{}` (`{}`): {{}}", name, flag);
+            quote! {
+                cfg.#name = ::serde::Deserialize::deserialize(::configure::overlay::arg(value)).map_err(|err| {
+                    <::configure::DeserializeError as ::serde::de::Error>::custom(format!(#msg, err))
+                })?;
+            }
+        }
+    };
+    quote! {
+        if let Some(value) = matches.get_one::<::std::string::String>(#long) {
+            #assign
+        }
+    }
+}
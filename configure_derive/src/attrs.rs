@@ -1,121 +1,683 @@
+use heck::{KebabCase, ShoutySnakeCase, SnakeCase};
+use proc_macro2::Span;
 use syn::*;
+use syn::ext::IdentExt;
+use syn::meta::ParseNestedMeta;
 
 pub struct CfgAttrs {
     pub name: Option<String>,
+    pub use_type_name: bool,
+    pub prefix: Option<String>,
     pub docs: bool,
+    pub docs_module: Option<Ident>,
+    pub derive_default: bool,
+    pub validate: Option<ExprPath>,
+    pub case: Option<Case>,
+    pub separator: Option<String>,
+    pub collect_errors: bool,
+    pub json_schema: bool,
+    pub partial: bool,
+    pub diff: bool,
+    pub redacted_debug: bool,
+    pub builder: bool,
+    pub env: Option<String>,
+    /// The `rename_all` rule of the struct's serde attributes.
+    pub rename_all: Option<String>,
 }
 
-impl CfgAttrs {
-    pub fn new(attrs: &[Attribute]) -> CfgAttrs {
-        let cfg_attrs = filter_attrs(attrs);
+/// The casing of a struct's environment variables, from a `case` attribute.
+#[derive(Copy, Clone)]
+pub enum Case {
+    ShoutySnake,
+    Snake,
+    Kebab,
+    Lower,
+}
+
+impl Case {
+    pub fn apply(self, name: &str) -> String {
+        match self {
+            Case::ShoutySnake   => name.to_shouty_snake_case(),
+            Case::Snake         => name.to_snake_case(),
+            Case::Kebab         => name.to_kebab_case(),
+            Case::Lower         => name.to_lowercase(),
+        }
+    }
 
+    /// The separator between the prefix and the field in a name.
+    pub fn separator(self) -> &'static str {
+        match self {
+            Case::Kebab => "-",
+            _           => "_",
+        }
+    }
+
+    /// The variant of `configure::source::Casing` for this case.
+    pub fn casing(self) -> Ident {
+        let casing = match self {
+            Case::ShoutySnake   => "Upper",
+            Case::Snake         => "Snake",
+            Case::Kebab         => "Kebab",
+            Case::Lower         => "Lower",
+        };
+        Ident::new(casing, Span::call_site())
+    }
+}
+
+impl CfgAttrs {
+    pub fn new(attrs: &[Attribute]) -> Result<CfgAttrs> {
         let mut cfg = CfgAttrs {
             name: None,
+            use_type_name: false,
+            prefix: None,
             docs: false,
+            docs_module: None,
+            derive_default: false,
+            validate: None,
+            case: None,
+            separator: None,
+            collect_errors: false,
+            json_schema: false,
+            partial: false,
+            diff: false,
+            redacted_debug: false,
+            builder: false,
+            env: None,
+            rename_all: serde_name(attrs, "rename_all"),
         };
 
         // Parse the cfg attrs
-        for attr in cfg_attrs {
-            if let NestedMetaItem::MetaItem(ref attr) = *attr {
-                match attr.name() {
-                    "name" if cfg.name.is_some()    => panic!("Multiple `name` attributes"),
-                    "name"                          => cfg.name = project_name(attr),
-                    "generate_docs" if cfg.docs     => panic!("Multiple `generate_docs` attributes"),
-                    "generate_docs"                 => cfg.docs = gen_docs(attr),
-                    unknown                         => {
-                        panic!("Unrecognized configure attribute `{}`", unknown)
+        for attr in filter_attrs(attrs) {
+            attr.parse_nested_meta(|meta| {
+                let multiple = |name: &str| meta.error(format!("Multiple `{}` attributes", name));
+                match &attr_name(&meta)[..] {
+                    "name" if cfg.name.is_some()            => return Err(multiple("name")),
+                    "name"                                  => {
+                        cfg.name = Some(string(&meta, "#[configure(name = \"$NAME\")]")?)
+                    }
+                    "use_type_name" if cfg.use_type_name    => return Err(multiple("use_type_name")),
+                    "use_type_name"                         => {
+                        cfg.use_type_name = word(&meta, "#[configure(use_type_name)]")?
+                    }
+                    "prefix" if cfg.prefix.is_some()        => return Err(multiple("prefix")),
+                    "prefix"                                => {
+                        cfg.prefix = Some(string(&meta, "#[configure(prefix = \"$PREFIX\")]")?)
+                    }
+                    "generate_docs" if cfg.docs             => return Err(multiple("generate_docs")),
+                    "generate_docs"                         => {
+                        cfg.docs = word(&meta, "#[configure(generate_docs)]")?
+                    }
+                    "docs_module" if cfg.docs_module.is_some() => return Err(multiple("docs_module")),
+                    "docs_module"                           => {
+                        cfg.docs_module = Some(docs_module(&meta)?)
+                    }
+                    "derive_default" if cfg.derive_default  => return Err(multiple("derive_default")),
+                    "derive_default"                        => {
+                        cfg.derive_default = word(&meta, "#[configure(derive_default)]")?
+                    }
+                    "validate" if cfg.validate.is_some()    => return Err(multiple("validate")),
+                    "validate"                              => {
+                        cfg.validate = Some(path(&meta, "#[configure(validate = \"$PATH\")]")?)
+                    }
+                    "case" if cfg.case.is_some()            => return Err(multiple("case")),
+                    "case"                                  => {
+                        cfg.case = Some(case(&meta)?)
+                    }
+                    "env" if cfg.env.is_some()              => return Err(multiple("env")),
+                    "env"                                   => {
+                        cfg.env = Some(string(&meta, "#[configure(env = \"$NAME\")]")?)
+                    }
+                    "collect_errors" if cfg.collect_errors  => return Err(multiple("collect_errors")),
+                    "collect_errors"                        => {
+                        cfg.collect_errors = word(&meta, "#[configure(collect_errors)]")?
+                    }
+                    "json_schema" if cfg.json_schema        => return Err(multiple("json_schema")),
+                    "json_schema"                           => {
+                        cfg.json_schema = word(&meta, "#[configure(json_schema)]")?
+                    }
+                    "partial" if cfg.partial                => return Err(multiple("partial")),
+                    "partial"                               => {
+                        cfg.partial = word(&meta, "#[configure(partial)]")?
+                    }
+                    "diff" if cfg.diff                      => return Err(multiple("diff")),
+                    "diff"                                  => {
+                        cfg.diff = word(&meta, "#[configure(diff)]")?
+                    }
+                    "redacted_debug" if cfg.redacted_debug  => return Err(multiple("redacted_debug")),
+                    "redacted_debug"                        => {
+                        cfg.redacted_debug = word(&meta, "#[configure(redacted_debug)]")?
+                    }
+                    "builder" if cfg.builder                => return Err(multiple("builder")),
+                    "builder"                               => {
+                        cfg.builder = word(&meta, "#[configure(builder)]")?
+                    }
+                    "separator" if cfg.separator.is_some()  => return Err(multiple("separator")),
+                    "separator"                             => {
+                        cfg.separator = Some(separator(&meta)?)
+                    }
+                    unknown                                 => {
+                        return Err(meta.error(format!("Unrecognized configure attribute `{}`", unknown)))
                     }
                 }
-            } else { panic!("Unrecognized configure attribute literal") }
+                Ok(())
+            })?;
         }
 
-        cfg
+        if let (Some(ref docs_module), false) = (&cfg.docs_module, cfg.docs) {
+            let msg = "The `docs_module` attribute requires #[configure(generate_docs)]";
+            return Err(Error::new(docs_module.span(), msg))
+        }
+
+        Ok(cfg)
     }
 }
 
 pub struct FieldAttrs {
+    /// The key serde deserializes this field from, which is what sources
+    /// are asked for.
+    pub key: String,
     pub docs: Option<String>,
+    pub doc_default: Option<String>,
+    pub hide_docs: bool,
+    pub env: Option<String>,
+    pub flag: bool,
+    pub flags: bool,
+    pub rename: Option<String>,
+    pub skip: bool,
+    pub default: Option<FieldDefault>,
+    pub validate: Option<ExprPath>,
+    pub range: Option<FieldRange>,
+    pub type_default: bool,
+    pub parse_with: Option<ExprPath>,
+    pub from_str: bool,
+    pub bytes: Option<Ident>,
+    pub time_of_day: bool,
+    pub percent: bool,
+    pub flatten: bool,
+    pub aliases: Vec<String>,
+    pub toml_aliases: Vec<String>,
+    pub toml_key: Option<String>,
+    pub namespace: Option<String>,
+    pub env_only: bool,
+    pub toml_only: bool,
+    pub secret: bool,
+    pub required: bool,
+    pub deprecated: Option<String>,
 }
 
-impl FieldAttrs {
-    pub fn new(field: &Field) -> FieldAttrs {
+/// A `range` attribute, like `#[configure(range = "1..=65535")]`.
+pub struct FieldRange {
+    pub expr: ExprRange,
+    /// The range as it was written, for errors and docs.
+    pub text: String,
+}
 
-        let mut cfg = FieldAttrs { docs: None };
+/// A `default` attribute. `#[configure(default)]` has no expression, while
+/// `#[configure(default = "expr")]` does.
+pub struct FieldDefault {
+    pub span: Span,
+    pub expr: Option<Expr>,
+}
+
+impl FieldAttrs {
+    pub fn new(field: &Field, cfg_attrs: &CfgAttrs) -> Result<FieldAttrs> {
+        let mut cfg = FieldAttrs {
+            key: serde_key(field, cfg_attrs.rename_all.as_deref()),
+            docs: None,
+            doc_default: None,
+            hide_docs: false,
+            env: None,
+            flag: false,
+            flags: false,
+            rename: None,
+            skip: false,
+            default: None,
+            validate: None,
+            range: None,
+            type_default: false,
+            parse_with: None,
+            from_str: false,
+            bytes: None,
+            time_of_day: false,
+            percent: false,
+            flatten: false,
+            aliases: vec![],
+            toml_aliases: vec![],
+            toml_key: None,
+            namespace: None,
+            env_only: false,
+            toml_only: false,
+            secret: false,
+            required: false,
+            deprecated: None,
+        };
 
-        let cfg_attrs = filter_attrs(&field.attrs);
+        let field_name = field.ident.as_ref().unwrap();
 
-        for attr in cfg_attrs {
-            if let NestedMetaItem::MetaItem(ref attr) = *attr {
-                match attr.name() {
-                    "docs" if cfg.docs.is_some()    => {
-                        let name = field.ident.as_ref().unwrap();
-                        panic!("Multiple `docs` attributes on one field: `{}`.", name)
+        for attr in filter_attrs(&field.attrs) {
+            attr.parse_nested_meta(|meta| {
+                let multiple = |name: &str| {
+                    meta.error(format!("Multiple `{}` attributes on one field: `{}`.", name, field_name))
+                };
+                match &attr_name(&meta)[..] {
+                    "docs" if cfg.docs.is_some()        => return Err(multiple("docs")),
+                    "docs"                              => {
+                        cfg.docs = Some(string(&meta, "#[configure(docs = \"$DOCS\")]")?)
+                    }
+                    "hide_docs" if cfg.hide_docs        => return Err(multiple("hide_docs")),
+                    "hide_docs"                         => {
+                        cfg.hide_docs = word(&meta, "#[configure(hide_docs)]")?
+                    }
+                    "doc_default" if cfg.doc_default.is_some() => return Err(multiple("doc_default")),
+                    "doc_default"                       => {
+                        cfg.doc_default = Some(string(&meta, "#[configure(doc_default = \"$DEFAULT\")]")?)
+                    }
+                    "env" if cfg.env.is_some()          => return Err(multiple("env")),
+                    "env"                               => {
+                        cfg.env = Some(string(&meta, "#[configure(env = \"$NAME\")]")?)
+                    }
+                    "flag" if cfg.flag                  => return Err(multiple("flag")),
+                    "flag"                              => {
+                        cfg.flag = word(&meta, "#[configure(flag)]")?
+                    }
+                    "flags" if cfg.flags                => return Err(multiple("flags")),
+                    "flags"                             => {
+                        cfg.flags = word(&meta, "#[configure(flags)]")?
+                    }
+                    "rename" if cfg.rename.is_some()    => return Err(multiple("rename")),
+                    "rename"                            => {
+                        cfg.rename = Some(string(&meta, "#[configure(rename = \"$NAME\")]")?)
+                    }
+                    "skip" if cfg.skip                  => return Err(multiple("skip")),
+                    "skip"                              => {
+                        cfg.skip = word(&meta, "#[configure(skip)]")?
+                    }
+                    "default" if cfg.default.is_some()  => return Err(multiple("default")),
+                    "default"                           => {
+                        cfg.default = Some(field_default(&meta)?)
+                    }
+                    "validate" if cfg.validate.is_some() => return Err(multiple("validate")),
+                    "validate"                          => {
+                        cfg.validate = Some(path(&meta, "#[configure(validate = \"$PATH\")]")?)
+                    }
+                    "range" if cfg.range.is_some()      => return Err(multiple("range")),
+                    "range"                             => {
+                        cfg.range = Some(field_range(&meta)?)
+                    }
+                    "type_default" if cfg.type_default  => return Err(multiple("type_default")),
+                    "type_default"                      => {
+                        cfg.type_default = word(&meta, "#[configure(type_default)]")?
+                    }
+                    "parse_with" if cfg.parse_with.is_some() => return Err(multiple("parse_with")),
+                    "parse_with"                        => {
+                        cfg.parse_with = Some(path(&meta, "#[configure(parse_with = \"$PATH\")]")?)
                     }
-                    "docs"                          => {
-                        cfg.docs = Some(field_docs(attr))
+                    "from_str" if cfg.from_str          => return Err(multiple("from_str")),
+                    "from_str"                          => {
+                        cfg.from_str = word(&meta, "#[configure(from_str)]")?
                     }
-                    unknown                         => {
-                        panic!("Unrecognized configure attribute `{}`", unknown)
+                    "bytes" if cfg.bytes.is_some()      => return Err(multiple("bytes")),
+                    "bytes"                             => {
+                        cfg.bytes = Some(bytes(&meta)?)
+                    }
+                    "time_of_day" if cfg.time_of_day    => return Err(multiple("time_of_day")),
+                    "time_of_day"                       => {
+                        cfg.time_of_day = word(&meta, "#[configure(time_of_day)]")?
+                    }
+                    "percent" if cfg.percent            => return Err(multiple("percent")),
+                    "percent"                           => {
+                        cfg.percent = word(&meta, "#[configure(percent)]")?
+                    }
+                    "flatten" if cfg.flatten            => return Err(multiple("flatten")),
+                    "flatten"                           => {
+                        cfg.flatten = word(&meta, "#[configure(flatten)]")?
+                    }
+                    "toml_key" if cfg.toml_key.is_some() => return Err(multiple("toml_key")),
+                    "toml_key"                          => {
+                        cfg.toml_key = Some(string(&meta, "#[configure(toml_key = \"$KEY\")]")?)
+                    }
+                    "namespace" if cfg.namespace.is_some() => return Err(multiple("namespace")),
+                    "namespace"                         => {
+                        cfg.namespace = Some(string(&meta, "#[configure(namespace = \"$NAME\")]")?)
+                    }
+                    "env_only" if cfg.env_only          => return Err(multiple("env_only")),
+                    "env_only"                          => {
+                        cfg.env_only = word(&meta, "#[configure(env_only)]")?
+                    }
+                    "toml_only" if cfg.toml_only        => return Err(multiple("toml_only")),
+                    "toml_only"                         => {
+                        cfg.toml_only = word(&meta, "#[configure(toml_only)]")?
+                    }
+                    "secret" if cfg.secret              => return Err(multiple("secret")),
+                    "secret"                            => {
+                        cfg.secret = word(&meta, "#[configure(secret)]")?
+                    }
+                    "required" if cfg.required          => return Err(multiple("required")),
+                    "required"                          => {
+                        cfg.required = word(&meta, "#[configure(required)]")?
+                    }
+                    "deprecated" if cfg.deprecated.is_some() => return Err(multiple("deprecated")),
+                    "deprecated"                        => {
+                        cfg.deprecated = Some(string(&meta, "#[configure(deprecated = \"$NOTE\")]")?)
+                    }
+                    // Aliases can be repeated.
+                    "alias"                             => {
+                        cfg.aliases.push(string(&meta, "#[configure(alias = \"$NAME\")]")?)
+                    }
+                    "toml_alias"                        => {
+                        cfg.toml_aliases.push(string(&meta, "#[configure(toml_alias = \"$KEY\")]")?)
+                    }
+                    unknown                             => {
+                        return Err(meta.error(format!("Unrecognized configure attribute `{}`", unknown)))
                     }
                 }
-            } else { panic!("Unrecognized configure attribute literal") }
+                Ok(())
+            })?;
+        }
+
+        let parsers = [
+            ("parse_with", cfg.parse_with.is_some()),
+            ("from_str", cfg.from_str),
+            ("bytes", cfg.bytes.is_some()),
+            ("time_of_day", cfg.time_of_day),
+            ("percent", cfg.percent),
+            ("flags", cfg.flags),
+        ];
+        let mut parsers = parsers.iter().filter(|&&(_, set)| set).map(|&(name, _)| name);
+        if let (Some(first), Some(second)) = (parsers.next(), parsers.next()) {
+            let msg = format!("Field `{}` cannot have both `{}` and `{}` attributes", field_name, first, second);
+            return Err(Error::new_spanned(field_name, msg))
+        }
+
+        // Fractions of integers would be rounded to nothing.
+        if cfg.percent && !is_float(field) {
+            let msg = format!("Field `{}` has the `percent` attribute, so it must be an `f32` or `f64`, \
+                               or an `Option` of one", field_name);
+            return Err(Error::new_spanned(&field.ty, msg))
+        }
+
+        if cfg.flag && cfg.flags {
+            let msg = format!("Field `{}` cannot have both `flag` and `flags` attributes", field_name);
+            return Err(Error::new_spanned(field_name, msg))
+        }
+
+        if cfg.env_only && cfg.toml_only {
+            let msg = format!("Field `{}` cannot have both `env_only` and `toml_only` attributes", field_name);
+            return Err(Error::new_spanned(field_name, msg))
+        }
+
+        if cfg.required && cfg.deprecated.is_some() {
+            let msg = format!("Field `{}` cannot be both `required` and `deprecated`", field_name);
+            return Err(Error::new_spanned(field_name, msg))
+        }
+
+        if cfg.secret && cfg.doc_default.is_some() {
+            let msg = format!("Field `{}` is secret, so its default cannot be documented with `doc_default`", field_name);
+            return Err(Error::new_spanned(field_name, msg))
         }
 
         if cfg.docs.is_none() {
             cfg.docs = desugared_docs(&field.attrs);
         }
 
-        cfg
+        Ok(cfg)
+    }
+
+    /// Whether this field is parsed from strings, rather than deserialized.
+    pub fn is_parsed(&self) -> bool {
+        self.parse_with.is_some() || self.from_str || self.bytes.is_some() || self.time_of_day || self.percent
+            || self.flags
     }
 }
 
-fn filter_attrs(attrs: &[Attribute]) -> Vec<&NestedMetaItem> {
-    let mut cfg_attrs = vec![];
-    for attr in attrs {
-        match attr.value {
-            MetaItem::List(ref name, ref members) if name.as_ref() == "configure"   => {
-                cfg_attrs.extend(members);
-            }
-            _   => continue
+fn filter_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("configure"))
+}
+
+fn attr_name(meta: &ParseNestedMeta) -> String {
+    meta.path.get_ident().map(|ident| ident.to_string()).unwrap_or_default()
+}
+
+/// The value of an attribute of the form `name = "value"`.
+fn string(meta: &ParseNestedMeta, form: &str) -> Result<String> {
+    if !meta.input.peek(Token![=]) {
+        return Err(unsupported(meta, form))
+    }
+    match meta.value()?.parse()? {
+        Lit::Str(string)    => Ok(string.value()),
+        value               => Err(Error::new_spanned(value, unsupported(meta, form))),
+    }
+}
+
+/// The path of an attribute of the form `name = "path::to::item"`.
+fn path(meta: &ParseNestedMeta, form: &str) -> Result<ExprPath> {
+    if !meta.input.peek(Token![=]) {
+        return Err(unsupported(meta, form))
+    }
+    let string: LitStr = meta.value()?.parse()?;
+    string.parse().map_err(|err| {
+        let msg = format!("Invalid `configure({})` path `{}`: {}", attr_name(meta), string.value(), err);
+        Error::new(string.span(), msg)
+    })
+}
+
+/// Check that an attribute is a bare word, of the form `name`.
+fn word(meta: &ParseNestedMeta, form: &str) -> Result<bool> {
+    if meta.input.is_empty() || meta.input.peek(Token![,]) {
+        Ok(true)
+    } else {
+        Err(unsupported(meta, form))
+    }
+}
+
+fn unsupported(meta: &ParseNestedMeta, form: &str) -> Error {
+    let msg = format!("Unsupported `configure({})` attribute; only supported form is {}", attr_name(meta), form);
+    meta.error(msg)
+}
+
+/// An inclusive range, `$MIN..=$MAX`, or a range unbounded at one end.
+fn field_range(meta: &ParseNestedMeta) -> Result<FieldRange> {
+    let form = "#[configure(range = \"$MIN..=$MAX\")]";
+    if !meta.input.peek(Token![=]) {
+        return Err(unsupported(meta, form))
+    }
+    let string: LitStr = meta.value()?.parse()?;
+    let text = string.value();
+    let expr = string.parse::<ExprRange>().map_err(|err| {
+        Error::new(string.span(), format!("Invalid `configure(range)` range `{}`: {}", text, err))
+    })?;
+    match (&expr.start, &expr.limits, &expr.end) {
+        (None, _, None)                             => {
+            Err(Error::new(string.span(), format!("The `configure(range)` range `{}` has no bounds", text)))
+        }
+        (_, RangeLimits::HalfOpen(_), Some(_))      => {
+            let msg = format!("The `configure(range)` range `{}` must include its end, as in {}", text, form);
+            Err(Error::new(string.span(), msg))
         }
+        _                                           => Ok(FieldRange { expr, text }),
     }
+}
 
-    cfg_attrs
+/// Whether the type of this field is `f32` or `f64`, or an `Option` of one.
+fn is_float(field: &Field) -> bool {
+    match *super::generic_arg(&field.ty, "Option").unwrap_or(&field.ty) {
+        Type::Path(ref ty)  => ty.path.is_ident("f32") || ty.path.is_ident("f64"),
+        _                   => false,
+    }
 }
 
-fn project_name(attr: &MetaItem) -> Option<String> {
-    if let MetaItem::NameValue(_, ref name) = *attr {
-        if let Lit::Str(ref string, _) = *name {
-            return Some(string.clone())
+fn field_default(meta: &ParseNestedMeta) -> Result<FieldDefault> {
+    let span = meta.path.get_ident().unwrap().span();
+    if !meta.input.peek(Token![=]) {
+        let msg = "Unsupported `configure(default)` attribute; supported forms are \
+                   #[configure(default)] and #[configure(default = \"$EXPR\")]";
+        return match word(meta, "") {
+            Ok(_)   => Ok(FieldDefault { span, expr: None }),
+            Err(_)  => Err(meta.error(msg)),
+        }
+    }
+
+    let string: LitStr = meta.value()?.parse()?;
+    match string.parse::<Expr>() {
+        Ok(expr)    => Ok(FieldDefault { span, expr: Some(expr) }),
+        Err(err)    => {
+            let msg = format!("Invalid `configure(default)` expression `{}`: {}", string.value(), err);
+            Err(Error::new(string.span(), msg))
+        }
+    }
+}
+
+/// The encoding of a `bytes` attribute, as the name of its decoder.
+fn bytes(meta: &ParseNestedMeta) -> Result<Ident> {
+    let form = "#[configure(bytes = \"utf8\")], #[configure(bytes = \"hex\")] or #[configure(bytes = \"base64\")]";
+    if !meta.input.peek(Token![=]) {
+        return Err(unsupported(meta, form))
+    }
+    let string: LitStr = meta.value()?.parse()?;
+    match &string.value()[..] {
+        encoding @ "utf8" | encoding @ "hex" | encoding @ "base64"  => Ok(Ident::new(encoding, string.span())),
+        encoding                                                    => {
+            let msg = format!("Unknown `configure(bytes)` encoding `{}`; supported encodings are \
+                               `utf8`, `hex` and `base64`", encoding);
+            Err(Error::new(string.span(), msg))
+        }
+    }
+}
+
+fn case(meta: &ParseNestedMeta) -> Result<Case> {
+    let form = "#[configure(case = \"shouty_snake\")], #[configure(case = \"snake\")], \
+                #[configure(case = \"kebab\")] or #[configure(case = \"lower\")]";
+    if !meta.input.peek(Token![=]) {
+        return Err(unsupported(meta, form))
+    }
+    let string: LitStr = meta.value()?.parse()?;
+    match &string.value()[..] {
+        "shouty_snake"  => Ok(Case::ShoutySnake),
+        "snake"         => Ok(Case::Snake),
+        "kebab"         => Ok(Case::Kebab),
+        "lower"         => Ok(Case::Lower),
+        case            => {
+            let msg = format!("Unknown `configure(case)` case `{}`; supported cases are \
+                               `shouty_snake`, `snake`, `kebab` and `lower`", case);
+            Err(Error::new(string.span(), msg))
+        }
+    }
+}
+
+fn docs_module(meta: &ParseNestedMeta) -> Result<Ident> {
+    if !meta.input.peek(Token![=]) {
+        return Err(unsupported(meta, "#[configure(docs_module = \"$NAME\")]"))
+    }
+    let string: LitStr = meta.value()?.parse()?;
+    match parse_str::<Ident>(&string.value()) {
+        Ok(ident)   => Ok(Ident::new(&ident.to_string(), string.span())),
+        Err(_)      => {
+            let msg = format!("Invalid `configure(docs_module)` name `{}`; it must be an identifier", string.value());
+            Err(Error::new(string.span(), msg))
         }
     }
-    panic!("Unsupported `configure(name)` attribute; only supported form is #[configure(name = \"$NAME\")]")
 }
 
-fn gen_docs(attr: &MetaItem) -> bool {
-    if let MetaItem::Word(_) = *attr {
-        return true
-    } else  {
-        panic!("Unsupported `configure(docs)` attribute; only supported form is #[configure(docs)]")
+fn separator(meta: &ParseNestedMeta) -> Result<String> {
+    if !meta.input.peek(Token![=]) {
+        return Err(unsupported(meta, "#[configure(separator = \"$SEPARATOR\")]"))
     }
+    let string: LitStr = meta.value()?.parse()?;
+    if string.value().is_empty() {
+        return Err(Error::new(string.span(), "The `configure(separator)` cannot be empty"))
+    }
+    Ok(string.value())
 }
 
-fn field_docs(attr: &MetaItem) -> String {
-    if let MetaItem::NameValue(_, ref name) = *attr {
-        if let Lit::Str(ref string, _) = *name {
-            return string.clone()
+/// The key serde deserializes a field from: its `rename`, or else its name
+/// with the struct's `rename_all` rule applied.
+fn serde_key(field: &Field, rename_all: Option<&str>) -> String {
+    if let Some(rename) = serde_name(&field.attrs, "rename") {
+        return rename
+    }
+    let name = field.ident.as_ref().unwrap().unraw().to_string();
+    let capitalize = |word: &str| {
+        let mut chars = word.chars();
+        chars.next().map(|first| first.to_uppercase().chain(chars).collect::<String>()).unwrap_or_default()
+    };
+    // The rules apply to snake case names, as serde's do.
+    match rename_all {
+        Some("UPPERCASE") | Some("SCREAMING_SNAKE_CASE")    => name.to_ascii_uppercase(),
+        Some("PascalCase")                                  => name.split('_').map(capitalize).collect(),
+        Some("camelCase")                                   => {
+            let pascal = name.split('_').map(capitalize).collect::<String>();
+            let mut chars = pascal.chars();
+            chars.next().map(|first| first.to_lowercase().chain(chars).collect()).unwrap_or_default()
         }
+        Some("kebab-case")                                  => name.replace('_', "-"),
+        Some("SCREAMING-KEBAB-CASE")                        => name.to_ascii_uppercase().replace('_', "-"),
+        _                                                   => name,
+    }
+}
+
+/// The value of a serde attribute of the form `#[serde(name = "value")]`,
+/// or the deserialize half of `#[serde(name(deserialize = "value"))]`.
+///
+/// Serde reports malformed serde attributes, so they are ignored here.
+pub fn serde_name(attrs: &[Attribute], name: &str) -> Option<String> {
+    let mut value = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident(name) && meta.input.peek(Token![=]) {
+                value = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident(name) && meta.input.peek(token::Paren) {
+                meta.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("deserialize") {
+                        value = Some(meta.value()?.parse::<LitStr>()?.value());
+                    } else {
+                        meta.value()?.parse::<LitStr>()?;
+                    }
+                    Ok(())
+                })?;
+            } else if meta.input.peek(Token![=]) {
+                meta.value()?.parse::<Expr>()?;
+            } else if meta.input.peek(token::Paren) {
+                let _content;
+                parenthesized!(_content in meta.input);
+            }
+            Ok(())
+        });
     }
-    panic!("Unsupported `configure(docs)` attribute; only supported form is #[configure(docs = \"$NAME\")]")
+    value
+}
+
+/// The doc comment in these attributes, joined into one line.
+pub fn desugared_docs(attrs: &[Attribute]) -> Option<String> {
+    let lines = doc_lines(attrs);
+    let docs = lines.iter().map(|line| line.trim()).filter(|line| !line.is_empty()).collect::<Vec<_>>();
+    if docs.is_empty() { None } else { Some(docs.join(" ")) }
+}
+
+/// The doc comment in these attributes, keeping its lines and paragraphs.
+pub fn desugared_paragraphs(attrs: &[Attribute]) -> Option<String> {
+    let lines = doc_lines(attrs);
+    let lines = lines.iter().map(|line| line.strip_prefix(' ').unwrap_or(line)).collect::<Vec<_>>();
+    let docs = lines.join("\n");
+    let docs = docs.trim();
+    if docs.is_empty() { None } else { Some(docs.to_owned()) }
 }
 
-fn desugared_docs(attrs: &[Attribute]) -> Option<String> {
-    if let Some(attr) = attrs.iter().find(|attr| attr.is_sugared_doc) {
-        if let MetaItem::NameValue(_, ref name) = attr.value {
-            if let Lit::Str(ref string, _) = *name {
-                return Some(string.trim_left_matches('/').to_owned())
+fn doc_lines(attrs: &[Attribute]) -> Vec<String> {
+    let mut lines = vec![];
+    for attr in attrs {
+        if let Meta::NameValue(ref meta) = attr.meta {
+            if meta.path.is_ident("doc") {
+                if let Expr::Lit(ExprLit { lit: Lit::Str(ref string), .. }) = meta.value {
+                    // An empty `///` line separates paragraphs.
+                    let value = string.value();
+                    if value.is_empty() {
+                        lines.push(value)
+                    } else {
+                        lines.extend(value.lines().map(String::from))
+                    }
+                }
             }
         }
     }
-    None
+    lines
 }
@@ -1,84 +1,1831 @@
 extern crate heck;
 extern crate proc_macro;
+extern crate proc_macro2;
 extern crate syn;
 
 #[macro_use] extern crate quote;
 
+mod args;
 mod attrs;
+mod schema;
 
 use std::env;
 use std::fmt::Write;
 
-use heck::ShoutySnakeCase;
-use proc_macro::TokenStream;
-use quote::Tokens;
+use heck::{ShoutySnakeCase, SnakeCase};
+use proc_macro2::{Span, TokenStream};
 use syn::*;
+use syn::ext::IdentExt;
 
-use attrs::{CfgAttrs, FieldAttrs};
+use attrs::{Case, CfgAttrs, FieldAttrs};
 
 #[proc_macro_derive(Configure, attributes(configure))]
-pub fn derive_configure(input: TokenStream) -> TokenStream {
-    let ast = parse_derive_input(&input.to_string()).unwrap();
-    let gen = impl_configure(ast);
-    gen.parse().unwrap()
+pub fn derive_configure(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    match impl_configure(ast) {
+        Ok(gen)     => gen.into(),
+        Err(err)    => compile_error(err).into(),
+    }
+}
+
+// `Error::to_compile_error` refers to `::core`, which is not in scope in
+// crates using the 2015 edition.
+fn compile_error(err: Error) -> TokenStream {
+    err.into_iter().map(|err| {
+        let msg = err.to_string();
+        quote_spanned!(err.span()=> compile_error!(#msg);)
+    }).collect()
+}
+
+fn impl_configure(ast: DeriveInput) -> Result<TokenStream> {
+    let ty = &ast.ident;
+    let generics = &ast.generics;
+    let cfg_attrs = CfgAttrs::new(&ast.attrs[..])?;
+    let project = project_name(&cfg_attrs, ty, env::var("CARGO_PKG_NAME").ok())?;
+    let vars = VarNames {
+        prefix: cfg_attrs.prefix.clone().unwrap_or_else(|| project.clone()),
+        case: cfg_attrs.case.unwrap_or(Case::ShoutySnake),
+        separator: cfg_attrs.separator.clone(),
+    };
+    if let Data::Enum(ref data) = ast.data {
+        return impl_enum(&ast, data, &cfg_attrs, &project, &vars)
+    }
+    if let Data::Struct(DataStruct { fields: Fields::Unnamed(ref fields), .. }) = ast.data {
+        if fields.unnamed.len() == 1 {
+            return impl_newtype(&ast, &fields.unnamed[0], &cfg_attrs, &project, &vars)
+        }
+    }
+    if cfg_attrs.env.is_some() {
+        let msg = "The `configure(env)` attribute is only supported on newtype structs; \
+                   set it on each field instead";
+        return Err(Error::new_spanned(ty, msg))
+    }
+    let fields = &assert_ast_is_struct(&ast)?[..];
+    let attrs = fields.iter().map(|field| FieldAttrs::new(field, &cfg_attrs)).collect::<Result<Vec<_>>>()?;
+    let docs = if cfg_attrs.docs {
+        let (docs, rows) = docs(&ast.attrs, fields, &attrs, &project, &vars);
+        let consts = env_consts(fields, &attrs, &vars)?;
+        Some(environment_docs(ty, generics, &cfg_attrs, &project, &docs, &rows, consts))
+    } else {
+        None
+    };
+    let register = register_docs(&cfg_attrs);
+    let json_schema = if cfg_attrs.json_schema {
+        let description = attrs::desugared_docs(&ast.attrs);
+        let schema = schema::json_schema(&ty.to_string(), description.as_deref(), fields, &attrs, &vars);
+        quote! {
+            /// A draft-07 JSON Schema of the environment variables this
+            /// configuration is read from, with a property for each
+            /// variable. The types of the properties are mapped from the
+            /// types of the fields on a best-effort basis.
+            pub fn config_schema() -> ::std::string::String {
+                ::std::string::String::from(#schema)
+            }
+        }
+    } else {
+        quote!()
+    };
+    let builder = if cfg_attrs.builder { builder(&ast, fields) } else { quote!() };
+    let partial = if cfg_attrs.partial {
+        partial(&ast, &struct_info(&cfg_attrs, &attrs), fields, &attrs, &project, &vars)
+    } else {
+        quote!()
+    };
+    let clap = if cfg!(feature = "clap") { args::clap_args(fields, &attrs) } else { quote!() };
+    let diff = if cfg_attrs.diff { diff(fields, &attrs) } else { quote!() };
+    let regenerate_changed = regenerate_changed(fields);
+    let struct_info = struct_info(&cfg_attrs, &attrs);
+    let prefix = &vars.prefix;
+    let deserialize = deserialize(fields, &attrs, &project, &vars);
+    let validate = validate(&cfg_attrs, fields, &attrs);
+    let generate_from = generate_from(&struct_info, &validate, fields, &attrs, &project, &vars);
+    let collect_errors = if cfg_attrs.collect_errors { collect_errors(fields, &attrs, &project, &vars) } else { quote!() };
+    let default = derive_default(&cfg_attrs, ty, generics, fields, &attrs)?;
+    let debug = if cfg_attrs.redacted_debug { Some(redacted_debug(&ast, fields, &attrs)?) } else { None };
+    let env_vars = env_vars(&attrs, &vars);
+    let env_vars_fn = env_vars_fn(fields, &attrs, &vars);
+    let flattened_keys = flattened_keys(&attrs);
+    let example_fields = example_fields(&attrs, &vars);
+    let field_metadata = field_metadata(fields, &attrs, &vars);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let mut bounds = vec![quote!(#ty #ty_generics: ::serde::Deserialize<'static>)];
+    bounds.extend(fields.iter().zip(&attrs).filter(|&(_, attrs)| attrs.type_default).map(|(field, _)| {
+        let ty = &field.ty;
+        quote!(#ty: ::std::default::Default)
+    }));
+    if cfg_attrs.collect_errors {
+        bounds.extend(deserialized_bounds(fields, &attrs));
+    }
+    let configure_where = where_with(generics, &bounds);
+
+    Ok(quote!{
+        impl #impl_generics ::configure::Configure for #ty #ty_generics #configure_where {
+            fn generate() -> ::std::result::Result<Self, ::configure::DeserializeError> {
+                #register
+                #struct_info
+                let result = ::configure::source::CONFIGURATION.generate_struct(#project, &INFO, |deserializer| {
+                    #deserialize
+                    Ok(cfg)
+                });
+                #collect_errors
+                let cfg = result?;
+                #validate
+                Ok(cfg)
+            }
+        }
+
+        impl #impl_generics #ty #ty_generics #where_clause {
+            /// The environment variables this configuration is read from,
+            /// with their deprecated aliases, in the order of the fields.
+            /// The variables of flattened fields are not known here, so
+            /// they are only included by `env_vars`; those of fields hidden
+            /// from the docs with `hide_docs` are included.
+            pub const ENV_VARS: &'static [&'static str] = &[#(#env_vars),*];
+
+            #env_vars_fn
+
+            /// What is known about each field of this configuration, in the
+            /// order of the fields, after its attributes are applied.
+            pub const FIELDS: &'static [::configure::FieldMetadata] = &[#(#field_metadata),*];
+
+            /// Render this configuration as `NAME=value` lines, using the
+            /// environment variable names it would be generated from. The
+            /// values of secret fields are replaced with `<redacted>`.
+            // The bound is higher-ranked so this is only checked when the
+            // method is called; types which do not implement `Serialize` can
+            // still derive `Configure`.
+            pub fn to_env_lines(&self) -> ::std::result::Result<::std::vec::Vec<::std::string::String>, ::configure::DeserializeError>
+                where for<'a> &'a Self: ::serde::Serialize,
+            {
+                #struct_info
+                ::configure::to_env_lines_struct(#prefix, &INFO, &self)
+            }
+
+            /// Render the default value of this configuration as a commented
+            /// TOML template, with the docs and environment variable of each
+            /// field. Secret fields, and fields which default to `None`, are
+            /// commented out; fields hidden from the docs are left out.
+            // A plain `Self: Default` bound names no generic parameter, so
+            // rustc would check it where the struct is defined, failing for
+            // structs without a default. The binder defers it to the call.
+            pub fn example_toml() -> ::std::result::Result<::std::string::String, ::configure::DeserializeError>
+                where for<'a> Self: ::std::default::Default,
+                      for<'a> &'a Self: ::serde::Serialize,
+            {
+                static FIELDS: &[::configure::ExampleField] = &[#(#example_fields),*];
+                ::configure::example_toml_struct(FIELDS, &&<Self as ::std::default::Default>::default())
+            }
+
+            /// Render the default value of this configuration as a commented
+            /// `.env` file, with the docs of each field, in the same format
+            /// the environment is read in. Secret fields are left blank, and
+            /// fields which default to `None` are commented out; fields which
+            /// are not read from the environment, flattened fields and fields
+            /// hidden from the docs are left out.
+            // Higher-ranked for the same reason as in `example_toml`: structs
+            // without a `Default` implementation can still derive `Configure`.
+            pub fn example_env_file() -> ::std::result::Result<::std::string::String, ::configure::DeserializeError>
+                where for<'a> Self: ::std::default::Default,
+                      for<'a> &'a Self: ::serde::Serialize,
+            {
+                static FIELDS: &[::configure::ExampleField] = &[#(#example_fields),*];
+                ::configure::example_env_file_struct(FIELDS, &&<Self as ::std::default::Default>::default())
+            }
+
+            /// Generate this configuration from an in-memory value, like a
+            /// `serde_json::Value`, instead of from the active configuration
+            /// source, which is not used at all. The value is deserialized
+            /// as the struct would be from a source, and then validated.
+            pub fn from_value<D>(value: D) -> ::std::result::Result<Self, ::configure::DeserializeError>
+                where D: ::serde::Deserializer<'static> + 'static,
+                      Self: ::serde::Deserialize<'static>,
+            {
+                #struct_info
+                let deserializer = ::configure::overlay::erase(value, &INFO);
+                #deserialize
+                #validate
+                Ok(cfg)
+            }
+
+            #regenerate_changed
+
+            #generate_from
+
+            #json_schema
+
+            #diff
+
+            #clap
+        }
+
+        impl #impl_generics ::configure::FlattenedKeys for #ty #ty_generics #where_clause {
+            const FLATTENED_KEYS: &'static [&'static str] = &[#(#flattened_keys),*];
+        }
+
+        #builder
+
+        #partial
+
+        #default
+
+        #debug
+
+        #docs
+    })
+}
+
+/// Derive `Configure` for an enum, which is deserialized from the source as
+/// a whole: the variable named after the enum selects the variant, and the
+/// fields of the variant are read like those of a flattened field.
+fn impl_enum(ast: &DeriveInput, data: &DataEnum, cfg_attrs: &CfgAttrs, project: &str, vars: &VarNames) -> Result<TokenStream> {
+    let ty = &ast.ident;
+    let generics = &ast.generics;
+    let unsupported = [
+        ("derive_default", cfg_attrs.derive_default),
+        ("collect_errors", cfg_attrs.collect_errors),
+        ("json_schema", cfg_attrs.json_schema),
+        ("partial", cfg_attrs.partial),
+        ("diff", cfg_attrs.diff),
+        ("redacted_debug", cfg_attrs.redacted_debug),
+        ("builder", cfg_attrs.builder),
+        // The variable selecting the variant is already named after the enum.
+        ("use_type_name", cfg_attrs.use_type_name),
+    ];
+    if let Some(&(name, _)) = unsupported.iter().find(|&&(_, set)| set) {
+        let msg = format!("The `configure({})` attribute is not supported on enums", name);
+        return Err(Error::new_spanned(ty, msg))
+    }
+    for variant in &data.variants {
+        if let Fields::Unnamed(ref fields) = variant.fields {
+            if fields.unnamed.len() != 1 {
+                let msg = format!("Cannot derive `Configure` for enum with tuple variant `{}`", variant.ident);
+                return Err(Error::new_spanned(fields, msg))
+            }
+        }
+        if let Some(field) = variant.fields.iter().find(|field| field.attrs.iter().any(|attr| attr.path().is_ident("configure"))) {
+            let msg = "`configure` attributes are not supported on the fields of enum variants";
+            return Err(Error::new_spanned(field, msg))
+        }
+    }
+
+    let docs = if cfg_attrs.docs {
+        let (docs, rows) = enum_docs(ty, &ast.attrs, data, project, vars);
+        let consts = enum_env_consts(ty, data, vars)?;
+        Some(environment_docs(ty, generics, cfg_attrs, project, &docs, &rows, consts))
+    } else {
+        None
+    };
+    let register = register_docs(cfg_attrs);
+    let struct_info = struct_info(cfg_attrs, &[]);
+    let validate = validate(cfg_attrs, &[], &[]);
+    let env_vars = enum_env_vars(ty, data, vars).into_iter().map(|(_, var_name)| var_name);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let configure_where = where_with(generics, &[quote!(#ty #ty_generics: ::serde::Deserialize<'static>)]);
+
+    Ok(quote!{
+        impl #impl_generics ::configure::Configure for #ty #ty_generics #configure_where {
+            fn generate() -> ::std::result::Result<Self, ::configure::DeserializeError> {
+                #register
+                #struct_info
+                let cfg = ::configure::source::CONFIGURATION.generate_struct(#project, &INFO, |deserializer| {
+                    let cfg: Self = ::serde::Deserialize::deserialize(deserializer)?;
+                    Ok(cfg)
+                })?;
+                #validate
+                Ok(cfg)
+            }
+        }
+
+        impl #impl_generics #ty #ty_generics #where_clause {
+            /// The environment variables this configuration is read from:
+            /// the variable selecting the variant, then the variables of
+            /// the fields of each variant. The variables of newtype
+            /// variants are not included.
+            pub const ENV_VARS: &'static [&'static str] = &[#(#env_vars),*];
+
+            /// Generate this configuration from an in-memory value, like a
+            /// `serde_json::Value`, instead of from the active configuration
+            /// source, which is not used at all. The value is deserialized
+            /// as the enum would be from a source, and then validated.
+            pub fn from_value<D>(value: D) -> ::std::result::Result<Self, ::configure::DeserializeError>
+                where D: ::serde::Deserializer<'static> + 'static,
+                      Self: ::serde::Deserialize<'static>,
+            {
+                #struct_info
+                let deserializer = ::configure::overlay::erase(value, &INFO);
+                let cfg: Self = ::serde::Deserialize::deserialize(deserializer)?;
+                #validate
+                Ok(cfg)
+            }
+        }
+
+        #docs
+    })
 }
 
-fn impl_configure(ast: DeriveInput) -> Tokens {
+/// Derive `Configure` for a newtype struct, whose whole value is read from
+/// one variable named after the package, or from the `value` key of its
+/// table in the `Cargo.toml`.
+fn impl_newtype(ast: &DeriveInput, field: &Field, cfg_attrs: &CfgAttrs, project: &str, vars: &VarNames) -> Result<TokenStream> {
     let ty = &ast.ident;
     let generics = &ast.generics;
-    let cfg_attrs = CfgAttrs::new(&ast.attrs[..]);
-    let fields = assert_ast_is_struct(&ast);
-    let project = cfg_attrs.name.or_else(|| env::var("CARGO_PKG_NAME").ok()).unwrap();
-    let docs = if cfg_attrs.docs { Some(docs(fields, &project)) } else { None };
+    let inner = &field.ty;
+    let unsupported = [
+        ("derive_default", cfg_attrs.derive_default),
+        ("collect_errors", cfg_attrs.collect_errors),
+        ("json_schema", cfg_attrs.json_schema),
+        ("partial", cfg_attrs.partial),
+        ("diff", cfg_attrs.diff),
+        ("redacted_debug", cfg_attrs.redacted_debug),
+        ("builder", cfg_attrs.builder),
+    ];
+    if let Some(&(name, _)) = unsupported.iter().find(|&&(_, set)| set) {
+        let msg = format!("The `configure({})` attribute is not supported on newtype structs", name);
+        return Err(Error::new_spanned(ty, msg))
+    }
+    if field.attrs.iter().any(|attr| attr.path().is_ident("configure")) {
+        let msg = "`configure` attributes are not supported on the field of a newtype struct; \
+                   set them on the struct instead";
+        return Err(Error::new_spanned(field, msg))
+    }
+
+    let var_name = cfg_attrs.env.clone().unwrap_or_else(|| vars.case.apply(&vars.prefix));
+    let msg = format!("missing configuration: set {} or [package.metadata.{}].value", var_name, project);
+    let struct_info = {
+        let prefix = opt_str(&cfg_attrs.prefix);
+        let separator = opt_str(&cfg_attrs.separator);
+        quote! {
+            static INFO: ::configure::source::StructInfo = ::configure::source::StructInfo {
+                prefix: #prefix,
+                fields: &[::configure::source::FieldInfo {
+                    env: Some(#var_name),
+                    ..::configure::source::FieldInfo::new("value")
+                }],
+                case: None,
+                separator: #separator,
+            };
+        }
+    };
+    let validate = validate(cfg_attrs, &[], &[]);
+    let docs = if cfg_attrs.docs {
+        let field_docs = attrs::desugared_docs(&field.attrs).or_else(|| attrs::desugared_docs(&ast.attrs));
+        let var_type = type_name(inner);
+        let mut docs = format!("These environment variables can be used to configure {}.\n\n", project);
+        match field_docs {
+            Some(ref field_docs)    => { let _ = writeln!(docs, "- **{}** ({}): {}", var_name, var_type, field_docs); }
+            None                    => { let _ = writeln!(docs, "- **{}** ({})", var_name, var_type); }
+        }
+        docs.push_str(OUTRO);
+        let rows = [Row {
+            var_name: var_name.clone(),
+            var_type,
+            default: None,
+            required: false,
+            docs: field_docs,
+        }];
+        Some(environment_docs(ty, generics, cfg_attrs, project, &docs, &rows, quote!()))
+    } else {
+        None
+    };
+    let register = register_docs(cfg_attrs);
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let configure_where = where_with(generics, &[quote!(#inner: ::serde::Deserialize<'static>)]);
 
-    quote!{
-        impl #generics ::configure::Configure for #ty #generics {
+    Ok(quote!{
+        impl #impl_generics ::configure::Configure for #ty #ty_generics #configure_where {
             fn generate() -> ::std::result::Result<Self, ::configure::DeserializeError> {
-                let deserializer = ::configure::source::CONFIGURATION.get(#project);
-                ::serde::Deserialize::deserialize(deserializer)
+                #register
+                #struct_info
+                let cfg = ::configure::source::CONFIGURATION.generate_struct(#project, &INFO, |deserializer| {
+                    let mut value = None;
+                    ::configure::overlay::overlay(deserializer, &mut value, &["value"], &[
+                        &|value: &mut Option<#inner>, deserializer| {
+                            *value = Some(::serde::Deserialize::deserialize(deserializer)?);
+                            Ok(())
+                        }
+                    ])?;
+                    match value {
+                        Some(value) => Ok(#ty(value)),
+                        None        => Err(::serde::de::Error::custom(#msg)),
+                    }
+                })?;
+                #validate
+                Ok(cfg)
             }
         }
 
+        impl #impl_generics #ty #ty_generics #where_clause {
+            /// The environment variable this configuration is read from.
+            pub const ENV_VARS: &'static [&'static str] = &[#var_name];
+        }
+
         #docs
+    })
+}
+
+/// The name of the configuration: the `name` attribute or else the package
+/// name, followed with `use_type_name` by the name of the type.
+fn project_name(cfg_attrs: &CfgAttrs, ty: &Ident, pkg_name: Option<String>) -> Result<String> {
+    let name = match (cfg_attrs.name.clone(), pkg_name) {
+        (Some(name), _) | (None, Some(name))    => name,
+        (None, None)                            => {
+            let msg = "Cannot determine the package name, because `CARGO_PKG_NAME` is not set; \
+                       name the package with #[configure(name = \"$NAME\")]";
+            return Err(Error::new_spanned(ty, msg))
+        }
+    };
+    if !cfg_attrs.use_type_name {
+        return Ok(name)
+    }
+    match type_key(ty) {
+        Some(key)   => Ok(format!("{}_{}", name, key)),
+        None        => {
+            let msg = format!("`{}` has no name left for #[configure(use_type_name)] once its suffix is \
+                               removed; name it with #[configure(name = \"$NAME\")] instead", ty);
+            Err(Error::new_spanned(ty, msg))
+        }
     }
 }
 
-fn assert_ast_is_struct(ast: &DeriveInput) -> &[Field] {
-    match ast.body {
-        Body::Struct(VariantData::Struct(ref fields))   => fields,
-        Body::Struct(VariantData::Unit)                 => &[],
-        Body::Struct(VariantData::Tuple(_))             => {
-            panic!("Cannot derive `Configure` for tuple struct")
+/// The name of a type for `use_type_name`, in snake case and without a
+/// `Configuration`, `Config` or `Cfg` suffix: `server` for `ServerConfig`.
+fn type_key(ty: &Ident) -> Option<String> {
+    let name = ty.unraw().to_string();
+    let stripped = ["Configuration", "Config", "Cfg"].iter()
+        .find_map(|suffix| name.strip_suffix(suffix))
+        .unwrap_or(&name);
+    if stripped.is_empty() { None } else { Some(stripped.to_snake_case()) }
+}
+
+fn assert_ast_is_struct(ast: &DeriveInput) -> Result<Vec<&Field>> {
+    match ast.data {
+        Data::Struct(DataStruct { fields: Fields::Named(ref fields), .. })      => Ok(fields.named.iter().collect()),
+        Data::Struct(DataStruct { fields: Fields::Unit, .. })                   => Ok(vec![]),
+        Data::Struct(DataStruct { fields: Fields::Unnamed(ref fields), .. })    => {
+            // Newtype structs are derived by `impl_newtype`.
+            Err(Error::new_spanned(fields, "Cannot derive `Configure` for tuple struct with more than one field"))
+        }
+        // Enums are derived by `impl_enum`.
+        Data::Enum(DataEnum { enum_token, .. })                                 => {
+            Err(Error::new_spanned(enum_token, "Cannot derive `Configure` for enum"))
+        }
+        Data::Union(DataUnion { union_token, .. })                              => {
+            Err(Error::new_spanned(union_token, "Cannot derive `Configure` for union"))
+        }
+    }
+}
+
+/// A `diff` method listing the fields which differ between two values of
+/// the struct, in the order of the fields. Skipped fields are not compared.
+fn diff(fields: &[&Field], attrs: &[FieldAttrs]) -> TokenStream {
+    let fields = fields.iter().zip(attrs).filter(|&(_, attrs)| !attrs.skip).collect::<Vec<_>>();
+    let names = fields.iter().map(|&(field, _)| field.ident.as_ref().unwrap()).collect::<Vec<_>>();
+    let keys = names.iter().map(|name| name.unraw().to_string());
+    let secrets = fields.iter().map(|&(_, attrs)| attrs.secret);
+    let bounds = fields.iter().map(|&(field, _)| {
+        let ty = &field.ty;
+        quote!(#ty: ::std::cmp::PartialEq + ::std::fmt::Debug)
+    });
+
+    quote! {
+        /// The fields whose values differ between this configuration and
+        /// `other`, in the order of the fields, with their values rendered
+        /// with `Debug`. The values of secret fields are redacted, and
+        /// skipped fields are not compared.
+        pub fn diff(&self, other: &Self) -> ::std::vec::Vec<::configure::FieldDiff>
+            where #(#bounds,)*
+        {
+            let mut diffs = ::std::vec::Vec::new();
+            #(
+                diffs.extend(::configure::FieldDiff::field(#keys, &self.#names, &other.#names, #secrets));
+            )*
+            diffs
+        }
+    }
+}
+
+/// A `{Struct}Builder` with a setter for each field, and a `builder` method
+/// creating one.
+fn builder(ast: &DeriveInput, fields: &[&Field]) -> TokenStream {
+    let ty = &ast.ident;
+    let vis = &ast.vis;
+    let generics = &ast.generics;
+    let builder = format_ident!("{}Builder", ty);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let names = fields.iter().map(|field| field.ident.as_ref().unwrap()).collect::<Vec<_>>();
+    let tys = fields.iter().map(|field| &field.ty).collect::<Vec<_>>();
+    // Each setter is as visible as its field, so the builder gives no more
+    // access to the struct than it already has.
+    let setter_vis = fields.iter().map(|field| &field.vis);
+    let setter_docs = names.iter().map(|name| {
+        format!("Set `{}`, whatever the source of configuration says.", name)
+    });
+    let doc = format!("A builder generating a `{}`, with some fields set by the program instead \
+                       of read from the source of configuration.", ty);
+
+    quote! {
+        #[doc = #doc]
+        #vis struct #builder #generics #where_clause {
+            #(#names: ::std::option::Option<#tys>,)*
+        }
+
+        impl #impl_generics #ty #ty_generics #where_clause {
+            /// A builder which generates this configuration, then overrides
+            /// the fields set on the builder. Fields which are not set are
+            /// generated just as they are by `generate`.
+            pub fn builder() -> #builder #ty_generics {
+                #builder {
+                    #(#names: None,)*
+                }
+            }
         }
-        Body::Enum(_)                                   => {
-            panic!("Cannot derive `Configure` for enum")
+
+        impl #impl_generics #builder #ty_generics #where_clause {
+            #(
+                #[doc = #setter_docs]
+                #setter_vis fn #names<V: ::std::convert::Into<#tys>>(mut self, value: V) -> Self {
+                    self.#names = Some(value.into());
+                    self
+                }
+            )*
+
+            /// Generate the configuration, then override the fields set on
+            /// this builder. The overrides are not validated.
+            pub fn build(self) -> ::std::result::Result<#ty #ty_generics, ::configure::DeserializeError>
+                where for<'a> #ty #ty_generics: ::configure::Configure,
+            {
+                let mut cfg = <#ty #ty_generics as ::configure::Configure>::generate()?;
+                #(
+                    if let Some(value) = self.#names {
+                        cfg.#names = value;
+                    }
+                )*
+                Ok(cfg)
+            }
         }
     }
 }
 
-fn docs(fields: &[Field], project: &str) -> Tokens {
-    let mut docs = format!("These environment variables can be used to configure {}.\n\n", project);
-    for field in fields {
+/// A `{Struct}Partial` in which every field is optional, and a `merge`
+/// method overwriting the fields of the struct which are set in one.
+fn partial(
+    ast: &DeriveInput,
+    struct_info: &TokenStream,
+    fields: &[&Field],
+    attrs: &[FieldAttrs],
+    project: &str,
+    vars: &VarNames,
+) -> TokenStream {
+    let ty = &ast.ident;
+    let vis = &ast.vis;
+    let generics = &ast.generics;
+    let partial = format_ident!("{}Partial", ty);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let names = fields.iter().map(|field| field.ident.as_ref().unwrap()).collect::<Vec<_>>();
+    let decls = fields.iter().zip(attrs).map(|(field, attrs)| {
         let name = field.ident.as_ref().unwrap();
+        let field_vis = &field.vis;
         let ty = &field.ty;
+        // As in the struct itself, parsed fields are hidden from serde.
+        let skip = if attrs.is_parsed() { quote!(#[serde(skip)]) } else { quote!() };
+        // The source is asked for the keys of the struct, renamed by serde.
+        let key = &attrs.key;
+        let rename = if *name != key { quote!(#[serde(rename = #key)]) } else { quote!() };
+        quote!(#skip #rename #field_vis #name: ::std::option::Option<#ty>)
+    });
+    let doc = format!("A `{}` in which every field is optional, to be merged into one with `{}::merge`.", ty, ty);
+
+    let parsed = fields.iter().zip(attrs).filter(|&(_, attrs)| attrs.is_parsed()).collect::<Vec<_>>();
+    let deserialize = if parsed.is_empty() {
+        quote!(::serde::Deserialize::deserialize(deserializer))
+    } else {
+        let parsed_names = parsed.iter().map(|&(_, attrs)| &attrs.key);
+        let lists = parsed.iter().map(|&(field, attrs)| is_list(field, attrs));
+        let all = attrs.iter().map(|attrs| &attrs.key);
+        let parse = parsed.iter().map(|&(field, attrs)| {
+            let name = field.ident.as_ref().unwrap();
+            let parse = parse_value(field, attrs, &vars.var_name(attrs));
+            quote! {
+                |cfg: &mut Self, values: &[::std::string::String]| -> ::std::result::Result<(), ::std::string::String> {
+                    cfg.#name = Some((#parse)(values)?);
+                    Ok(())
+                }
+            }
+        });
+        quote! {
+            let parsed = ::configure::overlay::Parsed::new(&[#((#parsed_names, #lists)),*], &[#(#all),*]);
+            let mut cfg: Self = ::serde::Deserialize::deserialize(parsed.wrap(deserializer))?;
+            parsed.assign(&mut cfg, &[#(&#parse),*])?;
+            Ok(cfg)
+        }
+    };
 
-        let attrs = FieldAttrs::new(field);
+    quote! {
+        #[doc = #doc]
+        #[derive(::configure::__serde::Deserialize)]
+        #[serde(crate = "::configure::__serde")]
+        #vis struct #partial #generics #where_clause {
+            #(#decls,)*
+        }
 
-        let var_name = format!("{}_{}", project, name).to_shouty_snake_case();
-        let var_type = quote! { #ty };
+        impl #impl_generics ::std::default::Default for #partial #ty_generics #where_clause {
+            fn default() -> Self {
+                #partial {
+                    #(#names: None,)*
+                }
+            }
+        }
 
-        if let Some(field_docs) = attrs.docs {
-            let _ = writeln!(docs, "- **{}** ({}): {}", var_name, var_type, field_docs);
+        impl #impl_generics #partial #ty_generics #where_clause {
+            /// Read only the fields which the active source of configuration
+            /// provides, leaving the others `None` rather than setting them
+            /// to their defaults. Required fields and validators are not
+            /// checked.
+            pub fn from_env() -> ::std::result::Result<Self, ::configure::DeserializeError>
+                where Self: ::serde::Deserialize<'static>,
+            {
+                #struct_info
+                ::configure::source::CONFIGURATION.generate_struct(#project, &INFO, |deserializer| {
+                    #deserialize
+                })
+            }
+        }
+
+        impl #impl_generics #ty #ty_generics #where_clause {
+            /// Overwrite the fields of this configuration which are set in
+            /// `partial`, leaving the others as they are.
+            pub fn merge(&mut self, partial: #partial #ty_generics) {
+                #(
+                    if let Some(value) = partial.#names {
+                        self.#names = value;
+                    }
+                )*
+            }
+        }
+    }
+}
+
+fn regenerate_changed(fields: &[&Field]) -> TokenStream {
+    let names = fields.iter().map(|field| field.ident.as_ref().unwrap());
+    let names2 = fields.iter().map(|field| field.ident.as_ref().unwrap());
+    let names3 = fields.iter().map(|field| field.ident.as_ref().unwrap().unraw().to_string());
+    let tys = fields.iter().map(|field| &field.ty);
+
+    quote! {
+        /// Regenerate this configuration, returning the names of the fields
+        /// whose values changed.
+        // As with `to_env_lines`, the bounds are higher-ranked so they are
+        // only checked if this method is called.
+        pub fn regenerate_changed(&mut self) -> ::std::result::Result<::std::vec::Vec<&'static str>, ::configure::DeserializeError>
+            where Self: ::configure::Configure, #(for<'a> &'a #tys: ::std::cmp::PartialEq,)*
+        {
+            let new = <Self as ::configure::Configure>::generate()?;
+            let mut changed = ::std::vec::Vec::new();
+            #(
+                if &self.#names != &new.#names2 {
+                    changed.push(#names3);
+                }
+            )*
+            *self = new;
+            Ok(changed)
+        }
+    }
+}
+
+fn struct_info(cfg_attrs: &CfgAttrs, attrs: &[FieldAttrs]) -> TokenStream {
+    let prefix = opt_str(&cfg_attrs.prefix);
+    let separator = opt_str(&cfg_attrs.separator);
+    let case = match cfg_attrs.case {
+        Some(case)  => {
+            let casing = case.casing();
+            quote!(Some(::configure::source::Casing::#casing))
+        }
+        None        => quote!(None),
+    };
+    let infos = attrs.iter().map(|attrs| {
+        let name = &attrs.key;
+        let env = opt_str(&attrs.env);
+        let flag = attrs.flag;
+        let rename = opt_str(&attrs.rename);
+        let skip = attrs.skip;
+        let flatten = attrs.flatten;
+        let aliases = &attrs.aliases;
+        let toml_aliases = &attrs.toml_aliases;
+        let toml_key = opt_str(&attrs.toml_key);
+        let namespace = opt_str(&attrs.namespace);
+        let env_only = attrs.env_only;
+        let toml_only = attrs.toml_only;
+        let secret = attrs.secret;
+        quote! {
+            ::configure::source::FieldInfo {
+                name: #name,
+                env: #env,
+                flag: #flag,
+                rename: #rename,
+                skip: #skip,
+                flatten: #flatten,
+                aliases: &[#(#aliases),*],
+                toml_aliases: &[#(#toml_aliases),*],
+                toml_key: #toml_key,
+                namespace: #namespace,
+                env_only: #env_only,
+                toml_only: #toml_only,
+                secret: #secret,
+            }
+        }
+    });
+
+    quote! {
+        static INFO: ::configure::source::StructInfo = ::configure::source::StructInfo {
+            prefix: #prefix,
+            fields: &[#(#infos),*],
+            case: #case,
+            separator: #separator,
+        };
+    }
+}
+
+/// Statements deserializing `cfg`, failing if the `required` fields are
+/// missing and warning about the `deprecated` fields which are set, then
+/// resetting the `type_default` fields which the source did
+/// not provide, and parsing the `parse_with`, `from_str` and `bytes` fields.
+fn deserialize(fields: &[&Field], attrs: &[FieldAttrs], project: &str, vars: &VarNames) -> TokenStream {
+    let required = attrs.iter().filter(|attrs| attrs.required).map(|attrs| {
+        let key = &attrs.key;
+        let hint = format!("set {}", settings(attrs, project, vars));
+        quote!((#key, #hint))
+    }).collect::<Vec<_>>();
+    // The required fields are checked first, so that parsed fields, which
+    // are hidden from everything after them, still count as provided.
+    let require = if required.is_empty() {
+        quote!()
+    } else {
+        quote!(let deserializer = ::configure::overlay::require(deserializer, &[#(#required),*]);)
+    };
+    let deprecated = deprecated(attrs, project, vars);
+
+    let type_defaults = fields.iter().zip(attrs)
+        .filter(|&(_, attrs)| attrs.type_default)
+        .collect::<Vec<_>>();
+    let parsed = fields.iter().zip(attrs)
+        .filter(|&(_, attrs)| attrs.is_parsed())
+        .collect::<Vec<_>>();
+
+    let deserialize = if type_defaults.is_empty() {
+        quote!(let cfg: Self = ::serde::Deserialize::deserialize(deserializer)?;)
+    } else {
+        let names = type_defaults.iter().map(|&(field, _)| field.ident.as_ref().unwrap());
+        // The present keys are those serde asked for, renamed like the fields.
+        let keys = type_defaults.iter().map(|&(_, attrs)| &attrs.key);
+        quote! {
+            let (mut cfg, present): (Self, _) = ::configure::overlay::with_present(deserializer)?;
+            #(
+                if !present.iter().any(|key| key == #keys) {
+                    cfg.#names = ::std::default::Default::default();
+                }
+            )*
+        }
+    };
+
+    if parsed.is_empty() {
+        return quote!(#require #deprecated #deserialize)
+    }
+
+    let names = parsed.iter().map(|&(_, attrs)| &attrs.key);
+    let lists = parsed.iter().map(|&(field, attrs)| is_list(field, attrs));
+    let all = attrs.iter().map(|attrs| &attrs.key);
+    let parse = parsed.iter().map(|&(field, attrs)| parse_field(field, attrs, vars));
+    quote! {
+        #require
+        #deprecated
+        let parsed = ::configure::overlay::Parsed::new(&[#((#names, #lists)),*], &[#(#all),*]);
+        let deserializer = parsed.wrap(deserializer);
+        #deserialize
+        let mut cfg = cfg;
+        parsed.assign(&mut cfg, &[#(&#parse),*])?;
+    }
+}
+
+/// A closure parsing the strings of a `parse_with`, `from_str` or `bytes`
+/// field into that field of the configuration, returning a
+/// `Result<(), String>`.
+fn parse_field(field: &Field, attrs: &FieldAttrs, vars: &VarNames) -> TokenStream {
+    let name = field.ident.as_ref().unwrap();
+    let parse = parse_value(field, attrs, &vars.var_name(attrs));
+    quote! {
+        |cfg: &mut Self, values: &[::std::string::String]| -> ::std::result::Result<(), ::std::string::String> {
+            cfg.#name = (#parse)(values)?;
+            Ok(())
+        }
+    }
+}
+
+/// A closure parsing the strings of a `parse_with`, `from_str`, `bytes`,
+/// `time_of_day`, `percent` or `flags` field into a value of the field's
+/// type, returning a `Result<T, String>`.
+///
+/// The seconds of a `time_of_day` field are converted with `Into`, so it can
+/// be any integer type wide enough to hold them. The names of a `flags`
+/// field are collected into its type, like a `HashSet<String>`. The parsed
+/// value is wrapped in `Some` if the field is an `Option`. The elements of a
+/// `from_str` field which is a `Vec` are parsed one by one.
+///
+/// Errors name the field and `var_name`, where the strings came from.
+fn parse_value(field: &Field, attrs: &FieldAttrs, var_name: &str) -> TokenStream {
+    let name = field.ident.as_ref().unwrap();
+    let field_ty = &field.ty;
+
+    let body = if attrs.flags {
+        // Flag names are case insensitive, and empty names are ignored.
+        quote! {
+            Ok(values.iter()
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+                .map(|value| value.to_lowercase())
+                .collect())
+        }
+    } else if is_list(field, attrs) {
+        let ty = generic_arg(&field.ty, "Vec").unwrap();
+        let msg = format!("invalid value for `{}` (`{}`): element {{}}: {{}}", name, var_name);
+        quote! {
+            let mut parsed = ::std::vec::Vec::new();
+            for (idx, value) in values.iter().enumerate() {
+                match <#ty as ::std::str::FromStr>::from_str(value) {
+                    Ok(element) => parsed.push(element),
+                    Err(err)    => return Err(format!(#msg, idx, err)),
+                }
+            }
+            Ok(parsed)
+        }
+    } else {
+        let option = generic_arg(&field.ty, "Option");
+        let parse = match (attrs.parse_with.as_ref(), attrs.bytes.as_ref()) {
+            (Some(parse_with), _)   => quote!(#parse_with(value)),
+            (_, Some(encoding))     => quote!(::configure::overlay::decode::#encoding(value)),
+            _ if attrs.time_of_day  => quote!(::configure::parse_time_of_day(value).map(::std::convert::Into::into)),
+            _ if attrs.percent      => {
+                let ty = option.unwrap_or(&field.ty);
+                quote!(::configure::parse_percent::<#ty>(value))
+            }
+            _                       => {
+                let ty = option.unwrap_or(&field.ty);
+                quote!(<#ty as ::std::str::FromStr>::from_str(value))
+            }
+        };
+        let parsed = if option.is_some() { quote!(Some(parsed)) } else { quote!(parsed) };
+        let msg = format!("invalid value for `{}` (`{}`): {{}}", name, var_name);
+        quote! {
+            let value = &values[0][..];
+            match #parse {
+                Ok(parsed)  => Ok(#parsed),
+                Err(err)    => Err(format!(#msg, err)),
+            }
+        }
+    };
+
+    quote! {
+        |values: &[::std::string::String]| -> ::std::result::Result<#field_ty, ::std::string::String> {
+            #body
+        }
+    }
+}
+
+/// Whether this is a `from_str` field which is a `Vec`, so that it is parsed
+/// from a list.
+fn is_list(field: &Field, attrs: &FieldAttrs) -> bool {
+    attrs.flags || attrs.from_str && generic_arg(&field.ty, "Vec").is_some()
+}
+
+/// The type argument of `ty`, if it is the generic type `wrapper`, like
+/// `Option<T>`.
+fn generic_arg<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    let segment = match *ty {
+        Type::Path(ref ty)  => ty.path.segments.last()?,
+        _                   => return None,
+    };
+    if segment.ident != wrapper {
+        return None
+    }
+    match segment.arguments {
+        PathArguments::AngleBracketed(ref args) if args.args.len() == 1 => match args.args[0] {
+            GenericArgument::Type(ref ty)   => Some(ty),
+            _                               => None,
+        },
+        _                                                               => None,
+    }
+}
+
+/// Where this field can be set: its variable, its key in the `Cargo.toml`,
+/// or either of them.
+fn settings(attrs: &FieldAttrs, project: &str, vars: &VarNames) -> String {
+    let var_name = vars.var_name(attrs);
+    let table = attrs.namespace.as_deref().unwrap_or(project);
+    let toml_path = format!("[package.metadata.{}].{}", table, toml_key(attrs));
+    match (attrs.env_only, attrs.toml_only) {
+        (true, _)   => var_name,
+        (_, true)   => toml_path,
+        _           => format!("{} or {}", var_name, toml_path),
+    }
+}
+
+/// A statement wrapping the deserializer to warn about the `deprecated`
+/// fields which the source provides, if there are any.
+fn deprecated(attrs: &[FieldAttrs], project: &str, vars: &VarNames) -> TokenStream {
+    let deprecated = attrs.iter().filter_map(|attrs| {
+        let note = attrs.deprecated.as_ref()?;
+        let key = &attrs.key;
+        let warning = format!("{} is deprecated: {}", settings(attrs, project, vars), note);
+        Some(quote!((#key, #warning)))
+    }).collect::<Vec<_>>();
+    if deprecated.is_empty() {
+        quote!()
+    } else {
+        quote!(let deserializer = ::configure::overlay::deprecated(deserializer, &[#(#deprecated),*]);)
+    }
+}
+
+/// The key this field is read from in the `Cargo.toml`.
+fn toml_key(attrs: &FieldAttrs) -> String {
+    attrs.toml_key.clone().or_else(|| attrs.rename.clone()).unwrap_or_else(|| attrs.key.clone())
+}
+
+/// How the environment variables of a struct are named.
+struct VarNames {
+    prefix: String,
+    case: Case,
+    /// The separator before the fields of flattened fields, if it is not
+    /// the separator of the case.
+    separator: Option<String>,
+}
+
+impl VarNames {
+    /// The environment variable this field is read from.
+    fn var_name(&self, attrs: &FieldAttrs) -> String {
+        match attrs.env {
+            Some(ref env)   => env.clone(),
+            None            => {
+                let key = attrs.rename.as_ref().unwrap_or(&attrs.key);
+                let prefix = attrs.namespace.as_ref().unwrap_or(&self.prefix);
+                self.case.apply(&format!("{}{}{}", prefix, self.case.separator(), key))
+            }
+        }
+    }
+}
+
+/// The environment variables these fields are read from, each followed by
+/// its aliases. Fields which are never read from the environment, and
+/// flattened fields, whose variables are not known here, are left out.
+fn env_vars(attrs: &[FieldAttrs], vars: &VarNames) -> Vec<String> {
+    let mut env_vars = vec![];
+    for attrs in attrs {
+        if attrs.skip || attrs.toml_only || attrs.flatten {
+            continue
+        }
+        env_vars.push(vars.var_name(attrs));
+        env_vars.extend(attrs.aliases.iter().cloned());
+    }
+    env_vars
+}
+
+/// The `env_vars` function, listing the variables of the fields in order
+/// like `ENV_VARS`, with the variables of each flattened field in its place.
+fn env_vars_fn(fields: &[&Field], attrs: &[FieldAttrs], vars: &VarNames) -> TokenStream {
+    let mut bounds = vec![];
+    let mut pushes = vec![];
+    for (field, attrs) in fields.iter().zip(attrs) {
+        if attrs.skip || attrs.toml_only {
+            continue
+        }
+        if attrs.flatten {
+            // Flattened structs are read with the prefix of their field,
+            // and their own configure attributes do not apply.
+            let ty = &field.ty;
+            let separator = vars.separator.as_deref().unwrap_or(vars.case.separator());
+            let prefix = format!("{}{}", vars.var_name(attrs), separator);
+            let casing = vars.case.casing();
+            bounds.push(quote!(for<'a> #ty: ::configure::FlattenedKeys));
+            pushes.push(quote! {
+                env_vars.extend(<#ty as ::configure::FlattenedKeys>::FLATTENED_KEYS.iter().map(|key| {
+                    format!("{}{}", #prefix, ::configure::source::Casing::#casing.apply(key))
+                }));
+            });
         } else {
-            let _ = writeln!(docs, "- **{}** ({})", var_name, var_type);
+            let var_name = vars.var_name(attrs);
+            let aliases = &attrs.aliases;
+            pushes.push(quote! {
+                env_vars.extend([#var_name, #(#aliases),*].iter().map(|&var| ::std::string::String::from(var)));
+            });
+        }
+    }
+    let where_clause = if bounds.is_empty() { quote!() } else { quote!(where #(#bounds,)*) };
+
+    quote! {
+        /// The environment variables this configuration is read from, as
+        /// in `ENV_VARS`, with the variables of each flattened field in its
+        /// place. The types of flattened fields must derive `Configure` too.
+        // The bounds are higher-ranked so they are only checked when this is
+        // called; flattening types which do not derive `Configure` is fine.
+        pub fn env_vars() -> ::std::vec::Vec<::std::string::String> #where_clause {
+            let mut env_vars = ::std::vec::Vec::new();
+            #(#pushes)*
+            env_vars
+        }
+    }
+}
+
+/// The keys of the fields read from the environment when the struct is
+/// flattened into another.
+fn flattened_keys(attrs: &[FieldAttrs]) -> Vec<&String> {
+    attrs.iter().filter(|attrs| !(attrs.skip || attrs.toml_only || attrs.flatten)).map(|attrs| &attrs.key).collect()
+}
+
+/// What the example TOML template says about each of these fields.
+fn example_fields(attrs: &[FieldAttrs], vars: &VarNames) -> Vec<TokenStream> {
+    attrs.iter().filter(|attrs| !attrs.skip && !attrs.hide_docs).map(|attrs| {
+        let key = attrs.rename.as_ref().unwrap_or(&attrs.key);
+        let var_name = if attrs.toml_only || attrs.flatten { None } else { Some(vars.var_name(attrs)) };
+        let var_name = opt_str(&var_name);
+        let docs = opt_str(&attrs.docs);
+        let secret = attrs.secret;
+        quote! {
+            ::configure::ExampleField {
+                key: #key,
+                var_name: #var_name,
+                docs: #docs,
+                secret: #secret,
+            }
+        }
+    }).collect()
+}
+
+/// A `FieldMetadata` for each field.
+fn field_metadata(fields: &[&Field], attrs: &[FieldAttrs], vars: &VarNames) -> Vec<TokenStream> {
+    fields.iter().zip(attrs).map(|(field, attrs)| {
+        let name = field.ident.as_ref().unwrap().unraw().to_string();
+        let read = !attrs.skip && !attrs.flatten;
+        let env_var = if read && !attrs.toml_only { Some(vars.var_name(attrs)) } else { None };
+        let env_var = opt_str(&env_var);
+        let toml_key = if read && !attrs.env_only { Some(toml_key(attrs)) } else { None };
+        let toml_key = opt_str(&toml_key);
+        let ty = type_name(&field.ty);
+        let docs = opt_str(&attrs.docs);
+        let secret = attrs.secret;
+        let required = attrs.required;
+        let skip = attrs.skip;
+        let flatten = attrs.flatten;
+        quote! {
+            ::configure::FieldMetadata {
+                name: #name,
+                env_var: #env_var,
+                toml_key: #toml_key,
+                ty: #ty,
+                docs: #docs,
+                secret: #secret,
+                required: #required,
+                skip: #skip,
+                flatten: #flatten,
+            }
+        }
+    }).collect()
+}
+
+/// A type as it would be written, like `Option<Vec<String>>`, without the
+/// spaces the tokens are separated by.
+fn type_name(ty: &Type) -> String {
+    let tokens = quote!(#ty).to_string();
+    let is_punct = |c: char| "<>:,&*()[];".contains(c);
+    let mut name = String::with_capacity(tokens.len());
+    let mut chars = tokens.chars().peekable();
+    while let Some(c) = chars.next() {
+        // Only the spaces between words, like in `&'static str`, are kept,
+        // and commas are followed by one.
+        let between_words = !name.ends_with(' ') && !name.ends_with(is_punct)
+            && chars.peek().is_some_and(|&next| !is_punct(next));
+        match c {
+            ' ' if !between_words   => { }
+            ',' | ';'               => { name.push(c); name.push(' ') }
+            c                       => name.push(c),
+        }
+    }
+    name
+}
+
+/// The environment variables an enum is read from, with what they are
+/// named after: the variable selecting the variant, named after the enum,
+/// then the fields of each variant, each only once.
+fn enum_env_vars(ty: &Ident, data: &DataEnum, vars: &VarNames) -> Vec<(Ident, String)> {
+    let key = Ident::new(&ty.to_string().to_snake_case(), ty.span());
+    let tag = vars.case.apply(&format!("{}{}{}", vars.prefix, vars.case.separator(), key));
+    let nested = format!("{}{}", tag, vars.separator.as_deref().unwrap_or(vars.case.separator()));
+    let mut env_vars = vec![(key, tag)];
+    for field in data.variants.iter().flat_map(|variant| &variant.fields) {
+        if let Some(ref ident) = field.ident {
+            let var_name = format!("{}{}", nested, vars.case.apply(&ident.to_string()));
+            if !env_vars.iter().any(|(_, other)| *other == var_name) {
+                env_vars.push((ident.clone(), var_name));
+            }
+        }
+    }
+    env_vars
+}
+
+/// Statements checking `cfg` with the field ranges and validators, then the
+/// struct validator, returning early with the first error.
+fn validate(cfg_attrs: &CfgAttrs, fields: &[&Field], attrs: &[FieldAttrs]) -> TokenStream {
+    let field_checks = fields.iter().zip(attrs).map(|(field, attrs)| {
+        let name = field.ident.as_ref().unwrap();
+        let range_check = attrs.range.as_ref().map(|range| {
+            let expr = &range.expr;
+            let msg = format!("invalid value for `{}`: {{}} is not in the range {}", name, range.text);
+            let check = quote! {
+                if !::std::ops::RangeBounds::contains(&(#expr), value) {
+                    return Err(::serde::de::Error::custom(format!(#msg, value)))
+                }
+            };
+            // Optional fields are only checked if they are set.
+            match generic_arg(&field.ty, "Option") {
+                Some(_) => quote!(if let Some(ref value) = cfg.#name { #check }),
+                None    => quote!({ let value = &cfg.#name; #check }),
+            }
+        });
+        let validate_check = attrs.validate.as_ref().map(|validate| {
+            let msg = format!("invalid value for `{}`: {{}}", name);
+            quote! {
+                if let Err(msg) = #validate(&cfg.#name) {
+                    return Err(::serde::de::Error::custom(format!(#msg, msg)))
+                }
+            }
+        });
+        quote!(#range_check #validate_check)
+    });
+    let struct_check = cfg_attrs.validate.as_ref().map(|validate| quote! {
+        if let Err(msg) = #validate(&cfg) {
+            return Err(::serde::de::Error::custom(msg))
+        }
+    });
+
+    quote! {
+        #(#field_checks)*
+        #struct_check
+    }
+}
+
+fn derive_default(
+    cfg_attrs: &CfgAttrs,
+    ty: &Ident,
+    generics: &Generics,
+    fields: &[&Field],
+    attrs: &[FieldAttrs],
+) -> Result<Option<TokenStream>> {
+    if !cfg_attrs.derive_default {
+        for (field, attrs) in fields.iter().zip(attrs) {
+            if let Some(ref default) = attrs.default {
+                let name = field.ident.as_ref().unwrap();
+                let msg = format!("The `default` attribute on field `{}` requires \
+                                   #[configure(derive_default)] on the struct", name);
+                return Err(Error::new(default.span, msg))
+            }
+        }
+        return Ok(None)
+    }
+
+    // Fields without a `default` attribute use `Default::default()`.
+    let names = fields.iter().map(|field| field.ident.as_ref().unwrap());
+    let values = attrs.iter().map(|attrs| match attrs.default {
+        Some(ref default) if default.expr.is_some() => {
+            let expr = &default.expr;
+            quote!(#expr)
+        }
+        _                                           => quote!(::std::default::Default::default()),
+    });
+
+    let (impl_generics, ty_generics, _) = generics.split_for_impl();
+    let bounds = fields.iter().zip(attrs)
+        .filter(|&(_, attrs)| attrs.default.as_ref().is_none_or(|default| default.expr.is_none()))
+        .map(|(field, _)| {
+            let ty = &field.ty;
+            quote!(#ty: ::std::default::Default)
+        })
+        .collect::<Vec<_>>();
+    let where_clause = where_with(generics, &bounds);
+
+    Ok(Some(quote! {
+        impl #impl_generics ::std::default::Default for #ty #ty_generics #where_clause {
+            fn default() -> Self {
+                #ty {
+                    #(#names: #values,)*
+                }
+            }
+        }
+    }))
+}
+
+/// The where clause of `generics`, with `bounds` added to its predicates.
+///
+/// The bounds are on what the generated code needs, like
+/// `Self: Deserialize<'static>`, rather than on every type parameter, so
+/// that a struct generic over a backend which is not itself deserializable
+/// can still derive `Configure`.
+fn where_with(generics: &Generics, bounds: &[TokenStream]) -> TokenStream {
+    let predicates = generics.where_clause.iter().flat_map(|clause| &clause.predicates);
+    quote!(where #(#predicates,)* #(#bounds,)*)
+}
+
+/// A `Debug` implementation printing `<redacted>` for the secret fields,
+/// and the other fields as `#[derive(Debug)]` would.
+///
+/// Deriving `Debug` as well is an error. Only derives in later attributes
+/// can be seen here; a `Debug` in the same `derive` as `Configure` is
+/// reported by rustc as conflicting implementations.
+fn redacted_debug(ast: &DeriveInput, fields: &[&Field], attrs: &[FieldAttrs]) -> Result<TokenStream> {
+    let ty = &ast.ident;
+    for attr in ast.attrs.iter().filter(|attr| attr.path().is_ident("derive")) {
+        let derives = attr.parse_args_with(punctuated::Punctuated::<Path, Token![,]>::parse_terminated)?;
+        if let Some(debug) = derives.iter().find(|path| path.segments.last().is_some_and(|segment| segment.ident == "Debug")) {
+            let msg = format!("`{}` cannot derive `Debug`, because #[configure(redacted_debug)] \
+                               generates its `Debug` implementation", ty);
+            return Err(Error::new_spanned(debug, msg))
+        }
+    }
+
+    let name = ty.to_string();
+    let keys = fields.iter().map(|field| field.ident.as_ref().unwrap().unraw().to_string());
+    let values = fields.iter().zip(attrs).map(|(field, attrs)| {
+        let name = field.ident.as_ref().unwrap();
+        if attrs.secret { quote!(&format_args!("<redacted>")) } else { quote!(&self.#name) }
+    });
+    let bounds = fields.iter().zip(attrs).filter(|&(_, attrs)| !attrs.secret).map(|(field, _)| {
+        let ty = &field.ty;
+        quote!(#ty: ::std::fmt::Debug)
+    }).collect::<Vec<_>>();
+    let (impl_generics, ty_generics, _) = ast.generics.split_for_impl();
+    let where_clause = where_with(&ast.generics, &bounds);
+
+    Ok(quote! {
+        impl #impl_generics ::std::fmt::Debug for #ty #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                f.debug_struct(#name)
+                    #(.field(#keys, #values))*
+                    .finish()
+            }
         }
+    })
+}
+
+/// Bounds that the types of the fields which are deserialized, rather than
+/// parsed, implement `Deserialize`.
+fn deserialized_bounds(fields: &[&Field], attrs: &[FieldAttrs]) -> Vec<TokenStream> {
+    fields.iter().zip(attrs).filter(|&(_, attrs)| !attrs.is_parsed()).map(|(field, _)| {
+        let ty = &field.ty;
+        quote!(#ty: ::serde::Deserialize<'static>)
+    }).collect()
+}
+
+fn opt_str(string: &Option<String>) -> TokenStream {
+    match *string {
+        Some(ref string)    => quote!(Some(#string)),
+        None                => quote!(None),
     }
+}
 
-    docs.push_str("\nThis library uses the configure crate to manage its configuration; you can\
-                     also override how configuration is handled using the API in that crate.");
+fn generate_from(
+    struct_info: &TokenStream,
+    validate: &TokenStream,
+    fields: &[&Field],
+    attrs: &[FieldAttrs],
+    project: &str,
+    vars: &VarNames,
+) -> TokenStream {
+    let names = attrs.iter().map(|attrs| &attrs.key);
+    let bounds = deserialized_bounds(fields, attrs);
+    let deprecated = deprecated(attrs, project, vars);
+    let assign = fields.iter().zip(attrs).map(|(field, attrs)| {
+        let name = field.ident.as_ref().unwrap();
+        if attrs.is_parsed() {
+            let list = is_list(field, attrs);
+            let parse = parse_field(field, attrs, vars);
+            quote! {
+                let values = ::configure::overlay::raw_values(deserializer, #list)?;
+                (#parse)(cfg, &values).map_err(::serde::de::Error::custom)
+            }
+        } else {
+            quote! {
+                cfg.#name = ::serde::Deserialize::deserialize(deserializer)?;
+                Ok(())
+            }
+        }
+    });
 
     quote! {
+        /// Generate this configuration using `base` as the starting values.
+        /// Only the fields provided by the active source are overridden.
+        /// Validators run on the result, as with `generate`.
+        pub fn generate_from(mut base: Self) -> ::std::result::Result<Self, ::configure::DeserializeError>
+            where #(#bounds,)*
+        {
+            #struct_info
+            let deserializer = ::configure::source::CONFIGURATION.get_struct(#project, &INFO);
+            #deprecated
+            ::configure::overlay::overlay(deserializer, &mut base, &[#(#names),*], &[#(
+                &|cfg, deserializer| {
+                    #assign
+                }
+            ),*])?;
+            let cfg = base;
+            #validate
+            Ok(cfg)
+        }
+
+        /// Regenerate this configuration, overwriting only the fields
+        /// provided by the active source. Unlike `regenerate`, the other
+        /// fields keep their current values, including any changes made to
+        /// them after the configuration was generated. If validation fails,
+        /// this is left unchanged.
+        // As with `to_env_lines`, the `Clone` bound is higher-ranked so it
+        // is only checked if this method is called.
+        pub fn regenerate_present(&mut self) -> ::std::result::Result<(), ::configure::DeserializeError>
+            where #(#bounds,)* for<'a> Self: ::std::clone::Clone
+        {
+            *self = Self::generate_from(::std::clone::Clone::clone(self))?;
+            Ok(())
+        }
+    }
+}
+
+/// Statements which, if `result` is an error, deserialize the configuration
+/// again without stopping at the first invalid field, and return an error
+/// listing all of them.
+fn collect_errors(fields: &[&Field], attrs: &[FieldAttrs], project: &str, vars: &VarNames) -> TokenStream {
+    let names = attrs.iter().map(|attrs| &attrs.key);
+    let check = fields.iter().zip(attrs).map(|(field, attrs)| {
+        let ty = &field.ty;
+        if attrs.is_parsed() {
+            let list = is_list(field, attrs);
+            let parse = parse_value(field, attrs, &vars.var_name(attrs));
+            quote! {
+                let values = ::configure::overlay::raw_values(deserializer, #list)?;
+                (#parse)(&values).map(|_| ()).map_err(::serde::de::Error::custom)
+            }
+        } else {
+            quote! {
+                let _: #ty = ::serde::Deserialize::deserialize(deserializer)?;
+                Ok(())
+            }
+        }
+    });
+
+    quote! {
+        if let Err(err) = result {
+            let deserializer = ::configure::source::CONFIGURATION.get_struct(#project, &INFO);
+            return Err(::configure::overlay::collect_errors(err, deserializer, &[#(#names),*], &[#(
+                &|deserializer| {
+                    #check
+                }
+            ),*]))
+        }
+    }
+}
+
+/// The first paragraphs of the docs: the doc comment on the type, if it has
+/// one, and what the variables are for.
+fn intro(attrs: &[Attribute], project: &str) -> String {
+    let mut docs = String::new();
+    if let Some(paragraphs) = attrs::desugared_paragraphs(attrs) {
+        let _ = write!(docs, "{}\n\n", paragraphs);
+    }
+    let _ = write!(docs, "These environment variables can be used to configure {}.\n\n", project);
+    docs
+}
+
+/// The last paragraph of the docs.
+const OUTRO: &str = "\nThis library uses the configure crate to manage its configuration; you can \
+                     also override how configuration is handled using the API in that crate.";
+
+/// The docs as a module, for rustdoc, and as a function returning them, for
+/// binaries to print.
+fn environment_docs(
+    ty: &Ident,
+    generics: &Generics,
+    cfg_attrs: &CfgAttrs,
+    project: &str,
+    docs: &str,
+    rows: &[Row],
+    consts: TokenStream,
+) -> TokenStream {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let markdown = markdown(rows);
+    let module = match (&cfg_attrs.docs_module, cfg_attrs.use_type_name) {
+        (Some(module), _)   => module.clone(),
+        // Each of several structs in a crate has its own module.
+        (None, true)        => format_ident!("{}_environment_variables", type_key(ty).unwrap_or_default()),
+        (None, false)       => Ident::new("environment_variables", Span::call_site()),
+    };
+    let fn_docs = format!(" The environment variables which can be used to configure\n this, as documented in the `{}` module.", module);
+    quote! {
+        impl #impl_generics #ty #ty_generics #where_clause {
+            #[doc = #fn_docs]
+            pub fn environment_docs() -> &'static str {
+                #docs
+            }
+
+            /// The environment variables which can be used to configure
+            /// this, as a Markdown table.
+            pub fn environment_docs_markdown() -> ::std::string::String {
+                ::std::string::String::from(#markdown)
+            }
+
+            /// Add the docs of this configuration to those printed by
+            /// `configure::print_env_help`. This happens when it is first
+            /// generated, so this is only needed for configuration which
+            /// may not have been generated yet.
+            pub fn register_docs() {
+                static REGISTER: ::std::sync::Once = ::std::sync::Once::new();
+                REGISTER.call_once(|| ::configure::register_env_docs(#project, #docs));
+            }
+        }
+
         #[doc = #docs]
-        pub mod environment_variables { }
+        pub mod #module {
+            #consts
+        }
+    }
+}
+
+/// A statement registering the docs, if they are generated, with
+/// `configure::print_env_help` when the configuration is generated.
+fn register_docs(cfg_attrs: &CfgAttrs) -> TokenStream {
+    if cfg_attrs.docs { quote!(Self::register_docs();) } else { quote!() }
+}
+
+/// A constant holding the name of each variable in `env_vars`, named after
+/// its field in shouty snake case.
+fn env_consts(fields: &[&Field], attrs: &[FieldAttrs], vars: &VarNames) -> Result<TokenStream> {
+    let mut consts = Consts::default();
+    for (field, attrs) in fields.iter().zip(attrs) {
+        if attrs.skip || attrs.toml_only || attrs.flatten {
+            continue
+        }
+        consts.push(field.ident.as_ref().unwrap(), vars.var_name(attrs), attrs.hide_docs)?;
+    }
+    Ok(consts.tokens)
+}
+
+/// A constant holding the name of each variable in `enum_env_vars`.
+fn enum_env_consts(ty: &Ident, data: &DataEnum, vars: &VarNames) -> Result<TokenStream> {
+    let mut consts = Consts::default();
+    for (ident, var_name) in enum_env_vars(ty, data, vars) {
+        consts.push(&ident, var_name, false)?;
+    }
+    Ok(consts.tokens)
+}
+
+#[derive(Default)]
+struct Consts {
+    names: Vec<(String, String)>,
+    tokens: TokenStream,
+}
+
+impl Consts {
+    fn push(&mut self, ident: &Ident, var_name: String, hidden: bool) -> Result<()> {
+        let field = ident.unraw().to_string();
+        let name = field.to_shouty_snake_case();
+        if let Some((other, _)) = self.names.iter().find(|(_, other)| *other == name) {
+            let msg = format!("The names of `{}` and `{}` are both `{}` in shouty snake case, \
+                               so their constants in `environment_variables` would collide", other, field, name);
+            return Err(Error::new_spanned(ident, msg))
+        }
+        let doc = format!("The environment variable `{}` is read from.", field);
+        let const_name = Ident::new(&name, ident.span());
+        // The constants of hidden fields can be used, but are not shown.
+        let hidden = if hidden { quote!(#[doc(hidden)]) } else { quote!() };
+        self.tokens.extend(quote! {
+            #[doc = #doc]
+            #hidden
+            pub const #const_name: &str = #var_name;
+        });
+        self.names.push((field, name));
+        Ok(())
+    }
+}
+
+/// One variable in the Markdown table of the docs.
+struct Row {
+    var_name: String,
+    var_type: String,
+    default: Option<String>,
+    required: bool,
+    docs: Option<String>,
+}
+
+/// The docs as a Markdown table, with a row for each variable.
+fn markdown(rows: &[Row]) -> String {
+    // Table cells are one line, and end at the first `|`.
+    fn cell(text: &str) -> String {
+        text.replace('|', "\\|").replace("\r\n", "<br>").replace('\n', "<br>")
+    }
+
+    let mut markdown = String::from("| Variable | Type | Default | Required | Description |\n\
+                                     |----------|------|---------|----------|-------------|\n");
+    for row in rows {
+        let _ = writeln!(markdown, "| `{}` | {} | {} | {} | {} |",
+                         row.var_name,
+                         cell(&row.var_type),
+                         row.default.as_deref().map(cell).unwrap_or_default(),
+                         if row.required { "yes" } else { "no" },
+                         row.docs.as_deref().map(cell).unwrap_or_default());
+    }
+    markdown
+}
+
+fn docs(struct_attrs: &[Attribute], fields: &[&Field], attrs: &[FieldAttrs], project: &str, vars: &VarNames) -> (String, Vec<Row>) {
+    let mut docs = intro(struct_attrs, project);
+    let mut rows = vec![];
+    for (field, attrs) in fields.iter().zip(attrs) {
+        let ty = &field.ty;
+
+        if attrs.skip || attrs.hide_docs {
+            continue
+        }
+
+        let mut var_name = vars.var_name(attrs);
+        // The fields of a flattened struct are not known here, only the
+        // prefix of their variables.
+        if attrs.flatten {
+            var_name.push_str(vars.separator.as_deref().unwrap_or(vars.case.separator()));
+            var_name.push_str("{FIELD}");
+        }
+        // Flags are set by their presence, whatever their value.
+        let base_type = if attrs.flag { String::from("flag") } else { type_name(ty) };
+        let mut qualifiers = String::new();
+        if attrs.secret {
+            qualifiers.push_str(", secret");
+        }
+        if attrs.env_only {
+            qualifiers.push_str(", environment only");
+        }
+        if attrs.toml_only {
+            qualifiers.push_str(", Cargo.toml only");
+        }
+        if attrs.deprecated.is_some() {
+            qualifiers.push_str(", deprecated");
+        }
+        let mut var_type = base_type.clone();
+        if let Some(ref doc_default) = attrs.doc_default {
+            let _ = write!(var_type, ", default: {}", doc_default);
+        }
+        if attrs.required {
+            var_type.push_str(", required");
+        }
+        var_type.push_str(&qualifiers);
+
+        if let Some(ref field_docs) = attrs.docs {
+            let _ = writeln!(docs, "- **{}** ({}): {}", var_name, var_type, field_docs);
+        } else {
+            let _ = writeln!(docs, "- **{}** ({})", var_name, var_type);
+        }
+        if let Some(ref toml_key) = attrs.toml_key {
+            let _ = writeln!(docs, "  - `{}` in the `Cargo.toml`", toml_key);
+        }
+        if let Some(ref range) = attrs.range {
+            let _ = writeln!(docs, "  - Range: `{}`", range.text);
+        }
+        if let Some(ref note) = attrs.deprecated {
+            let _ = writeln!(docs, "  - Deprecated: {}", note);
+        }
+        for alias in &attrs.aliases {
+            let _ = writeln!(docs, "  - **{}**: deprecated alias of {}", alias, var_name);
+        }
+
+        rows.push(Row {
+            var_name,
+            var_type: base_type + &qualifiers,
+            default: attrs.doc_default.clone(),
+            required: attrs.required,
+            docs: attrs.docs.clone(),
+        });
+    }
+
+    docs.push_str(OUTRO);
+    (docs, rows)
+}
+
+fn enum_docs(ty: &Ident, enum_attrs: &[Attribute], data: &DataEnum, project: &str, vars: &VarNames) -> (String, Vec<Row>) {
+    let key = ty.to_string().to_snake_case();
+    let tag = vars.case.apply(&format!("{}{}{}", vars.prefix, vars.case.separator(), key));
+    let nested = format!("{}{}", tag, vars.separator.as_deref().unwrap_or(vars.case.separator()));
+    let variants = data.variants.iter().map(|variant| variant.ident.to_string().to_snake_case()).collect::<Vec<_>>();
+
+    let mut docs = intro(enum_attrs, project);
+    let _ = writeln!(docs, "- **{}** (one of {}): selects the variant", tag, variants.join(", "));
+    let mut rows = vec![Row {
+        var_name: tag.clone(),
+        var_type: format!("one of {}", variants.join(", ")),
+        default: None,
+        required: false,
+        docs: Some(String::from("selects the variant")),
+    }];
+    for (variant, name) in data.variants.iter().zip(&variants) {
+        for field in &variant.fields {
+            let ty = &field.ty;
+            let var_name = match field.ident {
+                Some(ref ident) => format!("{}{}", nested, vars.case.apply(&ident.to_string())),
+                // The fields of a newtype variant are not known here.
+                None            => format!("{}{{FIELD}}", nested),
+            };
+            let var_type = format!("{}, {} only", type_name(ty), name);
+            let field_docs = attrs::desugared_docs(&field.attrs);
+            match field_docs {
+                Some(ref field_docs)    => {
+                    let _ = writeln!(docs, "- **{}** ({}): {}", var_name, var_type, field_docs);
+                }
+                None                    => {
+                    let _ = writeln!(docs, "- **{}** ({})", var_name, var_type);
+                }
+            }
+            rows.push(Row { var_name, var_type, default: None, required: false, docs: field_docs });
+        }
+    }
+
+    docs.push_str(OUTRO);
+    (docs, rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn type_names_are_unspaced() {
+        let name = |ty: &str| type_name(&parse_str(ty).unwrap());
+        assert_eq!(name("String"), "String");
+        assert_eq!(name("Option<Vec<String>>"), "Option<Vec<String>>");
+        assert_eq!(name("std::collections::HashMap<String, u32>"), "std::collections::HashMap<String, u32>");
+        assert_eq!(name("&'static str"), "&'static str");
+        assert_eq!(name("(u8, [u16; 4])"), "(u8, [u16; 4])");
+    }
+
+    fn project(attrs: Attribute, pkg_name: Option<&str>) -> Result<String> {
+        let cfg_attrs = CfgAttrs::new(&[attrs]).unwrap();
+        project_name(&cfg_attrs, &parse_quote!(Config), pkg_name.map(String::from))
+    }
+
+    #[test]
+    fn project_name_without_cargo_pkg_name() {
+        assert_eq!(project(parse_quote!(#[configure(name = "app")]), None).unwrap(), "app");
+        assert_eq!(project(parse_quote!(#[configure(name = "app")]), Some("pkg")).unwrap(), "app");
+        assert_eq!(project(parse_quote!(#[configure(generate_docs)]), Some("pkg")).unwrap(), "pkg");
+
+        let err = project(parse_quote!(#[configure(generate_docs)]), None).unwrap_err();
+        assert!(err.to_string().starts_with("Cannot determine the package name"));
+    }
+
+    #[test]
+    fn type_names_without_suffixes() {
+        let key = |ty: &str| type_key(&Ident::new(ty, Span::call_site()));
+        assert_eq!(key("ServerConfig").unwrap(), "server");
+        assert_eq!(key("MetricsCfg").unwrap(), "metrics");
+        assert_eq!(key("TracingConfiguration").unwrap(), "tracing");
+        assert_eq!(key("HttpClient").unwrap(), "http_client");
+        assert_eq!(key("Config"), None);
+
+        let cfg_attrs = CfgAttrs::new(&[parse_quote!(#[configure(name = "app", use_type_name)])]).unwrap();
+        assert_eq!(project_name(&cfg_attrs, &parse_quote!(ServerConfig), None).unwrap(), "app_server");
+        let err = project(parse_quote!(#[configure(use_type_name)]), Some("pkg")).unwrap_err();
+        assert!(err.to_string().starts_with("`Config` has no name left"));
+    }
+
+    #[test]
+    fn docs_use_case() {
+        let ast: DeriveInput = parse_quote! {
+            #[configure(name = "cased", case = "kebab", generate_docs)]
+            struct Config {
+                database_url: String,
+                #[configure(flatten)]
+                pool: Pool,
+            }
+        };
+        let docs = impl_configure(ast).unwrap().to_string();
+        assert!(docs.contains("**cased-database-url** (String)"), "{}", docs);
+        assert!(docs.contains("**cased-pool-{FIELD}** (Pool)"), "{}", docs);
+    }
+
+    #[test]
+    fn docs_mention_toml_key() {
+        let ast: DeriveInput = parse_quote! {
+            #[configure(name = "svc", generate_docs)]
+            struct Config {
+                #[configure(toml_key = "max-rps")]
+                max_requests_per_second: u32,
+            }
+        };
+        let docs = impl_configure(ast).unwrap().to_string();
+        assert!(docs.contains("**SVC_MAX_REQUESTS_PER_SECOND** (u32)\\n  - `max-rps` in the `Cargo.toml`"), "{}", docs);
+    }
+
+    #[test]
+    fn docs_mention_doc_default() {
+        let ast: DeriveInput = parse_quote! {
+            #[configure(name = "example", generate_docs)]
+            struct Config {
+                #[configure(doc_default = "127.0.0.1:7878")]
+                socket_addr: SocketAddr,
+                #[configure(doc_default = "8")]
+                threads: usize,
+                #[configure(doc_default = "/etc/example/cert.pem")]
+                tls_cert: PathBuf,
+                timeout: u64,
+            }
+        };
+        let docs = impl_configure(ast).unwrap().to_string();
+        assert!(docs.contains("**EXAMPLE_SOCKET_ADDR** (SocketAddr, default: 127.0.0.1:7878)"), "{}", docs);
+        assert!(docs.contains("**EXAMPLE_THREADS** (usize, default: 8)"), "{}", docs);
+        assert!(docs.contains("**EXAMPLE_TLS_CERT** (PathBuf, default: /etc/example/cert.pem)"), "{}", docs);
+        assert!(docs.contains("**EXAMPLE_TIMEOUT** (u64)"), "{}", docs);
+    }
+
+    #[test]
+    fn markdown_escapes_cells() {
+        let rows = [Row {
+            var_name: String::from("APP_MODE"),
+            var_type: String::from("Mode"),
+            default: Some(String::from("fast|safe")),
+            required: true,
+            docs: Some(String::from("How to run.\nEither fast or safe.")),
+        }];
+        assert_eq!(markdown(&rows).lines().nth(2).unwrap(),
+                   "| `APP_MODE` | Mode | fast\\|safe | yes | How to run.<br>Either fast or safe. |");
+    }
+
+    #[test]
+    fn enum_docs_list_variant_fields() {
+        let ast: DeriveInput = parse_quote! {
+            #[configure(name = "myapp", generate_docs)]
+            enum Storage {
+                Local,
+                S3 {
+                    /// The bucket to store objects in.
+                    bucket: String,
+                    region: Option<String>,
+                },
+                Custom(Backend),
+            }
+        };
+        let docs = impl_configure(ast).unwrap().to_string();
+        assert!(docs.contains("**MYAPP_STORAGE** (one of local, s3, custom)"), "{}", docs);
+        assert!(docs.contains("**MYAPP_STORAGE_BUCKET** (String, s3 only):"), "{}", docs);
+        assert!(docs.contains("**MYAPP_STORAGE_REGION** (Option<String>, s3 only)"), "{}", docs);
+        assert!(docs.contains("**MYAPP_STORAGE_{FIELD}** (Backend, custom only)"), "{}", docs);
+    }
+
+    #[test]
+    fn newtype_docs() {
+        let ast: DeriveInput = parse_quote! {
+            #[configure(name = "threads", generate_docs)]
+            struct Threads(
+                /// The number of worker threads.
+                usize,
+            );
+        };
+        let docs = impl_configure(ast).unwrap().to_string();
+        assert!(docs.contains("**THREADS** (usize): The number of worker threads."), "{}", docs);
+    }
+
+    #[test]
+    fn docs_from_doc_comments() {
+        let ast: DeriveInput = parse_quote! {
+            /// The configuration of the server.
+            ///
+            /// Read once at startup.
+            #[configure(name = "server", generate_docs)]
+            struct Config {
+                /// The address to listen on,
+                /// with its port.
+                addr: String,
+                /// Overridden by the attribute.
+                #[configure(docs = "The number of worker threads.")]
+                threads: usize,
+            }
+        };
+        let docs = impl_configure(ast).unwrap().to_string();
+        let intro = "\"The configuration of the server.\\n\\nRead once at startup.\\n\\n\
+                     These environment variables can be used to configure server.";
+        assert!(docs.contains(intro), "{}", docs);
+        assert!(docs.contains("**SERVER_ADDR** (String): The address to listen on, with its port.\\n"), "{}", docs);
+        assert!(docs.contains("**SERVER_THREADS** (usize): The number of worker threads.\\n"), "{}", docs);
+        assert!(!docs.contains("Overridden"), "{}", docs);
+    }
+
+    #[test]
+    fn docs_use_separator() {
+        let ast: DeriveInput = parse_quote! {
+            #[configure(name = "nested", separator = "__", generate_docs)]
+            struct Config {
+                #[configure(flatten)]
+                pool: Pool,
+            }
+        };
+        let docs = impl_configure(ast).unwrap().to_string();
+        assert!(docs.contains("**NESTED_POOL__{FIELD}** (Pool)"), "{}", docs);
     }
 }
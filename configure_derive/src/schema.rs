@@ -0,0 +1,238 @@
+//! JSON Schemas of the environment variables of configuration structs.
+use std::fmt::Write;
+
+use syn::*;
+
+use attrs::FieldAttrs;
+use super::{generic_arg, VarNames};
+
+/// A draft-07 JSON Schema of the environment variables of a struct, as an
+/// object with a property for each variable.
+///
+/// The type of each property is mapped from the syntax of the field's type,
+/// since the types themselves are not known to the derive:
+///
+/// - `bool`, and `flag` fields, are booleans.
+/// - The primitive integer types, and `time_of_day` fields, are integers.
+/// - `f32` and `f64`, and `percent` fields, are numbers.
+/// - `Vec`, `VecDeque`, `HashSet` and `BTreeSet`, and `flags` fields, are
+///   arrays of their elements.
+/// - `Option<T>` is the type of `T`.
+/// - Every other type, like `String`, `PathBuf` or an enum, is a string.
+///
+/// The variables of `required` fields are required. Defaults come from
+/// `doc_default`, as JSON values if they are literals of the property's type
+/// and as strings otherwise. Fields which are not read from the environment,
+/// and flattened fields, have no properties.
+pub fn json_schema(title: &str, description: Option<&str>, fields: &[&Field], attrs: &[FieldAttrs], vars: &VarNames) -> String {
+    let mut properties = vec![];
+    let mut required = vec![];
+    for (field, attrs) in fields.iter().zip(attrs) {
+        if attrs.skip || attrs.toml_only || attrs.flatten {
+            continue
+        }
+        let var_name = vars.var_name(attrs);
+        let ty = schema_type(&field.ty, attrs);
+        let mut property = format!("      {}: {{\n        \"type\": {}", json_string(&var_name), json_string(ty.name()));
+        if let SchemaType::Array(ref items) = ty {
+            let _ = write!(property, ",\n        \"items\": {{ \"type\": {} }}", json_string(items.name()));
+        }
+        if let Some(ref docs) = attrs.docs {
+            let _ = write!(property, ",\n        \"description\": {}", json_string(docs));
+        }
+        if let Some(ref default) = attrs.doc_default {
+            let _ = write!(property, ",\n        \"default\": {}", ty.literal(default));
+        }
+        property.push_str("\n      }");
+        properties.push(property);
+        if attrs.required {
+            required.push(json_string(&var_name));
+        }
+    }
+
+    let mut schema = String::from("{\n");
+    schema.push_str("  \"$schema\": \"http://json-schema.org/draft-07/schema#\",\n");
+    let _ = writeln!(schema, "  \"title\": {},", json_string(title));
+    if let Some(description) = description {
+        let _ = writeln!(schema, "  \"description\": {},", json_string(description));
+    }
+    schema.push_str("  \"type\": \"object\",\n");
+    if properties.is_empty() {
+        schema.push_str("  \"properties\": {},\n");
+    } else {
+        let _ = writeln!(schema, "  \"properties\": {{\n{}\n  }},", properties.join(",\n"));
+    }
+    let _ = writeln!(schema, "  \"required\": [{}]", required.join(", "));
+    schema.push('}');
+    schema
+}
+
+/// The JSON Schema type of a property.
+#[derive(Debug, PartialEq)]
+enum SchemaType {
+    Boolean,
+    Integer,
+    Number,
+    String,
+    Array(Box<SchemaType>),
+}
+
+impl SchemaType {
+    fn name(&self) -> &'static str {
+        match *self {
+            SchemaType::Boolean     => "boolean",
+            SchemaType::Integer     => "integer",
+            SchemaType::Number      => "number",
+            SchemaType::String      => "string",
+            SchemaType::Array(_)    => "array",
+        }
+    }
+
+    /// A `doc_default` as a JSON value of this type, or as a string if it
+    /// is not a literal of this type.
+    fn literal(&self, default: &str) -> String {
+        let is_literal = match *self {
+            SchemaType::Boolean     => default == "true" || default == "false",
+            SchemaType::Integer     => is_json_number(default, true),
+            SchemaType::Number      => is_json_number(default, false),
+            _                       => false,
+        };
+        if is_literal { default.to_owned() } else { json_string(default) }
+    }
+}
+
+/// Whether `s` is a JSON number, which must be an integer if `integer`.
+fn is_json_number(s: &str, integer: bool) -> bool {
+    fn digits(s: &str) -> (&str, &str) {
+        s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()))
+    }
+
+    let (int, rest) = digits(s.strip_prefix('-').unwrap_or(s));
+    if int.is_empty() || int.len() > 1 && int.starts_with('0') {
+        return false
+    }
+    if integer {
+        return rest.is_empty()
+    }
+    let rest = match rest.strip_prefix('.') {
+        Some(fraction)  => match digits(fraction) {
+            ("", _)         => return false,
+            (_, rest)       => rest,
+        },
+        None            => rest,
+    };
+    match rest.strip_prefix(['e', 'E']) {
+        Some(exponent)  => {
+            let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+            let (digits, rest) = digits(exponent);
+            !digits.is_empty() && rest.is_empty()
+        }
+        None            => rest.is_empty(),
+    }
+}
+
+fn schema_type(ty: &Type, attrs: &FieldAttrs) -> SchemaType {
+    if attrs.flag {
+        return SchemaType::Boolean
+    }
+    if attrs.flags {
+        return SchemaType::Array(Box::new(SchemaType::String))
+    }
+    if attrs.time_of_day {
+        return SchemaType::Integer
+    }
+    if attrs.percent {
+        return SchemaType::Number
+    }
+    if attrs.bytes.is_some() {
+        return SchemaType::String
+    }
+    type_of(ty)
+}
+
+fn type_of(ty: &Type) -> SchemaType {
+    if let Some(inner) = generic_arg(ty, "Option") {
+        return type_of(inner)
+    }
+    for list in &["Vec", "VecDeque", "HashSet", "BTreeSet"] {
+        if let Some(element) = generic_arg(ty, list) {
+            return SchemaType::Array(Box::new(type_of(element)))
+        }
+    }
+    let name = match *ty {
+        Type::Path(ref ty) if ty.qself.is_none() => match ty.path.segments.last() {
+            Some(segment)   => segment.ident.to_string(),
+            None            => return SchemaType::String,
+        },
+        _                                       => return SchemaType::String,
+    };
+    match &name[..] {
+        "bool"                                                          => SchemaType::Boolean,
+        "u8" | "u16" | "u32" | "u64" | "u128" | "usize"
+            | "i8" | "i16" | "i32" | "i64" | "i128" | "isize"           => SchemaType::Integer,
+        "f32" | "f64"                                                   => SchemaType::Number,
+        _                                                               => SchemaType::String,
+    }
+}
+
+/// A string as a JSON string literal.
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"'                         => json.push_str("\\\""),
+            '\\'                        => json.push_str("\\\\"),
+            '\n'                        => json.push_str("\\n"),
+            '\r'                        => json.push_str("\\r"),
+            '\t'                        => json.push_str("\\t"),
+            c if (c as u32) < 0x20      => { let _ = write!(json, "\\u{:04x}", c as u32); }
+            c                           => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ty(ty: &str) -> SchemaType {
+        type_of(&parse_str(ty).unwrap())
+    }
+
+    #[test]
+    fn types_are_mapped_by_syntax() {
+        assert_eq!(ty("u16"), SchemaType::Integer);
+        assert_eq!(ty("isize"), SchemaType::Integer);
+        assert_eq!(ty("f64"), SchemaType::Number);
+        assert_eq!(ty("bool"), SchemaType::Boolean);
+        assert_eq!(ty("Option<u32>"), SchemaType::Integer);
+        assert_eq!(ty("std::vec::Vec<String>"), SchemaType::Array(Box::new(SchemaType::String)));
+        assert_eq!(ty("HashSet<i64>"), SchemaType::Array(Box::new(SchemaType::Integer)));
+        assert_eq!(ty("Option<Vec<bool>>"), SchemaType::Array(Box::new(SchemaType::Boolean)));
+        assert_eq!(ty("PathBuf"), SchemaType::String);
+        assert_eq!(ty("LogLevel"), SchemaType::String);
+        assert_eq!(ty("(u8, u8)"), SchemaType::String);
+    }
+
+    #[test]
+    fn defaults_are_literals_of_their_type() {
+        assert_eq!(SchemaType::Integer.literal("8"), "8");
+        assert_eq!(SchemaType::Integer.literal("eight"), "\"eight\"");
+        assert_eq!(SchemaType::Integer.literal("+8"), "\"+8\"");
+        assert_eq!(SchemaType::Number.literal("1.5"), "1.5");
+        assert_eq!(SchemaType::Number.literal("-2.5e-3"), "-2.5e-3");
+        assert_eq!(SchemaType::Number.literal(".5"), "\".5\"");
+        assert_eq!(SchemaType::Number.literal("inf"), "\"inf\"");
+        assert_eq!(SchemaType::Boolean.literal("true"), "true");
+        assert_eq!(SchemaType::String.literal("8"), "\"8\"");
+    }
+
+    #[test]
+    fn strings_are_escaped() {
+        assert_eq!(json_string("a \"quoted\" \\ path\n"), "\"a \\\"quoted\\\" \\\\ path\\n\"");
+        assert_eq!(json_string("\u{1}"), "\"\\u0001\"");
+    }
+}
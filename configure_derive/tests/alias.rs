@@ -0,0 +1,30 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "migrated", generate_docs)]
+pub struct Config {
+    #[configure(alias = "MIGRATED_DB_URL", alias = "DATABASE_URL")]
+    database_url: String,
+}
+
+#[test]
+fn aliases_are_read_after_the_primary_name() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    use_default_config!();
+
+    env::set_var("DATABASE_URL", "postgres://alias");
+    assert_eq!(Config::generate().unwrap().database_url, "postgres://alias");
+
+    env::set_var("MIGRATED_DB_URL", "postgres://first-alias");
+    assert_eq!(Config::generate().unwrap().database_url, "postgres://first-alias");
+
+    env::set_var("MIGRATED_DATABASE_URL", "postgres://primary");
+    assert_eq!(Config::generate().unwrap().database_url, "postgres://primary");
+}
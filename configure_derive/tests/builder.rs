@@ -0,0 +1,77 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+
+use configure::Configure;
+use std::path::PathBuf;
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "built", builder)]
+#[serde(default)]
+pub struct Config {
+    host: String,
+    port: u16,
+    log_dir: PathBuf,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            host: String::from("localhost"),
+            port: 80,
+            log_dir: PathBuf::from("/var/log"),
+        }
+    }
+}
+
+#[test]
+fn builder_overrides_the_environment() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    env::set_var("BUILT_HOST", "example.com");
+    env::set_var("BUILT_PORT", "8000");
+    use_default_config!();
+
+    let cfg = Config::builder().port(8080u16).build().unwrap();
+    assert_eq!(cfg, Config {
+        host: String::from("example.com"),
+        port: 8080,
+        log_dir: PathBuf::from("/var/log"),
+    });
+
+    let cfg = Config::builder().log_dir("/tmp").host("override.com").build().unwrap();
+    assert_eq!(cfg, Config {
+        host: String::from("override.com"),
+        port: 8000,
+        log_dir: PathBuf::from("/tmp"),
+    });
+
+    let cfg = Config::builder().build().unwrap();
+    assert_eq!(cfg, Config {
+        host: String::from("example.com"),
+        port: 8000,
+        log_dir: PathBuf::from("/var/log"),
+    });
+
+    env::set_var("BUILT_PORT", "not a port");
+    assert!(Config::builder().port(8080u16).build().is_err());
+}
+
+// Without #[configure(builder)] no builder is generated, so a field may be
+// named `build`.
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "unbuilt")]
+pub struct Unbuilt {
+    build: u32,
+}
+
+#[test]
+fn build_field_without_builder() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    env::set_var("UNBUILT_BUILD", "3");
+    use_default_config!();
+
+    assert_eq!(Unbuilt::generate().unwrap(), Unbuilt { build: 3 });
+}
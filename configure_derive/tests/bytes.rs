@@ -0,0 +1,39 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Debug, Default, PartialEq)]
+#[configure(name = "encoded")]
+#[serde(default)]
+pub struct Config {
+    #[configure(bytes = "utf8")]
+    salt: Vec<u8>,
+    #[configure(bytes = "hex")]
+    key: Vec<u8>,
+    #[configure(bytes = "base64")]
+    token: Option<Vec<u8>>,
+}
+
+#[test]
+fn bytes_encodings() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    env::set_var("ENCODED_SALT", "deadbeef");
+    env::set_var("ENCODED_KEY", "deadbeef");
+    env::set_var("ENCODED_TOKEN", "3q2+7w==");
+    use_default_config!();
+
+    assert_eq!(Config::generate().unwrap(), Config {
+        salt: b"deadbeef".to_vec(),
+        key: vec![0xde, 0xad, 0xbe, 0xef],
+        token: Some(vec![0xde, 0xad, 0xbe, 0xef]),
+    });
+
+    env::set_var("ENCODED_TOKEN", "not base64");
+    let err = Config::generate().unwrap_err().to_string();
+    assert_eq!(err, "invalid value for `token` (`ENCODED_TOKEN`): `not base64` is not base64");
+}
@@ -0,0 +1,36 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Serialize, Debug, PartialEq)]
+#[configure(name = "snakeCased", case = "snake", generate_docs)]
+pub struct Config {
+    database_url: String,
+    #[configure(required)]
+    pool_size: u32,
+}
+
+#[test]
+fn snake_case_variables() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    env::set_var("snake_cased_database_url", "postgres://localhost");
+    env::set_var("SNAKE_CASED_POOL_SIZE", "4");
+    use_default_config!();
+
+    assert_eq!(Config::generate().unwrap_err().to_string(),
+               "missing required configuration: \
+                set snake_cased_pool_size or [package.metadata.snakeCased].pool_size");
+
+    env::set_var("snake_cased_pool_size", "8");
+    let cfg = Config::generate().unwrap();
+    assert_eq!(cfg, Config { database_url: String::from("postgres://localhost"), pool_size: 8 });
+    assert_eq!(cfg.to_env_lines().unwrap(), vec![
+        "snake_cased_database_url=postgres://localhost",
+        "snake_cased_pool_size=8",
+    ]);
+}
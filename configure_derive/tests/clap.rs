@@ -0,0 +1,79 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+use std::path::PathBuf;
+
+use configure::Configure;
+use configure::clap::Command;
+
+#[derive(Configure, Deserialize, Debug, Default, PartialEq)]
+#[configure(name = "flagged")]
+#[serde(default)]
+pub struct Config {
+    /// The address to listen on.
+    socket_addr: String,
+    threads: Option<u32>,
+    hosts: Vec<String>,
+    tls: bool,
+    #[configure(flag)]
+    verbose: bool,
+    #[configure(percent)]
+    cpu_share: f64,
+    #[configure(secret)]
+    password: String,
+    log_dir: PathBuf,
+    #[configure(skip)]
+    #[serde(skip)]
+    reloads: u32,
+}
+
+fn command() -> Command {
+    Config::augment_args(Command::new("flagged"))
+}
+
+#[test]
+fn flags_override_the_environment() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    env::set_var("FLAGGED_SOCKET_ADDR", "127.0.0.1:8080");
+    env::set_var("FLAGGED_THREADS", "4");
+    env::set_var("FLAGGED_HOSTS", "alpha");
+    env::set_var("FLAGGED_PASSWORD", "hunter2");
+    use_default_config!();
+
+    let matches = command().try_get_matches_from(vec!["flagged"]).unwrap();
+    assert_eq!(Config::from_matches(&matches).unwrap(), Config::generate().unwrap());
+
+    let matches = command().try_get_matches_from(vec![
+        "flagged",
+        "--socket-addr", "0.0.0.0:80",
+        "--hosts=beta,gamma",
+        "--tls",
+        "--verbose",
+        "--cpu-share", "50%",
+        "--log-dir", "/var/log/flagged",
+    ]).unwrap();
+    assert_eq!(Config::from_matches(&matches).unwrap(), Config {
+        socket_addr: String::from("0.0.0.0:80"),
+        threads: Some(4),
+        hosts: vec![String::from("beta"), String::from("gamma")],
+        tls: true,
+        verbose: true,
+        cpu_share: 0.5,
+        password: String::from("hunter2"),
+        log_dir: PathBuf::from("/var/log/flagged"),
+        reloads: 0,
+    });
+
+    assert!(command().try_get_matches_from(vec!["flagged", "--threads", "four"]).is_err());
+    assert!(command().try_get_matches_from(vec!["flagged", "--reloads", "1"]).is_err());
+    let matches = command().try_get_matches_from(vec!["flagged", "--cpu-share", "half%"]).unwrap();
+    assert_eq!(Config::from_matches(&matches).unwrap_err().to_string(),
+               "invalid value for `cpu_share` (`--cpu-share`): `half%` is not a percentage: invalid float literal");
+
+    let help = command().render_help().to_string();
+    assert!(help.contains("--socket-addr <socket-addr>"), "{}", help);
+    assert!(help.contains("The address to listen on."), "{}", help);
+}
@@ -0,0 +1,38 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+use std::net::IpAddr;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Debug, Default, PartialEq)]
+#[configure(name = "collected", collect_errors)]
+#[serde(default)]
+pub struct Config {
+    threads: u32,
+    port: u16,
+    name: String,
+    #[configure(from_str)]
+    #[serde(skip)]
+    host: Option<IpAddr>,
+}
+
+#[test]
+fn every_invalid_field_is_reported() {
+    use_default_config!();
+
+    env::set_var("COLLECTED_THREADS", "four");
+    assert_eq!(Config::generate().unwrap_err().to_string(), "invalid digit found in string");
+
+    env::set_var("COLLECTED_PORT", "99999");
+    env::set_var("COLLECTED_NAME", "server");
+    env::set_var("COLLECTED_HOST", "localhost");
+    assert_eq!(Config::generate().unwrap_err().to_string(),
+               "3 configuration errors: \
+                `threads`: invalid digit found in string; \
+                `port`: number too large to fit in target type; \
+                `host`: invalid value for `host` (`COLLECTED_HOST`): invalid IP address syntax");
+}
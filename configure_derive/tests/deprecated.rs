@@ -0,0 +1,65 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+use std::path::PathBuf;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Debug, Default, PartialEq)]
+#[configure(name = "legacy", generate_docs)]
+#[serde(default)]
+pub struct Config {
+    timeout: u32,
+    #[configure(deprecated = "use LEGACY_TIMEOUT instead")]
+    old_timeout: u32,
+    #[configure(deprecated = "it is ignored", env_only)]
+    old_threads: u32,
+}
+
+#[derive(Configure, Deserialize, Debug, Default, PartialEq)]
+#[configure(name = "unset")]
+#[serde(default)]
+pub struct Unset {
+    timeout: u32,
+    #[configure(deprecated = "use UNSET_TIMEOUT instead")]
+    old_timeout: u32,
+}
+
+#[test]
+fn deprecated_fields_warn_when_set() {
+    let dir: PathBuf = env::var_os("CARGO_MANIFEST_DIR").unwrap().into();
+    env::set_var("CARGO_MANIFEST_DIR", dir.join("tests/fixtures/deprecated"));
+    use_default_config!();
+
+    // Set in the Cargo.toml.
+    let (config, warnings) = Config::generate_with_warnings().unwrap();
+    assert_eq!(config.old_timeout, 30);
+    assert_eq!(warnings, [
+        "LEGACY_OLD_TIMEOUT or [package.metadata.legacy].old_timeout is deprecated: use LEGACY_TIMEOUT instead",
+    ]);
+
+    // Set in the environment.
+    env::set_var("LEGACY_OLD_THREADS", "4");
+    let (config, warnings) = Config::generate_with_warnings().unwrap();
+    assert_eq!(config.old_threads, 4);
+    assert_eq!(warnings, [
+        "LEGACY_OLD_TIMEOUT or [package.metadata.legacy].old_timeout is deprecated: use LEGACY_TIMEOUT instead",
+        "LEGACY_OLD_THREADS is deprecated: it is ignored",
+    ]);
+
+    // Not set at all.
+    env::set_var("UNSET_TIMEOUT", "10");
+    let (unset, warnings) = Unset::generate_with_warnings().unwrap();
+    assert_eq!(unset, Unset { timeout: 10, old_timeout: 0 });
+    assert!(warnings.is_empty(), "{:?}", warnings);
+}
+
+#[test]
+fn deprecated_fields_are_documented() {
+    let docs = Config::environment_docs();
+    assert!(docs.contains("- **LEGACY_OLD_TIMEOUT** (u32, deprecated)\n  - Deprecated: use LEGACY_TIMEOUT instead\n"), "{}", docs);
+    assert!(docs.contains("- **LEGACY_TIMEOUT** (u32)\n"), "{}", docs);
+}
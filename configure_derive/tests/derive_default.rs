@@ -0,0 +1,54 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+use std::time::Duration;
+
+use configure::Configure;
+
+fn default_hosts() -> Vec<String> {
+    vec![String::from("localhost")]
+}
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "defaulted", derive_default)]
+#[serde(default)]
+pub struct Config {
+    #[configure(default = "8")]
+    threads: usize,
+    #[configure(default = "String::from(\"example.com\")")]
+    host: String,
+    #[configure(default = "default_hosts()")]
+    hosts: Vec<String>,
+    #[configure(default = "Duration::from_secs(30)")]
+    timeout: Duration,
+    #[configure(default)]
+    verbose: bool,
+    port: Option<u16>,
+}
+
+#[test]
+fn derive_default_uses_field_defaults() {
+    assert_eq!(Config::default(), Config {
+        threads: 8,
+        host: String::from("example.com"),
+        hosts: vec![String::from("localhost")],
+        timeout: Duration::from_secs(30),
+        verbose: false,
+        port: None,
+    });
+}
+
+#[test]
+fn generated_config_falls_back_to_field_defaults() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    env::set_var("DEFAULTED_HOST", "db.example.com");
+    use_default_config!();
+
+    let cfg = Config::generate().unwrap();
+    assert_eq!(cfg.threads, 8);
+    assert_eq!(cfg.host, "db.example.com");
+    assert_eq!(cfg.hosts, vec!["localhost"]);
+}
@@ -0,0 +1,41 @@
+extern crate serde;
+
+extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use configure::{Configure, FieldDiff};
+
+#[derive(Configure, Deserialize, Debug, Default, PartialEq)]
+#[configure(name = "diffed", diff)]
+#[serde(default)]
+pub struct Config {
+    threads: usize,
+    host: String,
+    #[configure(secret)]
+    password: String,
+    tls: bool,
+    r#type: String,
+    #[configure(skip)]
+    #[serde(skip)]
+    reloads: u32,
+}
+
+#[test]
+fn changed_fields_in_order() {
+    let old = Config::default();
+    let new = Config {
+        threads: 8,
+        password: String::from("hunter2"),
+        r#type: String::from("worker"),
+        reloads: 1,
+        ..Config::default()
+    };
+
+    assert_eq!(old.diff(&old), []);
+    assert_eq!(old.diff(&new), [
+        FieldDiff { field: "threads", old: String::from("0"), new: String::from("8") },
+        FieldDiff { field: "password", old: String::from("<redacted>"), new: String::from("<redacted>") },
+        FieldDiff { field: "type", old: String::from("\"\""), new: String::from("\"worker\"") },
+    ]);
+    assert_eq!(new.diff(&old)[0].to_string(), "threads: 8 -> 0");
+}
@@ -0,0 +1,26 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+#[derive(Configure, Deserialize)]
+#[configure(name = "server", generate_docs, docs_module = "server_variables")]
+#[allow(dead_code)]
+pub struct ServerConfig {
+    threads: usize,
+}
+
+#[derive(Configure, Deserialize)]
+#[configure(name = "client", generate_docs, docs_module = "client_variables")]
+#[allow(dead_code)]
+pub struct ClientConfig {
+    timeout: u64,
+}
+
+#[test]
+fn both_modules_are_present() {
+    assert_eq!(server_variables::THREADS, "SERVER_THREADS");
+    assert_eq!(client_variables::TIMEOUT, "CLIENT_TIMEOUT");
+    assert!(ServerConfig::environment_docs().contains("**SERVER_THREADS** (usize)"));
+    assert!(ClientConfig::environment_docs().contains("**CLIENT_TIMEOUT** (u64)"));
+}
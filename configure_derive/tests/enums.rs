@@ -0,0 +1,68 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+use std::path::PathBuf;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "enums_unit")]
+pub enum Level {
+    Low,
+    High,
+}
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "enums_env")]
+pub enum Storage {
+    Local { path: PathBuf },
+    S3 { bucket: String, region: String },
+}
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "enums_toml")]
+#[serde(rename = "Storage")]
+pub enum TomlStorage {
+    Local { path: PathBuf },
+    S3 { bucket: String, region: String },
+}
+
+fn use_fixture() {
+    let dir: PathBuf = env::var_os("CARGO_MANIFEST_DIR").unwrap().into();
+    env::set_var("CARGO_MANIFEST_DIR", dir.join("tests/fixtures/enums"));
+    use_default_config!();
+}
+
+#[test]
+fn unit_variant() {
+    use_fixture();
+    env::set_var("ENUMS_UNIT_LEVEL", "high");
+
+    assert_eq!(Level::generate().unwrap(), Level::High);
+}
+
+#[test]
+fn struct_variant_from_env() {
+    use_fixture();
+    env::set_var("ENUMS_ENV_STORAGE", "s3");
+    env::set_var("ENUMS_ENV_STORAGE_BUCKET", "logs");
+    env::set_var("ENUMS_ENV_STORAGE_REGION", "eu-west-1");
+
+    assert_eq!(Storage::generate().unwrap(), Storage::S3 {
+        bucket: String::from("logs"),
+        region: String::from("eu-west-1"),
+    });
+}
+
+#[test]
+fn struct_variant_from_toml() {
+    use_fixture();
+
+    assert_eq!(TomlStorage::generate().unwrap(), TomlStorage::S3 {
+        bucket: String::from("logs"),
+        region: String::from("eu-west-1"),
+    });
+}
@@ -0,0 +1,63 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+
+use configure::Configure;
+
+mod server {
+    #[derive(Configure, Deserialize, Debug, PartialEq)]
+    #[configure(name = "consts", generate_docs)]
+    pub struct Config {
+        pub threads: usize,
+        #[configure(rename = "addr")]
+        pub socket_addr: String,
+        #[configure(env = "CONSTS_DATABASE")]
+        pub database_url: String,
+    }
+}
+
+mod storage {
+    #[derive(Configure, Deserialize, Debug, PartialEq)]
+    #[configure(name = "consts", generate_docs)]
+    pub enum Storage {
+        Local { path: String },
+        S3 { bucket: String },
+    }
+}
+
+#[test]
+fn constants_name_variables() {
+    use server::environment_variables::*;
+
+    assert_eq!(THREADS, "CONSTS_THREADS");
+    assert_eq!(SOCKET_ADDR, "CONSTS_ADDR");
+    assert_eq!(DATABASE_URL, "CONSTS_DATABASE");
+
+    env::remove_var("CARGO_MANIFEST_DIR");
+    env::set_var(THREADS, "4");
+    env::set_var(SOCKET_ADDR, "0.0.0.0:80");
+    env::set_var(DATABASE_URL, "postgres://localhost");
+    use_default_config!();
+
+    assert_eq!(server::Config::generate().unwrap(), server::Config {
+        threads: 4,
+        socket_addr: String::from("0.0.0.0:80"),
+        database_url: String::from("postgres://localhost"),
+    });
+}
+
+#[test]
+fn enum_constants_name_variables() {
+    use storage::environment_variables::*;
+
+    env::remove_var("CARGO_MANIFEST_DIR");
+    env::set_var(STORAGE, "s3");
+    env::set_var(BUCKET, "assets");
+    use_default_config!();
+
+    assert_eq!(PATH, "CONSTS_STORAGE_PATH");
+    assert_eq!(storage::Storage::generate().unwrap(), storage::Storage::S3 { bucket: String::from("assets") });
+}
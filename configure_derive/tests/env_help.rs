@@ -0,0 +1,55 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+
+use configure::Configure;
+
+/// The server.
+#[derive(Configure, Deserialize, Default)]
+#[configure(name = "help_server", generate_docs, docs_module = "server_variables")]
+#[serde(default)]
+pub struct Server {
+    /// The port to listen on.
+    port: u16,
+}
+
+/// The database.
+#[derive(Configure, Deserialize, Default)]
+#[configure(name = "help_database", generate_docs, docs_module = "database_variables")]
+#[serde(default)]
+pub struct Database {
+    /// The URL of the database.
+    url: String,
+}
+
+/// Not generated or registered.
+#[derive(Configure, Deserialize, Default)]
+#[configure(name = "help_unused", generate_docs, docs_module = "unused_variables")]
+#[serde(default)]
+#[allow(dead_code)]
+pub struct Unused {
+    verbose: bool,
+}
+
+#[test]
+fn registered_docs_are_printed_in_order() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    use_default_config!();
+
+    Database::register_docs();
+    Server::generate().unwrap();
+    Database::generate().unwrap();
+
+    let mut out = vec![];
+    configure::print_env_help(&mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let expected = format!("# help_database\n\n{}\n\n# help_server\n\n{}\n",
+                           Database::environment_docs(), Server::environment_docs());
+    assert_eq!(out, expected);
+    assert!(out.contains("- **HELP_DATABASE_URL** (String): The URL of the database.\n"), "{}", out);
+    assert!(out.contains("- **HELP_SERVER_PORT** (u16): The port to listen on.\n"), "{}", out);
+    assert!(!out.contains("HELP_UNUSED"), "{}", out);
+}
@@ -0,0 +1,54 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+use std::path::PathBuf;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Serialize, Debug, PartialEq)]
+#[configure(name = "lines")]
+#[serde(default)]
+pub struct Config {
+    threads: usize,
+    hosts: Vec<String>,
+    tls_cert: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            threads: 1,
+            hosts: vec![String::from("localhost")],
+            tls_cert: None,
+        }
+    }
+}
+
+#[test]
+fn round_trip_through_env_lines() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    use_default_config!();
+
+    let cfg = Config {
+        threads: 8,
+        hosts: vec![String::from("alpha"), String::from("beta")],
+        tls_cert: Some("etc/certificate".into()),
+    };
+
+    let lines = cfg.to_env_lines().unwrap();
+    assert_eq!(lines, vec![
+        "LINES_THREADS=8",
+        "LINES_HOSTS=alpha,beta",
+        "LINES_TLS_CERT=etc/certificate",
+    ]);
+
+    for line in &lines {
+        let mut parts = line.splitn(2, '=');
+        env::set_var(parts.next().unwrap(), parts.next().unwrap());
+    }
+
+    assert_eq!(Config::generate().unwrap(), cfg);
+}
@@ -0,0 +1,46 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "overridden")]
+#[serde(default)]
+pub struct Config {
+    #[configure(env = "DATABASE_URL")]
+    database_url: String,
+    threads: usize,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            database_url: String::from("postgres://localhost"),
+            threads: 1,
+        }
+    }
+}
+
+#[test]
+fn env_attribute_uses_exact_variable_name() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    env::set_var("DATABASE_URL", "postgres://db.example.com");
+    env::set_var("OVERRIDDEN_DATABASE_URL", "postgres://ignored.example.com");
+    env::set_var("OVERRIDDEN_THREADS", "4");
+    use_default_config!();
+
+    assert_eq!(Config::generate().unwrap(), Config {
+        database_url: String::from("postgres://db.example.com"),
+        threads: 4,
+    });
+
+    env::remove_var("DATABASE_URL");
+    assert_eq!(Config::generate_from(Config::default()).unwrap(), Config {
+        database_url: String::from("postgres://localhost"),
+        threads: 4,
+    });
+}
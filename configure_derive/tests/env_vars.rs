@@ -0,0 +1,89 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+#[derive(Configure, Deserialize)]
+#[configure(name = "listed")]
+#[allow(dead_code)]
+pub struct Config {
+    socket_addr: String,
+    #[configure(rename = "max_conns")]
+    max_connections: u32,
+    #[configure(env = "DATABASE_URL", alias = "DB_URL")]
+    database_url: String,
+    #[configure(alias = "LISTED_TIMEOUT_MS")]
+    timeout: u64,
+    #[configure(skip)]
+    #[serde(skip)]
+    cache: Vec<u8>,
+    #[configure(toml_only)]
+    features: Vec<String>,
+    #[configure(flatten)]
+    pool: Pool,
+}
+
+#[derive(Configure, Deserialize)]
+#[configure(name = "pool")]
+#[allow(dead_code)]
+pub struct Pool {
+    size: u32,
+    idle_timeout: u64,
+    #[configure(skip)]
+    #[serde(skip)]
+    stats: Vec<u32>,
+}
+
+#[derive(Configure, Deserialize)]
+#[configure(name = "listed")]
+#[allow(dead_code)]
+pub enum Storage {
+    Local { path: String },
+    S3 { bucket: String, path: String },
+}
+
+#[test]
+fn env_vars_are_listed() {
+    assert_eq!(Config::ENV_VARS, &[
+        "LISTED_SOCKET_ADDR",
+        "LISTED_MAX_CONNS",
+        "DATABASE_URL",
+        "DB_URL",
+        "LISTED_TIMEOUT",
+        "LISTED_TIMEOUT_MS",
+    ]);
+}
+
+#[test]
+fn flattened_env_vars_are_listed() {
+    assert_eq!(Config::env_vars(), [
+        "LISTED_SOCKET_ADDR",
+        "LISTED_MAX_CONNS",
+        "DATABASE_URL",
+        "DB_URL",
+        "LISTED_TIMEOUT",
+        "LISTED_TIMEOUT_MS",
+        "LISTED_POOL_SIZE",
+        "LISTED_POOL_IDLE_TIMEOUT",
+    ]);
+    assert_eq!(Pool::env_vars(), ["POOL_SIZE", "POOL_IDLE_TIMEOUT"]);
+}
+
+#[test]
+fn enum_env_vars_are_listed() {
+    assert_eq!(Storage::ENV_VARS, &[
+        "LISTED_STORAGE",
+        "LISTED_STORAGE_PATH",
+        "LISTED_STORAGE_BUCKET",
+    ]);
+}
+
+#[derive(Configure)]
+#[configure(name = "listed_hosts", env = "LISTED_HOST_LIST")]
+#[allow(dead_code)]
+pub struct Hosts(Vec<String>);
+
+#[test]
+fn newtype_env_vars_are_listed() {
+    assert_eq!(Hosts::ENV_VARS, &["LISTED_HOST_LIST"]);
+}
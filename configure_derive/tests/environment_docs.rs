@@ -0,0 +1,56 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+#[derive(Configure, Deserialize)]
+#[configure(name = "documented", generate_docs)]
+#[serde(default)]
+pub struct Config {
+    /// The number of worker threads.
+    threads: usize,
+    #[configure(flag)]
+    verbose: bool,
+    #[configure(env = "DATABASE_URL")]
+    database_url: String,
+    hosts: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config { threads: 4, verbose: false, database_url: String::new(), hosts: vec![] }
+    }
+}
+
+mod documented {
+    #[derive(Configure, Deserialize)]
+    #[configure(name = "documented", generate_docs)]
+    #[allow(dead_code)]
+    pub struct Port(u16);
+}
+
+mod hosts {
+    #[derive(Configure, Deserialize)]
+    #[configure(name = "documented_hosts", generate_docs)]
+    #[allow(dead_code)]
+    pub struct Hosts(Option<Vec<String>>);
+}
+
+#[test]
+fn environment_docs() {
+    let docs = Config::environment_docs();
+    assert!(docs.starts_with("These environment variables can be used to configure documented."), "{}", docs);
+    assert!(docs.contains("- **DOCUMENTED_THREADS** (usize): The number of worker threads.\n"), "{}", docs);
+    assert!(docs.contains("- **DOCUMENTED_VERBOSE** (flag)\n"), "{}", docs);
+    assert!(docs.contains("- **DATABASE_URL** (String)\n"), "{}", docs);
+    assert!(docs.contains("- **DOCUMENTED_HOSTS** (Vec<String>)\n"), "{}", docs);
+    assert!(docs.ends_with("you can also override how configuration is handled using the API in that crate."), "{}", docs);
+}
+
+#[test]
+fn newtype_environment_docs() {
+    let docs = documented::Port::environment_docs();
+    assert!(docs.contains("- **DOCUMENTED** (u16)\n"), "{}", docs);
+    let docs = hosts::Hosts::environment_docs();
+    assert!(docs.contains("- **DOCUMENTED_HOSTS** (Option<Vec<String>>)\n"), "{}", docs);
+}
@@ -1,7 +1,7 @@
 extern crate serde;
+#[macro_use] extern crate serde_json;
 
 #[macro_use] extern crate configure;
-#[macro_use] extern crate configure_derive;
 #[macro_use] extern crate serde_derive;
 
 use std::env;
@@ -15,7 +15,7 @@ use configure::Configure;
 #[configure(generate_docs)]
 #[serde(default)]
 pub struct Config {
-    #[configure(docs = "This is a socket address.")]
+    #[configure(docs = "This is a socket address.", doc_default = "127.0.0.1:7878")]
     socket_addr: SocketAddr,
     /// This is the cert path.
     tls_cert: Option<PathBuf>,
@@ -41,3 +41,33 @@ fn check_that_it_works() {
     assert_eq!(cfg.socket_addr, "127.0.0.1:7878".parse().unwrap());
     assert_eq!(cfg.tls_cert, Some("etc/certificate".into()));
 }
+
+#[test]
+fn from_json_value() {
+    let cfg = Config::from_value(json!({
+        "socket_addr": "0.0.0.0:80",
+        "tls_cert": "etc/certificate",
+    })).unwrap();
+
+    assert_eq!(cfg.socket_addr, "0.0.0.0:80".parse().unwrap());
+    assert_eq!(cfg.tls_cert, Some("etc/certificate".into()));
+
+    let cfg = Config::from_value(json!({})).unwrap();
+    assert_eq!(cfg.socket_addr, "127.0.0.1:7878".parse().unwrap());
+    assert!(Config::from_value(json!({ "socket_addr": "nowhere" })).is_err());
+}
+
+#[test]
+fn environment_docs_markdown() {
+    assert_eq!(Config::environment_docs_markdown(), "\
+| Variable | Type | Default | Required | Description |
+|----------|------|---------|----------|-------------|
+| `EXAMPLE_SOCKET_ADDR` | SocketAddr | 127.0.0.1:7878 | no | This is a socket address. |
+| `EXAMPLE_TLS_CERT` | Option<PathBuf> |  | no | This is the cert path. |
+");
+}
+
+#[test]
+fn env_vars() {
+    assert_eq!(Config::ENV_VARS, &["EXAMPLE_SOCKET_ADDR", "EXAMPLE_TLS_CERT"]);
+}
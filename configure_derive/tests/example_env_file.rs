@@ -0,0 +1,64 @@
+extern crate serde;
+
+extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::path::PathBuf;
+
+use configure::Configure;
+use configure::source::{CONFIGURATION, StaticMapSource};
+
+#[derive(Configure, Deserialize, Serialize, Debug, PartialEq)]
+#[configure(name = "dotenv")]
+#[serde(default)]
+pub struct Config {
+    /// The number of worker threads.
+    threads: usize,
+    /// The hosts to connect to.
+    hosts: Vec<String>,
+    /// A certificate to serve TLS with.
+    tls_cert: Option<PathBuf>,
+    #[configure(secret)]
+    token: String,
+    #[configure(toml_only)]
+    log_dir: PathBuf,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            threads: 4,
+            hosts: vec![String::from("alpha"), String::from("beta")],
+            tls_cert: None,
+            token: String::new(),
+            log_dir: PathBuf::from("/var/log"),
+        }
+    }
+}
+
+#[test]
+fn example_env_file_round_trips() {
+    let example = Config::example_env_file().unwrap();
+    assert_eq!(example, "\
+# The number of worker threads.
+DOTENV_THREADS=4
+
+# The hosts to connect to.
+DOTENV_HOSTS=alpha,beta
+
+# A certificate to serve TLS with.
+# DOTENV_TLS_CERT=
+
+DOTENV_TOKEN=
+");
+
+    let pairs = example.lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let mut parts = line.splitn(2, '=');
+            (parts.next().unwrap().to_owned(), parts.next().unwrap().to_owned())
+        })
+        .collect::<Vec<_>>();
+    CONFIGURATION.set(StaticMapSource::new(pairs));
+    assert_eq!(Config::generate().unwrap(), Config::default());
+}
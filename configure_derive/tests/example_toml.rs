@@ -0,0 +1,84 @@
+extern crate serde;
+extern crate toml;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::path::PathBuf;
+
+#[derive(Configure, Deserialize, Serialize, Debug, PartialEq)]
+#[configure(name = "sample")]
+#[serde(default)]
+pub struct Config {
+    /// The number of worker threads.
+    threads: usize,
+    /// The hosts to connect to.
+    hosts: Vec<String>,
+    /// A certificate to serve TLS with.
+    tls_cert: Option<PathBuf>,
+    #[configure(secret)]
+    password: String,
+    #[configure(flatten)]
+    database: Database,
+    #[configure(skip)]
+    #[serde(skip)]
+    internal: u32,
+}
+
+#[derive(Configure, Deserialize, Serialize, Debug, PartialEq)]
+#[configure(name = "sample_database")]
+#[serde(default)]
+pub struct Database {
+    url: String,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            threads: 4,
+            hosts: vec![String::from("localhost")],
+            tls_cert: None,
+            password: String::from("hunter2"),
+            database: Database::default(),
+            internal: 0,
+        }
+    }
+}
+
+impl Default for Database {
+    fn default() -> Database {
+        Database { url: String::from("postgres://localhost") }
+    }
+}
+
+#[test]
+fn example_toml_renders_defaults() {
+    let example = Config::example_toml().unwrap();
+    assert_eq!(example, "\
+# The number of worker threads.
+# Environment variable: SAMPLE_THREADS
+threads = 4
+
+# The hosts to connect to.
+# Environment variable: SAMPLE_HOSTS
+hosts = [\"localhost\"]
+
+# A certificate to serve TLS with.
+# Environment variable: SAMPLE_TLS_CERT
+# tls_cert =
+
+# Environment variable: SAMPLE_PASSWORD
+# password = \"<redacted>\"
+
+[database]
+url = \"postgres://localhost\"
+");
+}
+
+#[test]
+fn example_toml_round_trips() {
+    let example = Config::example_toml().unwrap();
+    assert!(example.parse::<toml::Value>().is_ok());
+    let config: Config = toml::from_str(&example).unwrap();
+    assert_eq!(config, Config::default());
+}
@@ -0,0 +1,79 @@
+extern crate serde;
+
+extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use configure::{Configure, FieldMetadata};
+
+#[derive(Configure, Deserialize)]
+#[configure(name = "described")]
+#[allow(dead_code)]
+pub struct Config {
+    /// The address to listen on.
+    socket_addr: String,
+    #[configure(rename = "max_conns")]
+    max_connections: Option<u32>,
+    #[configure(env = "DATABASE_URL", required, secret)]
+    database_url: String,
+    #[configure(toml_key = "log-level", env_only)]
+    log_level: String,
+    #[configure(toml_only)]
+    features: Vec<String>,
+    #[configure(skip)]
+    #[serde(skip)]
+    cache: Vec<u8>,
+    #[configure(flatten)]
+    pool: Pool,
+}
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+pub struct Pool {
+    size: u32,
+}
+
+#[test]
+fn fields_are_described() {
+    let field = |name, ty| FieldMetadata {
+        name,
+        env_var: None,
+        toml_key: None,
+        ty,
+        docs: None,
+        secret: false,
+        required: false,
+        skip: false,
+        flatten: false,
+    };
+
+    assert_eq!(Config::FIELDS, [
+        FieldMetadata {
+            env_var: Some("DESCRIBED_SOCKET_ADDR"),
+            toml_key: Some("socket_addr"),
+            docs: Some("The address to listen on."),
+            ..field("socket_addr", "String")
+        },
+        FieldMetadata {
+            env_var: Some("DESCRIBED_MAX_CONNS"),
+            toml_key: Some("max_conns"),
+            ..field("max_connections", "Option<u32>")
+        },
+        FieldMetadata {
+            env_var: Some("DATABASE_URL"),
+            toml_key: Some("database_url"),
+            secret: true,
+            required: true,
+            ..field("database_url", "String")
+        },
+        FieldMetadata {
+            env_var: Some("DESCRIBED_LOG_LEVEL"),
+            ..field("log_level", "String")
+        },
+        FieldMetadata {
+            toml_key: Some("features"),
+            ..field("features", "Vec<String>")
+        },
+        FieldMetadata { skip: true, ..field("cache", "Vec<u8>") },
+        FieldMetadata { flatten: true, ..field("pool", "Pool") },
+    ]);
+}
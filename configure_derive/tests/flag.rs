@@ -0,0 +1,28 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Debug, Default, PartialEq)]
+#[configure(name = "app")]
+#[serde(default)]
+pub struct Config {
+    #[configure(flag)]
+    verbose: bool,
+    #[configure(flag)]
+    quiet: bool,
+}
+
+#[test]
+fn flags_are_set_by_presence() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    env::set_var("APP_VERBOSE", "");
+    env::remove_var("APP_QUIET");
+    use_default_config!();
+
+    assert_eq!(Config::generate().unwrap(), Config { verbose: true, quiet: false });
+}
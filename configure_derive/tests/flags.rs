@@ -0,0 +1,51 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::collections::{BTreeSet, HashSet};
+use std::env;
+use std::path::PathBuf;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Debug, Default, PartialEq)]
+#[configure(name = "flagged")]
+#[serde(default)]
+pub struct Config {
+    #[configure(flags)]
+    features: HashSet<String>,
+    #[configure(flags)]
+    debug: BTreeSet<String>,
+    switches: Vec<bool>,
+}
+
+fn set(names: &[&str]) -> HashSet<String> {
+    names.iter().map(|&name| String::from(name)).collect()
+}
+
+#[test]
+fn flags() {
+    let dir: PathBuf = env::var_os("CARGO_MANIFEST_DIR").unwrap().into();
+    env::set_var("CARGO_MANIFEST_DIR", dir.join("tests/fixtures/flags"));
+    use_default_config!();
+
+    let cfg = Config::generate().unwrap();
+    assert_eq!(cfg.features, set(&["metrics", "tracing"]));
+    assert!(cfg.debug.is_empty());
+
+    env::set_var("FLAGGED_FEATURES", "Verbose, debug,,DEBUG");
+    env::set_var("FLAGGED_DEBUG", "sql,http,sql");
+    let cfg = Config::generate().unwrap();
+    assert_eq!(cfg.features, set(&["verbose", "debug"]));
+    assert_eq!(cfg.debug.into_iter().collect::<Vec<_>>(), vec!["http", "sql"]);
+
+    env::set_var("FLAGGED_FEATURES", "");
+    assert!(Config::generate().unwrap().features.is_empty());
+
+    env::set_var("FLAGGED_SWITCHES", "true,false,1,0,TRUE");
+    assert_eq!(Config::generate().unwrap().switches, vec![true, false, true, false, true]);
+
+    env::set_var("FLAGGED_SWITCHES", "true,yes");
+    assert!(Config::generate().is_err());
+}
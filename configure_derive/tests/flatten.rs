@@ -0,0 +1,40 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+use std::path::PathBuf;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "nested", generate_docs)]
+pub struct Config {
+    threads: usize,
+    #[configure(flatten)]
+    db: DbConfig,
+}
+
+/// An ordinary struct, which does not derive `Configure`.
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct DbConfig {
+    url: String,
+    pool_size: u32,
+}
+
+#[test]
+fn flatten_reads_prefixed_vars_and_tables() {
+    let dir: PathBuf = env::var_os("CARGO_MANIFEST_DIR").unwrap().into();
+    env::set_var("CARGO_MANIFEST_DIR", dir.join("tests/fixtures/flatten"));
+    env::set_var("NESTED_DB_POOL_SIZE", "16");
+    use_default_config!();
+
+    assert_eq!(Config::generate().unwrap(), Config {
+        threads: 4,
+        db: DbConfig {
+            url: String::from("postgres://localhost"),
+            pool_size: 16,
+        },
+    });
+}
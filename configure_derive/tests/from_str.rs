@@ -0,0 +1,58 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+use std::net::{IpAddr, Ipv4Addr};
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "from_str")]
+#[serde(default)]
+pub struct Config {
+    #[configure(from_str)]
+    allowed: Vec<IpAddr>,
+    #[configure(from_str)]
+    gateway: Option<IpAddr>,
+    #[configure(from_str)]
+    threads: usize,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            allowed: vec![],
+            gateway: None,
+            threads: 1,
+        }
+    }
+}
+
+fn localhost() -> IpAddr {
+    IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))
+}
+
+#[test]
+fn from_str() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    use_default_config!();
+
+    assert_eq!(Config::generate().unwrap(), Config::default());
+
+    env::set_var("FROM_STR_ALLOWED", "127.0.0.1, ::1");
+    env::set_var("FROM_STR_GATEWAY", "127.0.0.1");
+    env::set_var("FROM_STR_THREADS", "4");
+    assert_eq!(Config::generate().unwrap(), Config {
+        allowed: vec![localhost(), "::1".parse().unwrap()],
+        gateway: Some(localhost()),
+        threads: 4,
+    });
+
+    env::set_var("FROM_STR_ALLOWED", "127.0.0.1,localhost");
+    let err = Config::generate().unwrap_err().to_string();
+    assert_eq!(err, "invalid value for `allowed` (`FROM_STR_ALLOWED`): element 1: invalid IP address syntax");
+    let err = Config::generate_from(Config::default()).unwrap_err().to_string();
+    assert_eq!(err, "invalid value for `allowed` (`FROM_STR_ALLOWED`): element 1: invalid IP address syntax");
+}
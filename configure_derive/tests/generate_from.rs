@@ -0,0 +1,45 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "based")]
+#[serde(default)]
+pub struct Config {
+    threads: usize,
+    host: String,
+    hosts: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            threads: 1,
+            host: String::from("localhost"),
+            hosts: vec![],
+        }
+    }
+}
+
+#[test]
+fn generate_from_overrides_only_provided_fields() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    env::set_var("BASED_THREADS", "8");
+    env::set_var("BASED_HOSTS", "a,b");
+    use_default_config!();
+
+    let base = Config {
+        threads: 2,
+        host: String::from("example.com"),
+        hosts: vec![String::from("c")],
+    };
+
+    assert_eq!(Config::generate_from(base).unwrap(), Config {
+        threads: 8,
+        host: String::from("example.com"),
+        hosts: vec![String::from("a"), String::from("b")],
+    });
+}
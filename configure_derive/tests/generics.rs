@@ -0,0 +1,81 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+use std::fmt::Debug;
+use std::str::FromStr;
+
+use serde::de::DeserializeOwned;
+
+use configure::Configure;
+
+/// A backend, which is not configuration itself, but has options which are.
+pub trait Backend {
+    type Opts: DeserializeOwned + Default + Debug + PartialEq;
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Memory;
+
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct MemoryOpts {
+    capacity: usize,
+}
+
+impl Backend for Memory {
+    type Opts = MemoryOpts;
+}
+
+#[derive(Configure, Deserialize, Debug, Default, PartialEq)]
+#[configure(name = "generic_bounded")]
+#[serde(default)]
+pub struct Bounded<T: FromStr + Default> {
+    value: T,
+}
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "generic_where")]
+#[serde(bound = "T::Opts: DeserializeOwned")]
+pub struct WithWhere<T> where T: Backend {
+    #[configure(flatten)]
+    #[serde(default)]
+    opts: T::Opts,
+}
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "generic_default", derive_default)]
+#[serde(default)]
+pub struct WithDefault<T: Debug + Default = u16> {
+    #[configure(default = "8")]
+    threads: usize,
+    port: T,
+}
+
+#[test]
+fn bounded_generic() {
+    use_default_config!();
+    env::set_var("GENERIC_BOUNDED_VALUE", "7");
+
+    assert_eq!(Bounded::<u8>::generate().unwrap(), Bounded { value: 7 });
+}
+
+#[test]
+fn where_clause() {
+    use_default_config!();
+    env::set_var("GENERIC_WHERE_OPTS_CAPACITY", "64");
+
+    assert_eq!(WithWhere::<Memory>::generate().unwrap(), WithWhere { opts: MemoryOpts { capacity: 64 } });
+}
+
+#[test]
+fn parameter_with_default() {
+    use_default_config!();
+    env::set_var("GENERIC_DEFAULT_PORT", "8080");
+
+    let cfg: WithDefault = WithDefault::generate().unwrap();
+    assert_eq!(cfg, WithDefault { threads: 8, port: 8080 });
+    assert_eq!(WithDefault::<u16>::default(), WithDefault { threads: 8, port: 0 });
+}
@@ -0,0 +1,43 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Debug, Default, PartialEq)]
+#[configure(name = "hidden", generate_docs)]
+#[serde(default)]
+pub struct Config {
+    /// The number of worker threads.
+    threads: usize,
+    /// An internal toggle.
+    #[configure(hide_docs)]
+    experimental_scheduler: bool,
+}
+
+#[test]
+fn hidden_fields_are_not_documented() {
+    let docs = Config::environment_docs();
+    assert!(docs.contains("HIDDEN_THREADS"), "{}", docs);
+    assert!(!docs.contains("HIDDEN_EXPERIMENTAL_SCHEDULER"), "{}", docs);
+    assert!(!docs.contains("internal toggle"), "{}", docs);
+
+    let markdown = Config::environment_docs_markdown();
+    assert!(markdown.contains("HIDDEN_THREADS"), "{}", markdown);
+    assert!(!markdown.contains("HIDDEN_EXPERIMENTAL_SCHEDULER"), "{}", markdown);
+
+    assert_eq!(Config::ENV_VARS, &["HIDDEN_THREADS", "HIDDEN_EXPERIMENTAL_SCHEDULER"]);
+    assert_eq!(environment_variables::EXPERIMENTAL_SCHEDULER, "HIDDEN_EXPERIMENTAL_SCHEDULER");
+}
+
+#[test]
+fn hidden_fields_are_read() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    env::set_var("HIDDEN_EXPERIMENTAL_SCHEDULER", "true");
+    use_default_config!();
+
+    assert_eq!(Config::generate().unwrap(), Config { threads: 0, experimental_scheduler: true });
+}
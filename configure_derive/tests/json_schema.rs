@@ -0,0 +1,75 @@
+extern crate serde;
+extern crate serde_json;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Debug,
+    Info,
+}
+
+/// The configuration of the scheduler.
+#[derive(Configure, Deserialize)]
+#[configure(name = "schema", json_schema)]
+#[allow(dead_code)]
+pub struct Config {
+    /// The number of worker threads.
+    #[configure(doc_default = "4")]
+    threads: usize,
+    /// How much slower to run at night.
+    #[configure(doc_default = "1.5")]
+    slowdown: f64,
+    #[configure(required)]
+    url: String,
+    tls_cert: Option<PathBuf>,
+    #[configure(doc_default = "info")]
+    log_level: LogLevel,
+    /// The hosts to connect to.
+    hosts: Vec<String>,
+    ports: Option<Vec<u16>>,
+    #[configure(flag)]
+    verbose: bool,
+    #[configure(toml_only)]
+    log_dir: PathBuf,
+}
+
+#[test]
+fn schema_describes_each_variable() {
+    let schema: Value = serde_json::from_str(&Config::config_schema()).unwrap();
+    assert_eq!(schema, json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "Config",
+        "description": "The configuration of the scheduler.",
+        "type": "object",
+        "properties": {
+            "SCHEMA_THREADS": {
+                "type": "integer",
+                "description": "The number of worker threads.",
+                "default": 4,
+            },
+            "SCHEMA_SLOWDOWN": {
+                "type": "number",
+                "description": "How much slower to run at night.",
+                "default": 1.5,
+            },
+            "SCHEMA_URL": { "type": "string" },
+            "SCHEMA_TLS_CERT": { "type": "string" },
+            "SCHEMA_LOG_LEVEL": { "type": "string", "default": "info" },
+            "SCHEMA_HOSTS": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "The hosts to connect to.",
+            },
+            "SCHEMA_PORTS": { "type": "array", "items": { "type": "integer" } },
+            "SCHEMA_VERBOSE": { "type": "boolean" },
+        },
+        "required": ["SCHEMA_URL"],
+    }));
+}
@@ -0,0 +1,32 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+use std::path::PathBuf;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Debug, Default, PartialEq)]
+#[configure(name = "layers")]
+#[serde(default)]
+pub struct Config {
+    #[configure(env_only)]
+    password: String,
+    #[configure(toml_only)]
+    pool_size: u32,
+}
+
+#[test]
+fn fields_ignore_forbidden_layers() {
+    let dir: PathBuf = env::var_os("CARGO_MANIFEST_DIR").unwrap().into();
+    env::set_var("CARGO_MANIFEST_DIR", dir.join("tests/fixtures/layers"));
+    use_default_config!();
+
+    env::set_var("LAYERS_POOL_SIZE", "20");
+    assert_eq!(Config::generate().unwrap(), Config { password: String::new(), pool_size: 10 });
+
+    env::set_var("LAYERS_PASSWORD", "hunter2");
+    assert_eq!(Config::generate().unwrap(), Config { password: String::from("hunter2"), pool_size: 10 });
+}
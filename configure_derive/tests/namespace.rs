@@ -0,0 +1,44 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Debug, Default, PartialEq)]
+#[configure(name = "namespaced")]
+#[serde(default)]
+pub struct Config {
+    threads: usize,
+    #[configure(namespace = "logging")]
+    level: String,
+}
+
+#[derive(Configure, Deserialize, Debug, Default, PartialEq)]
+#[configure(name = "namespaced_required")]
+#[serde(default)]
+pub struct Required {
+    #[configure(namespace = "logging", required)]
+    format: String,
+}
+
+#[test]
+fn namespaced_fields() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    env::set_var("NAMESPACED_THREADS", "4");
+    env::set_var("NAMESPACED_LEVEL", "trace");
+    env::set_var("LOGGING_LEVEL", "debug");
+    use_default_config!();
+
+    assert_eq!(Config::generate().unwrap(), Config {
+        threads: 4,
+        level: String::from("debug"),
+    });
+
+    assert_eq!(Config::ENV_VARS, ["NAMESPACED_THREADS", "LOGGING_LEVEL"]);
+    assert_eq!(Required::generate().unwrap_err().to_string(),
+               "missing required configuration: \
+                set LOGGING_FORMAT or [package.metadata.logging].format");
+}
@@ -0,0 +1,52 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+
+use std::env;
+use std::path::PathBuf;
+
+use configure::Configure;
+
+#[derive(Configure, Debug, PartialEq)]
+#[configure(name = "newtype_threads")]
+pub struct Threads(usize);
+
+#[derive(Configure, Debug, PartialEq)]
+#[configure(name = "newtype_hosts", env = "NEWTYPE_HOST_LIST")]
+pub struct Hosts(Vec<String>);
+
+#[derive(Configure, Debug, PartialEq)]
+#[configure(name = "newtype_toml")]
+pub struct Workers(u32);
+
+fn use_fixture() {
+    let dir: PathBuf = env::var_os("CARGO_MANIFEST_DIR").unwrap().into();
+    env::set_var("CARGO_MANIFEST_DIR", dir.join("tests/fixtures/newtype"));
+    use_default_config!();
+}
+
+#[test]
+fn numeric_newtype() {
+    use_fixture();
+
+    assert_eq!(Threads::generate().unwrap_err().to_string(),
+               "missing configuration: set NEWTYPE_THREADS or [package.metadata.newtype_threads].value");
+
+    env::set_var("NEWTYPE_THREADS", "8");
+    assert_eq!(Threads::generate().unwrap(), Threads(8));
+}
+
+#[test]
+fn list_newtype() {
+    use_fixture();
+    env::set_var("NEWTYPE_HOST_LIST", "a.example.com, b.example.com");
+
+    assert_eq!(Hosts::generate().unwrap(), Hosts(vec![String::from("a.example.com"), String::from("b.example.com")]));
+}
+
+#[test]
+fn newtype_from_toml() {
+    use_fixture();
+
+    assert_eq!(Workers::generate().unwrap(), Workers(16));
+}
@@ -0,0 +1,72 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+
+use configure::Configure;
+
+/// A port, which has no `Deserialize` implementation.
+#[derive(Debug, PartialEq)]
+pub struct Port(u16);
+
+fn parse_port(value: &str) -> Result<Port, String> {
+    match value.parse() {
+        Ok(port) if port > 0    => Ok(Port(port)),
+        _                       => Err(format!("`{}` is not a valid port", value)),
+    }
+}
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "parsed")]
+#[serde(default)]
+pub struct Config {
+    #[configure(parse_with = "parse_port")]
+    #[serde(skip)]
+    port: Port,
+    #[configure(parse_with = "parse_port")]
+    #[serde(skip)]
+    admin_port: Option<Port>,
+    threads: usize,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            port: Port(7878),
+            admin_port: None,
+            threads: 1,
+        }
+    }
+}
+
+#[test]
+fn parse_with() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    use_default_config!();
+
+    env::set_var("PARSED_THREADS", "4");
+    assert_eq!(Config::generate().unwrap(), Config {
+        port: Port(7878),
+        admin_port: None,
+        threads: 4,
+    });
+
+    env::set_var("PARSED_PORT", "8080");
+    env::set_var("PARSED_ADMIN_PORT", "9090");
+    assert_eq!(Config::generate().unwrap(), Config {
+        port: Port(8080),
+        admin_port: Some(Port(9090)),
+        threads: 4,
+    });
+    assert_eq!(Config::generate_from(Config::default()).unwrap(), Config {
+        port: Port(8080),
+        admin_port: Some(Port(9090)),
+        threads: 4,
+    });
+
+    env::set_var("PARSED_PORT", "http");
+    let err = Config::generate().unwrap_err().to_string();
+    assert_eq!(err, "invalid value for `port` (`PARSED_PORT`): `http` is not a valid port");
+}
@@ -0,0 +1,73 @@
+extern crate serde;
+extern crate serde_json;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "patched", partial)]
+#[serde(default)]
+pub struct Config {
+    pub host: String,
+    pub port: u16,
+    pub tls_cert: Option<String>,
+    #[configure(time_of_day)]
+    pub run_at: u32,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            host: String::from("localhost"),
+            port: 80,
+            tls_cert: None,
+            run_at: 0,
+        }
+    }
+}
+
+#[test]
+fn merge_overwrites_set_fields() {
+    let mut cfg = Config::default();
+    cfg.merge(ConfigPartial {
+        port: Some(8080),
+        tls_cert: Some(Some(String::from("cert.pem"))),
+        ..ConfigPartial::default()
+    });
+    assert_eq!(cfg, Config {
+        host: String::from("localhost"),
+        port: 8080,
+        tls_cert: Some(String::from("cert.pem")),
+        run_at: 0,
+    });
+
+    let patch: ConfigPartial = serde_json::from_str(r#"{ "host": "example.com" }"#).unwrap();
+    cfg.merge(patch);
+    assert_eq!(cfg.host, "example.com");
+    assert_eq!(cfg.port, 8080);
+}
+
+#[test]
+fn partial_from_env_reads_only_set_fields() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    env::set_var("PATCHED_PORT", "9000");
+    use_default_config!();
+
+    let partial = ConfigPartial::from_env().unwrap();
+    assert_eq!(partial.host, None);
+    assert_eq!(partial.port, Some(9000));
+    assert_eq!(partial.tls_cert, None);
+    assert_eq!(partial.run_at, None);
+
+    let mut cfg = Config::default();
+    cfg.merge(partial);
+    assert_eq!(cfg, Config { port: 9000, ..Config::default() });
+
+    // Parsed fields are parsed as they are by `generate`.
+    env::set_var("PATCHED_RUN_AT", "01:00");
+    assert_eq!(ConfigPartial::from_env().unwrap().run_at, Some(3600));
+}
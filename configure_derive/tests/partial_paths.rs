@@ -0,0 +1,20 @@
+extern crate configure;
+extern crate serde;
+extern crate serde_derive;
+
+// Neither derive is imported, so the generated partial struct must not rely
+// on `Deserialize` being in scope.
+#[derive(configure::Configure, serde_derive::Deserialize, Debug, PartialEq)]
+#[configure(name = "qualified", partial)]
+pub struct Config {
+    port: u16,
+}
+
+#[test]
+fn partial_with_qualified_derives() {
+    let mut cfg = Config { port: 80 };
+    cfg.merge(ConfigPartial { port: Some(8080) });
+    assert_eq!(cfg, Config { port: 8080 });
+    cfg.merge(ConfigPartial::default());
+    assert_eq!(cfg, Config { port: 8080 });
+}
@@ -0,0 +1,48 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Debug, Default, PartialEq)]
+#[configure(name = "shares")]
+#[serde(default)]
+pub struct Config {
+    #[configure(percent)]
+    cpu_share: f64,
+    #[configure(percent)]
+    memory_share: Option<f32>,
+    #[configure(percent)]
+    disk_share: f64,
+}
+
+#[test]
+fn percent_fields() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    env::set_var("SHARES_CPU_SHARE", "50%");
+    env::set_var("SHARES_MEMORY_SHARE", "25%");
+    env::set_var("SHARES_DISK_SHARE", "0.75");
+    use_default_config!();
+
+    assert_eq!(Config::generate().unwrap(), Config {
+        cpu_share: 0.5,
+        memory_share: Some(0.25),
+        disk_share: 0.75,
+    });
+
+    // Percentages are not clamped: limits belong in a `range` attribute.
+    let cpu_share = |value: &str| {
+        env::set_var("SHARES_CPU_SHARE", value);
+        Config::generate().map(|cfg| cfg.cpu_share)
+    };
+    assert_eq!(cpu_share("100%").unwrap(), 1.0);
+    assert_eq!(cpu_share("250%").unwrap(), 2.5);
+    assert_eq!(cpu_share("-20%").unwrap(), -0.2);
+    assert_eq!(cpu_share("-0.5").unwrap(), -0.5);
+
+    let err = cpu_share("half%").unwrap_err().to_string();
+    assert_eq!(err, "invalid value for `cpu_share` (`SHARES_CPU_SHARE`): `half%` is not a percentage: invalid float literal");
+}
@@ -0,0 +1,31 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "example", prefix = "ACME")]
+pub struct Config {
+    socket_addr: SocketAddr,
+    threads: usize,
+}
+
+#[test]
+fn prefix_changes_only_env_var_names() {
+    let dir: PathBuf = env::var_os("CARGO_MANIFEST_DIR").unwrap().into();
+    env::set_var("CARGO_MANIFEST_DIR", dir.join("tests/fixtures/prefix"));
+    env::set_var("ACME_SOCKET_ADDR", "0.0.0.0:80");
+    env::set_var("EXAMPLE_THREADS", "8");
+    use_default_config!();
+
+    assert_eq!(Config::generate().unwrap(), Config {
+        socket_addr: "0.0.0.0:80".parse().unwrap(),
+        threads: 4,
+    });
+}
@@ -0,0 +1,92 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+
+use configure::Configure;
+
+fn even(workers: &u8) -> Result<(), String> {
+    if workers.is_multiple_of(2) { Ok(()) } else { Err(format!("{} is odd", workers)) }
+}
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "ranged", generate_docs)]
+#[serde(default)]
+pub struct Config {
+    #[configure(range = "1..=65535")]
+    port: u16,
+    #[configure(range = "2..=64", validate = "even")]
+    workers: u8,
+    #[configure(range = "..=0.5")]
+    sample_rate: f64,
+    #[configure(range = "1024..")]
+    admin_port: Option<u16>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            port: 7878,
+            workers: 4,
+            sample_rate: 0.1,
+            admin_port: None,
+        }
+    }
+}
+
+#[test]
+fn ranges() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    use_default_config!();
+
+    env::set_var("RANGED_PORT", "65535");
+    env::set_var("RANGED_WORKERS", "64");
+    env::set_var("RANGED_SAMPLE_RATE", "0.5");
+    assert_eq!(Config::generate().unwrap(), Config {
+        port: 65535,
+        workers: 64,
+        sample_rate: 0.5,
+        admin_port: None,
+    });
+
+    env::set_var("RANGED_PORT", "0");
+    let err = Config::generate().unwrap_err().to_string();
+    assert_eq!(err, "invalid value for `port`: 0 is not in the range 1..=65535");
+
+    env::set_var("RANGED_PORT", "1");
+    env::set_var("RANGED_WORKERS", "65");
+    let err = Config::generate().unwrap_err().to_string();
+    assert_eq!(err, "invalid value for `workers`: 65 is not in the range 2..=64");
+
+    // The range is checked before the validator.
+    env::set_var("RANGED_WORKERS", "3");
+    let err = Config::generate().unwrap_err().to_string();
+    assert_eq!(err, "invalid value for `workers`: 3 is odd");
+
+    env::set_var("RANGED_WORKERS", "2");
+    env::set_var("RANGED_SAMPLE_RATE", "0.75");
+    let err = Config::generate().unwrap_err().to_string();
+    assert_eq!(err, "invalid value for `sample_rate`: 0.75 is not in the range ..=0.5");
+
+    env::set_var("RANGED_SAMPLE_RATE", "0.5");
+    env::set_var("RANGED_ADMIN_PORT", "8081");
+    assert_eq!(Config::generate().unwrap().admin_port, Some(8081));
+
+    env::set_var("RANGED_ADMIN_PORT", "80");
+    let err = Config::generate().unwrap_err().to_string();
+    assert_eq!(err, "invalid value for `admin_port`: 80 is not in the range 1024..");
+
+    env::remove_var("RANGED_PORT");
+    env::remove_var("RANGED_WORKERS");
+    env::remove_var("RANGED_SAMPLE_RATE");
+    env::remove_var("RANGED_ADMIN_PORT");
+    let err = Config::generate_from(Config { port: 0, ..Config::default() }).unwrap_err().to_string();
+    assert_eq!(err, "invalid value for `port`: 0 is not in the range 1..=65535");
+}
+
+#[test]
+fn ranges_are_documented() {
+    assert!(Config::environment_docs().contains("- **RANGED_PORT** (u16)\n  - Range: `1..=65535`\n"));
+}
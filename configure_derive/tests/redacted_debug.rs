@@ -0,0 +1,33 @@
+extern crate serde;
+
+extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Default)]
+#[configure(name = "redacted", redacted_debug)]
+#[serde(default)]
+pub struct Config {
+    user: String,
+    #[configure(secret)]
+    password: String,
+    hosts: Vec<String>,
+    r#type: String,
+}
+
+#[test]
+fn secret_fields_are_redacted() {
+    let config = Config {
+        user: String::from("admin"),
+        password: String::from("hunter2"),
+        hosts: vec![String::from("localhost")],
+        r#type: String::from("web"),
+    };
+
+    assert_eq!(format!("{:?}", config),
+               "Config { user: \"admin\", password: <redacted>, hosts: [\"localhost\"], type: \"web\" }");
+    let pretty = format!("{:#?}", config);
+    assert!(pretty.contains("password: <redacted>,"));
+    assert!(!pretty.contains("hunter2"));
+}
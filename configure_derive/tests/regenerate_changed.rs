@@ -0,0 +1,54 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "changed")]
+#[serde(default)]
+pub struct Config {
+    threads: usize,
+    host: String,
+    hosts: Vec<String>,
+    r#type: String,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            threads: 1,
+            host: String::from("localhost"),
+            hosts: vec![],
+            r#type: String::from("web"),
+        }
+    }
+}
+
+#[test]
+fn regenerate_reports_changed_fields() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    env::set_var("CHANGED_HOST", "example.com");
+    use_default_config!();
+
+    let mut cfg = Config::generate().unwrap();
+    assert_eq!(cfg.regenerate_changed().unwrap(), Vec::<&str>::new());
+
+    env::set_var("CHANGED_THREADS", "4");
+    assert_eq!(cfg.regenerate_changed().unwrap(), vec!["threads"]);
+    assert_eq!(cfg.threads, 4);
+
+    env::remove_var("CHANGED_HOST");
+    env::set_var("CHANGED_HOSTS", "a,b");
+    env::set_var("CHANGED_TYPE", "worker");
+    assert_eq!(cfg.regenerate_changed().unwrap(), vec!["host", "hosts", "type"]);
+    assert_eq!(cfg, Config {
+        threads: 4,
+        host: String::from("localhost"),
+        hosts: vec![String::from("a"), String::from("b")],
+        r#type: String::from("worker"),
+    });
+}
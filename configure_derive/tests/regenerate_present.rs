@@ -0,0 +1,60 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Clone, Debug, PartialEq)]
+#[configure(name = "present")]
+#[serde(default)]
+pub struct Config {
+    threads: usize,
+    host: String,
+    #[configure(percent)]
+    sample_rate: f64,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            threads: 1,
+            host: String::from("localhost"),
+            sample_rate: 1.0,
+        }
+    }
+}
+
+#[test]
+fn regenerate_present_keeps_unset_fields() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    env::set_var("PRESENT_THREADS", "4");
+    use_default_config!();
+
+    let mut config = Config::generate().unwrap();
+    config.host = String::from("example.com");
+    config.sample_rate = 0.5;
+    env::set_var("PRESENT_THREADS", "8");
+    config.regenerate_present().unwrap();
+    assert_eq!(config, Config {
+        threads: 8,
+        host: String::from("example.com"),
+        sample_rate: 0.5,
+    });
+
+    env::set_var("PRESENT_SAMPLE_RATE", "25%");
+    config.regenerate_present().unwrap();
+    assert_eq!(config.sample_rate, 0.25);
+    assert_eq!(config.host, "example.com");
+
+    env::set_var("PRESENT_THREADS", "many");
+    assert!(config.regenerate_present().is_err());
+    assert_eq!(config.threads, 8);
+
+    config.regenerate().unwrap_err();
+    env::remove_var("PRESENT_THREADS");
+    config.regenerate().unwrap();
+    assert_eq!(config.host, "localhost");
+}
@@ -0,0 +1,38 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+use std::path::PathBuf;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "renamed")]
+#[serde(default)]
+pub struct Config {
+    #[configure(rename = "max_conns")]
+    max_connections: u32,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config { max_connections: 10 }
+    }
+}
+
+#[test]
+fn rename_changes_env_and_toml_keys() {
+    let dir: PathBuf = env::var_os("CARGO_MANIFEST_DIR").unwrap().into();
+    env::set_var("CARGO_MANIFEST_DIR", dir.join("tests/fixtures/rename"));
+    use_default_config!();
+
+    assert_eq!(Config::generate().unwrap(), Config { max_connections: 50 });
+
+    env::set_var("RENAMED_MAX_CONNECTIONS", "7");
+    assert_eq!(Config::generate().unwrap(), Config { max_connections: 50 });
+
+    env::set_var("RENAMED_MAX_CONNS", "20");
+    assert_eq!(Config::generate().unwrap(), Config { max_connections: 20 });
+}
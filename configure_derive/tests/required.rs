@@ -0,0 +1,80 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+use std::path::PathBuf;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Debug, Default, PartialEq)]
+#[configure(name = "required_one")]
+#[serde(default)]
+pub struct One {
+    threads: usize,
+    #[configure(required)]
+    database_url: String,
+}
+
+#[derive(Configure, Deserialize, Debug, Default, PartialEq)]
+#[configure(name = "required_two")]
+#[serde(default)]
+pub struct Two {
+    #[configure(required)]
+    database_url: String,
+    #[configure(required, env = "REQUIRED_TWO_SECRET_KEY")]
+    key: String,
+}
+
+#[derive(Configure, Deserialize, Debug, Default, PartialEq)]
+#[configure(name = "required_toml")]
+#[serde(default)]
+pub struct Toml {
+    #[configure(required)]
+    database_url: String,
+}
+
+fn use_fixture() {
+    let dir: PathBuf = env::var_os("CARGO_MANIFEST_DIR").unwrap().into();
+    env::set_var("CARGO_MANIFEST_DIR", dir.join("tests/fixtures/required"));
+    use_default_config!();
+}
+
+#[test]
+fn one_missing_required_field() {
+    use_fixture();
+    env::set_var("REQUIRED_ONE_THREADS", "4");
+
+    assert_eq!(One::generate().unwrap_err().to_string(),
+               "missing required configuration: \
+                set REQUIRED_ONE_DATABASE_URL or [package.metadata.required_one].database_url");
+}
+
+#[test]
+fn two_missing_required_fields() {
+    use_fixture();
+
+    assert_eq!(Two::generate().unwrap_err().to_string(),
+               "missing required configuration: \
+                set REQUIRED_TWO_DATABASE_URL or [package.metadata.required_two].database_url; \
+                set REQUIRED_TWO_SECRET_KEY or [package.metadata.required_two].key");
+
+    env::set_var("REQUIRED_TWO_DATABASE_URL", "postgres://localhost");
+    assert_eq!(Two::generate().unwrap_err().to_string(),
+               "missing required configuration: \
+                set REQUIRED_TWO_SECRET_KEY or [package.metadata.required_two].key");
+
+    env::set_var("REQUIRED_TWO_SECRET_KEY", "hunter2");
+    assert_eq!(Two::generate().unwrap(), Two {
+        database_url: String::from("postgres://localhost"),
+        key: String::from("hunter2"),
+    });
+}
+
+#[test]
+fn required_field_provided_by_toml() {
+    use_fixture();
+
+    assert_eq!(Toml::generate().unwrap(), Toml { database_url: String::from("postgres://localhost") });
+}
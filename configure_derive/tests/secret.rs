@@ -0,0 +1,31 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Serialize, Debug, PartialEq)]
+#[configure(name = "secretive")]
+pub struct Config {
+    user: String,
+    #[configure(secret)]
+    password: String,
+}
+
+#[test]
+fn secret_values_are_redacted() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    env::set_var("SECRETIVE_USER", "admin");
+    env::set_var("SECRETIVE_PASSWORD", "hunter2");
+    use_default_config!();
+
+    let cfg = Config::generate().unwrap();
+    assert_eq!(cfg.password, "hunter2");
+    assert_eq!(cfg.to_env_lines().unwrap(), vec![
+        "SECRETIVE_USER=admin",
+        "SECRETIVE_PASSWORD=<redacted>",
+    ]);
+}
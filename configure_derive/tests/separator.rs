@@ -0,0 +1,63 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "double_sep", separator = "__")]
+pub struct Double {
+    #[configure(flatten)]
+    db: Db,
+    db_pool_size: u32,
+}
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "single_sep", separator = "_")]
+pub struct Single {
+    #[configure(flatten)]
+    db: Db,
+    db_pool_size: u32,
+}
+
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct Db {
+    url: String,
+    pool_size: u32,
+}
+
+fn use_config() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    use_default_config!();
+}
+
+#[test]
+fn double_underscore_separator() {
+    use_config();
+    env::set_var("DOUBLE_SEP_DB__URL", "postgres://localhost");
+    env::set_var("DOUBLE_SEP_DB__POOL_SIZE", "4");
+    env::set_var("DOUBLE_SEP_DB_POOL_SIZE", "16");
+
+    assert_eq!(Double::generate().unwrap(), Double {
+        db: Db { url: String::from("postgres://localhost"), pool_size: 4 },
+        db_pool_size: 16,
+    });
+}
+
+#[test]
+fn single_underscore_separator_prefers_longest_field_name() {
+    use_config();
+    env::set_var("SINGLE_SEP_DB_URL", "postgres://localhost");
+    // Both `db_pool_size` and the `pool_size` of `db` would be read from
+    // this; the longer field name, `db_pool_size`, wins.
+    env::set_var("SINGLE_SEP_DB_POOL_SIZE", "16");
+
+    assert_eq!(Single::generate().unwrap(), Single {
+        db: Db { url: String::from("postgres://localhost"), pool_size: 0 },
+        db_pool_size: 16,
+    });
+}
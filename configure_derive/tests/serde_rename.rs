@@ -0,0 +1,39 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Debug, Default, PartialEq)]
+#[configure(name = "serde_renamed", generate_docs)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Config {
+    max_threads: usize,
+    #[serde(rename = "logLevel")]
+    level: String,
+    #[configure(percent)]
+    sample_rate: f64,
+}
+
+#[test]
+fn documented_vars_are_read() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    use_default_config!();
+
+    assert_eq!(Config::ENV_VARS, ["SERDE_RENAMED_MAX_THREADS", "SERDE_RENAMED_LOG_LEVEL", "SERDE_RENAMED_SAMPLE_RATE"]);
+    for var in Config::ENV_VARS {
+        assert!(Config::environment_docs().contains(var), "{} is not documented", var);
+    }
+
+    env::set_var("SERDE_RENAMED_MAX_THREADS", "4");
+    env::set_var("SERDE_RENAMED_LOG_LEVEL", "debug");
+    env::set_var("SERDE_RENAMED_SAMPLE_RATE", "25%");
+    assert_eq!(Config::generate().unwrap(), Config {
+        max_threads: 4,
+        level: String::from("debug"),
+        sample_rate: 0.25,
+    });
+}
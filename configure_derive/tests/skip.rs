@@ -0,0 +1,40 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "myapp")]
+#[serde(default)]
+pub struct Config {
+    threads: usize,
+    #[configure(skip)]
+    secret_internal: String,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            threads: 1,
+            secret_internal: String::from("built in"),
+        }
+    }
+}
+
+#[test]
+fn skipped_fields_are_never_read() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    env::set_var("MYAPP_THREADS", "4");
+    env::set_var("MYAPP_SECRET_INTERNAL", "overridden");
+    use_default_config!();
+
+    assert_eq!(Config::generate().unwrap(), Config {
+        threads: 4,
+        secret_internal: String::from("built in"),
+    });
+    assert_eq!(Config::generate_from(Config::default()).unwrap().secret_internal, "built in");
+}
@@ -0,0 +1,48 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Debug, Default, PartialEq)]
+#[configure(name = "scheduled")]
+#[serde(default)]
+pub struct Config {
+    #[configure(time_of_day)]
+    run_at: u32,
+    #[configure(time_of_day)]
+    backup_at: Option<u64>,
+}
+
+#[test]
+fn time_of_day_fields() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    env::set_var("SCHEDULED_RUN_AT", "14:30");
+    env::set_var("SCHEDULED_BACKUP_AT", "00:00:30");
+    use_default_config!();
+
+    assert_eq!(Config::generate().unwrap(), Config {
+        run_at: 14 * 3600 + 30 * 60,
+        backup_at: Some(30),
+    });
+
+    // The last second of the day is the largest time accepted.
+    env::set_var("SCHEDULED_BACKUP_AT", "23:59:59");
+    assert_eq!(Config::generate().unwrap().backup_at, Some(86_399));
+
+    let run_at_error = |value: &str| {
+        env::set_var("SCHEDULED_RUN_AT", value);
+        Config::generate().unwrap_err().to_string()
+    };
+    assert_eq!(run_at_error("24:00"),
+               "invalid value for `run_at` (`SCHEDULED_RUN_AT`): `24` is out of range in time of day `24:00`");
+    assert_eq!(run_at_error("25:00"),
+               "invalid value for `run_at` (`SCHEDULED_RUN_AT`): `25` is out of range in time of day `25:00`");
+    assert_eq!(run_at_error("14"),
+               "invalid value for `run_at` (`SCHEDULED_RUN_AT`): `14` is not a time of day (`HH:MM` or `HH:MM:SS`)");
+    assert_eq!(run_at_error("-1:00"),
+               "invalid value for `run_at` (`SCHEDULED_RUN_AT`): `-1:00` is not a time of day (`HH:MM` or `HH:MM:SS`)");
+}
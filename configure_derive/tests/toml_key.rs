@@ -0,0 +1,34 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+use std::path::PathBuf;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Debug, Default, PartialEq)]
+#[configure(name = "svc")]
+#[serde(default)]
+pub struct Config {
+    #[configure(toml_key = "max-rps")]
+    max_requests_per_second: u32,
+    #[configure(toml_key = "burst-size")]
+    burst: u32,
+}
+
+#[test]
+fn toml_key_replaces_field_name_in_toml() {
+    let dir: PathBuf = env::var_os("CARGO_MANIFEST_DIR").unwrap().into();
+    env::set_var("CARGO_MANIFEST_DIR", dir.join("tests/fixtures/toml_key"));
+    use_default_config!();
+
+    // `max-rps` is read, and `max_requests_per_second` is not; `burst` is
+    // only set under the field name, so it is not read at all.
+    assert_eq!(Config::generate().unwrap(), Config { max_requests_per_second: 100, burst: 0 });
+
+    // The environment variable is still named after the field.
+    env::set_var("SVC_MAX_REQUESTS_PER_SECOND", "200");
+    assert_eq!(Config::generate().unwrap(), Config { max_requests_per_second: 200, burst: 0 });
+}
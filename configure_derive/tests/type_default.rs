@@ -0,0 +1,85 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "type_defaulted")]
+#[serde(default)]
+pub struct Config {
+    threads: usize,
+    #[configure(type_default)]
+    retries: usize,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            threads: 4,
+            retries: 3,
+        }
+    }
+}
+
+#[test]
+fn type_default_ignores_struct_default() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    env::remove_var("TYPE_DEFAULTED_THREADS");
+    env::remove_var("TYPE_DEFAULTED_RETRIES");
+    use_default_config!();
+
+    // `threads` falls back to the struct default, `retries` to `usize::default()`.
+    assert_eq!(Config::generate().unwrap(), Config { threads: 4, retries: 0 });
+
+    env::set_var("TYPE_DEFAULTED_RETRIES", "5");
+    assert_eq!(Config::generate().unwrap(), Config { threads: 4, retries: 5 });
+}
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "type_defaulted_renamed")]
+#[serde(default)]
+pub struct Renamed {
+    #[serde(rename = "worker_count")]
+    #[configure(type_default)]
+    workers: u32,
+}
+
+impl Default for Renamed {
+    fn default() -> Renamed {
+        Renamed { workers: 8 }
+    }
+}
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "type_defaulted_camel")]
+#[serde(default, rename_all = "camelCase")]
+pub struct Camel {
+    #[configure(type_default)]
+    max_retries: u32,
+}
+
+impl Default for Camel {
+    fn default() -> Camel {
+        Camel { max_retries: 8 }
+    }
+}
+
+#[test]
+fn type_default_follows_serde_renames() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    use_default_config!();
+
+    env::remove_var("TYPE_DEFAULTED_RENAMED_WORKER_COUNT");
+    assert_eq!(Renamed::generate().unwrap(), Renamed { workers: 0 });
+    env::set_var("TYPE_DEFAULTED_RENAMED_WORKER_COUNT", "5");
+    assert_eq!(Renamed::generate().unwrap(), Renamed { workers: 5 });
+
+    env::remove_var("TYPE_DEFAULTED_CAMEL_MAX_RETRIES");
+    assert_eq!(Camel::generate().unwrap(), Camel { max_retries: 0 });
+    env::set_var("TYPE_DEFAULTED_CAMEL_MAX_RETRIES", "2");
+    assert_eq!(Camel::generate().unwrap(), Camel { max_retries: 2 });
+}
@@ -0,0 +1,16 @@
+extern crate serde;
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+mod config {
+    #[derive(Configure, Deserialize)]
+    #[configure(name = "example", builder)]
+    pub struct Config {
+        pub threads: usize,
+        token: String,
+    }
+}
+
+fn main() {
+    let _ = config::Config::builder().threads(4usize).token("secret");
+}
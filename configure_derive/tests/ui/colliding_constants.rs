@@ -0,0 +1,11 @@
+#[macro_use] extern crate configure;
+
+#[derive(Configure)]
+#[configure(name = "example", generate_docs)]
+#[allow(non_snake_case)]
+struct Config {
+    max_conns: u32,
+    maxConns: u32,
+}
+
+fn main() { }
@@ -0,0 +1,10 @@
+#[macro_use] extern crate configure;
+
+#[derive(Configure)]
+#[configure(name = "example")]
+struct Config {
+    #[configure(default = "4")]
+    threads: usize,
+}
+
+fn main() { }
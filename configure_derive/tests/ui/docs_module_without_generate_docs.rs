@@ -0,0 +1,9 @@
+#[macro_use] extern crate configure;
+
+#[derive(Configure)]
+#[configure(name = "example", docs_module = "server_variables")]
+struct Config {
+    threads: usize,
+}
+
+fn main() { }
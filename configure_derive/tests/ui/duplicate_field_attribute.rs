@@ -0,0 +1,11 @@
+#[macro_use] extern crate configure;
+
+#[derive(Configure)]
+#[configure(name = "example")]
+struct Config {
+    #[configure(env = "PORT")]
+    #[configure(env = "HTTP_PORT")]
+    port: u16,
+}
+
+fn main() { }
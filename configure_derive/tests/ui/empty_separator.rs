@@ -0,0 +1,9 @@
+#[macro_use] extern crate configure;
+
+#[derive(Configure)]
+#[configure(separator = "")]
+struct Config {
+    threads: usize,
+}
+
+fn main() { }
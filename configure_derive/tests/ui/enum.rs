@@ -0,0 +1,10 @@
+#[macro_use] extern crate configure;
+
+#[derive(Configure)]
+#[configure(name = "example")]
+enum Config {
+    Small,
+    Large(usize, usize),
+}
+
+fn main() { }
@@ -0,0 +1,9 @@
+#[macro_use] extern crate configure;
+
+#[derive(Configure)]
+struct Config {
+    #[configure(env_only, toml_only)]
+    password: String,
+}
+
+fn main() { }
@@ -0,0 +1,10 @@
+#[macro_use] extern crate configure;
+
+#[derive(Configure)]
+#[configure(name = "example", derive_default)]
+struct Config {
+    #[configure(default = "1 +")]
+    threads: usize,
+}
+
+fn main() { }
@@ -0,0 +1,9 @@
+#[macro_use] extern crate configure;
+
+#[derive(Configure)]
+#[configure(name = "example", generate_docs, docs_module = "server variables")]
+struct Config {
+    threads: usize,
+}
+
+fn main() { }
@@ -0,0 +1,13 @@
+#[macro_use] extern crate configure;
+
+fn parse_threads(value: &str) -> Result<usize, String> {
+    value.parse().map_err(|_| String::from("not a number"))
+}
+
+#[derive(Configure)]
+struct Config {
+    #[configure(from_str, parse_with = "parse_threads")]
+    threads: usize,
+}
+
+fn main() { }
@@ -0,0 +1,11 @@
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+#[derive(Configure, Deserialize)]
+#[configure(name = "example")]
+struct Config {
+    #[configure(percent)]
+    share: Option<u32>,
+}
+
+fn main() { }
@@ -0,0 +1,11 @@
+#[macro_use] extern crate configure;
+
+#[derive(Configure)]
+#[configure(redacted_debug)]
+#[derive(Clone, Debug)]
+struct Config {
+    #[configure(secret)]
+    password: String,
+}
+
+fn main() { }
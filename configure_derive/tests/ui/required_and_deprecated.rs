@@ -0,0 +1,9 @@
+#[macro_use] extern crate configure;
+
+#[derive(Configure)]
+struct Config {
+    #[configure(required, deprecated = "use CONFIG_PORT instead")]
+    old_port: u16,
+}
+
+fn main() { }
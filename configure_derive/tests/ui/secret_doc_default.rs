@@ -0,0 +1,9 @@
+#[macro_use] extern crate configure;
+
+#[derive(Configure)]
+struct Config {
+    #[configure(secret, doc_default = "hunter2")]
+    password: String,
+}
+
+fn main() { }
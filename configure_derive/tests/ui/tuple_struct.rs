@@ -0,0 +1,7 @@
+#[macro_use] extern crate configure;
+
+#[derive(Configure)]
+#[configure(name = "example")]
+struct Config(usize, usize);
+
+fn main() { }
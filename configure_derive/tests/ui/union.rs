@@ -0,0 +1,10 @@
+#[macro_use] extern crate configure;
+
+#[derive(Configure)]
+#[configure(name = "example")]
+union Config {
+    threads: u32,
+    timeout: u64,
+}
+
+fn main() { }
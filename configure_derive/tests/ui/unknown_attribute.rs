@@ -0,0 +1,9 @@
+#[macro_use] extern crate configure;
+
+#[derive(Configure)]
+#[configure(nmae = "example")]
+struct Config {
+    threads: usize,
+}
+
+fn main() { }
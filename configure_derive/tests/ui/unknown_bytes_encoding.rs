@@ -0,0 +1,9 @@
+#[macro_use] extern crate configure;
+
+#[derive(Configure)]
+struct Config {
+    #[configure(bytes = "base32")]
+    key: Vec<u8>,
+}
+
+fn main() { }
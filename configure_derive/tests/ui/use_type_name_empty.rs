@@ -0,0 +1,10 @@
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+#[derive(Configure, Deserialize)]
+#[configure(name = "example", use_type_name)]
+struct Config {
+    threads: usize,
+}
+
+fn main() { }
@@ -0,0 +1,40 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+use std::marker::PhantomData;
+
+use configure::Configure;
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "unit")]
+pub struct Config;
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "phantom")]
+pub struct Marker {
+    #[serde(default)]
+    marker: PhantomData<u32>,
+}
+
+#[test]
+fn unit_struct_generates() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    env::set_var("UNIT", "value");
+    env::set_var("UNIT_FIELD", "value");
+    use_default_config!();
+
+    assert_eq!(Config::generate().unwrap(), Config);
+    assert_eq!(Config::ENV_VARS, &[] as &[&str]);
+}
+
+#[test]
+fn phantom_data_generates() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    env::set_var("PHANTOM_MARKER", "value");
+    use_default_config!();
+
+    assert_eq!(Marker::generate().unwrap(), Marker { marker: PhantomData });
+}
@@ -0,0 +1,47 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+
+use configure::Configure;
+
+/// The server.
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "multi", use_type_name, generate_docs)]
+pub struct ServerConfig {
+    /// The port to listen on.
+    port: u16,
+}
+
+/// The metrics exporter.
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "multi", use_type_name, generate_docs)]
+pub struct MetricsCfg {
+    /// The port to export metrics on.
+    port: u16,
+}
+
+#[test]
+fn each_struct_has_its_own_variables() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    use_default_config!();
+    env::remove_var("MULTI_PORT");
+    env::set_var("MULTI_SERVER_PORT", "8080");
+    env::set_var("MULTI_METRICS_PORT", "9090");
+
+    assert_eq!(ServerConfig::generate().unwrap(), ServerConfig { port: 8080 });
+    assert_eq!(MetricsCfg::generate().unwrap(), MetricsCfg { port: 9090 });
+
+    assert_eq!(ServerConfig::ENV_VARS, &["MULTI_SERVER_PORT"]);
+    assert_eq!(MetricsCfg::ENV_VARS, &["MULTI_METRICS_PORT"]);
+}
+
+#[test]
+fn each_struct_has_its_own_docs_module() {
+    assert_eq!(server_environment_variables::PORT, "MULTI_SERVER_PORT");
+    assert_eq!(metrics_environment_variables::PORT, "MULTI_METRICS_PORT");
+    assert!(ServerConfig::environment_docs().contains("**MULTI_SERVER_PORT** (u16)"));
+    assert!(MetricsCfg::environment_docs().contains("**MULTI_METRICS_PORT** (u16)"));
+}
@@ -0,0 +1,73 @@
+extern crate serde;
+
+#[macro_use] extern crate configure;
+#[macro_use] extern crate serde_derive;
+
+use std::env;
+use std::path::PathBuf;
+
+use configure::Configure;
+
+fn unprivileged(port: &u16) -> Result<(), String> {
+    if *port > 1024 {
+        Ok(())
+    } else {
+        Err(format!("port must be >1024, not {}", port))
+    }
+}
+
+fn tls_pair(cfg: &Config) -> Result<(), String> {
+    match (&cfg.tls_cert, &cfg.tls_key) {
+        (&Some(_), &None) | (&None, &Some(_))   => {
+            Err(String::from("tls_cert and tls_key must both be set or neither"))
+        }
+        _                                       => Ok(()),
+    }
+}
+
+#[derive(Configure, Deserialize, Debug, PartialEq)]
+#[configure(name = "validated", validate = "tls_pair")]
+#[serde(default)]
+pub struct Config {
+    #[configure(validate = "unprivileged")]
+    port: u16,
+    tls_cert: Option<PathBuf>,
+    tls_key: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            port: 7878,
+            tls_cert: None,
+            tls_key: None,
+        }
+    }
+}
+
+#[test]
+fn validators() {
+    env::remove_var("CARGO_MANIFEST_DIR");
+    use_default_config!();
+
+    env::set_var("VALIDATED_PORT", "8080");
+    env::set_var("VALIDATED_TLS_CERT", "cert.pem");
+    env::set_var("VALIDATED_TLS_KEY", "key.pem");
+    assert_eq!(Config::generate().unwrap(), Config {
+        port: 8080,
+        tls_cert: Some(PathBuf::from("cert.pem")),
+        tls_key: Some(PathBuf::from("key.pem")),
+    });
+
+    env::set_var("VALIDATED_PORT", "80");
+    let err = Config::generate().unwrap_err().to_string();
+    assert_eq!(err, "invalid value for `port`: port must be >1024, not 80");
+
+    env::set_var("VALIDATED_PORT", "8080");
+    env::remove_var("VALIDATED_TLS_KEY");
+    let err = Config::generate().unwrap_err().to_string();
+    assert_eq!(err, "tls_cert and tls_key must both be set or neither");
+
+    let err = Config::generate_from(Config::default()).unwrap_err().to_string();
+    assert_eq!(err, "tls_cert and tls_key must both be set or neither");
+}